@@ -1,13 +1,19 @@
 use pace26stride::commands::{
-    arguments::{Arguments, parse_prog_arguments},
+    arguments::{ArgsError, Arguments, parse_prog_arguments},
     check::{CommandCheckError, command_check},
     profile::{CommandProfileError, command_profile},
     run::{CommandRunError, command_run},
+    selftest::{CommandSelftestError, command_selftest},
+    upload_daemon::{CommandUploadDaemonError, command_upload_daemon},
+    upload_summary::{CommandUploadSummaryError, command_upload_summary},
 };
 
 use thiserror::Error;
 use tracing::error;
 
+/// Exit code for invalid arguments (as used by clap for usage errors)
+const ARGS_ERROR_EXIT_CODE: i32 = 2;
+
 #[derive(Debug, Error)]
 enum MainError {
     #[error(transparent)]
@@ -18,6 +24,24 @@ enum MainError {
 
     #[error(transparent)]
     Profile(#[from] CommandProfileError),
+
+    #[error(transparent)]
+    UploadSummary(#[from] CommandUploadSummaryError),
+
+    #[error(transparent)]
+    Selftest(#[from] CommandSelftestError),
+
+    #[error(transparent)]
+    UploadDaemon(#[from] CommandUploadDaemonError),
+}
+
+impl MainError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            MainError::Run(e) => e.exit_code(),
+            _ => 1,
+        }
+    }
 }
 
 async fn dispatch_command(args: &Arguments) -> Result<(), MainError> {
@@ -25,6 +49,9 @@ async fn dispatch_command(args: &Arguments) -> Result<(), MainError> {
         Arguments::Check(args) => command_check(args).await?,
         Arguments::Run(args) => command_run(args).await?,
         Arguments::Profile(args) => command_profile(args).await?,
+        Arguments::UploadSummary(args) => command_upload_summary(args).await?,
+        Arguments::Selftest(args) => command_selftest(args).await?,
+        Arguments::UploadDaemon(args) => command_upload_daemon(args).await?,
     }
     Ok(())
 }
@@ -32,11 +59,19 @@ async fn dispatch_command(args: &Arguments) -> Result<(), MainError> {
 #[tokio::main]
 async fn main() {
     let _ = dotenvy::dotenv();
-    let args = parse_prog_arguments();
+    let args = match parse_prog_arguments() {
+        Ok(args) => args,
+        // prints the help and version messages as well as usage errors
+        Err(ArgsError::Clap(e)) => e.exit(),
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(ARGS_ERROR_EXIT_CODE)
+        }
+    };
 
     let res = dispatch_command(&args).await;
     if let Err(e) = res {
         error!("{e}");
-        std::process::exit(1)
+        std::process::exit(e.exit_code())
     }
 }
@@ -16,6 +16,7 @@ use std::{
 };
 
 const PARAM_LINE_PREFIX: &str = "#s test_params ";
+const DEFAULT_SOLUTION_EXT: &str = "out";
 
 #[derive(Parser, Deserialize)]
 struct Opts {
@@ -50,6 +51,17 @@ struct Opts {
     #[serde(default)]
     extra_alloc: Option<usize>,
 
+    #[arg(
+        long,
+        help = "Spawn a helper process sleeping this many seconds; its pid is reported as s_helper_pid"
+    )]
+    #[serde(default)]
+    spawn_sleeper: Option<f64>,
+
+    #[arg(long, help = "Send this signal to itself instead of exiting")]
+    #[serde(default)]
+    kill_self: Option<i32>,
+
     #[arg(short, long, help = "Ignore SIGTERM signal")]
     #[serde(default)]
     ignore_sigterm: bool,
@@ -69,6 +81,18 @@ struct Opts {
     #[arg(short = 'E', long, help = "Report environment variable")]
     #[serde(default)]
     report_environment: bool,
+
+    #[arg(
+        long,
+        help = "Extension of the solution file printed by default",
+        default_value = DEFAULT_SOLUTION_EXT
+    )]
+    #[serde(default = "default_solution_ext")]
+    solution_ext: String,
+}
+
+fn default_solution_ext() -> String {
+    DEFAULT_SOLUTION_EXT.into()
 }
 
 fn parse_opts_from_stdin() -> Option<Opts> {
@@ -108,6 +132,14 @@ fn main() {
         .unwrap();
     }
 
+    if let Some(secs) = opts.spawn_sleeper {
+        let helper = std::process::Command::new("sleep")
+            .arg(secs.to_string())
+            .spawn()
+            .unwrap();
+        println!("#s s_helper_pid {}", helper.id());
+    }
+
     if opts.wait_seconds > 0.0 {
         let start = Instant::now();
         while start.elapsed().as_secs_f64() < opts.wait_seconds {
@@ -141,8 +173,8 @@ fn main() {
 
     if let Some(msg) = opts.print.as_ref() {
         println!("{msg}");
-    } else if let Ok(solution_path) =
-        std::env::var("STRIDE_INSTANCE_PATH").map(|p| PathBuf::from(p).with_extension("out"))
+    } else if let Ok(solution_path) = std::env::var("STRIDE_INSTANCE_PATH")
+        .map(|p| PathBuf::from(p).with_extension(&opts.solution_ext))
     {
         println!(
             "#s s_demo_path \"{}\"",
@@ -155,8 +187,24 @@ fn main() {
     }
 
     if let Some(size) = opts.extra_alloc {
-        let mut vec: Vec<u8> = black_box(vec![0u8; size]);
-        vec.fill(1); // acutally access the memory
+        // grow gradually (like most solvers), so that a memory limit is hit with most of the
+        // memory in use
+        const CHUNK: usize = 16 << 20;
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
+        for start in (0..size).step_by(CHUNK) {
+            let mut chunk: Vec<u8> = black_box(vec![0u8; CHUNK.min(size - start)]);
+            chunk.fill(1); // acutally access the memory
+            chunks.push(chunk);
+        }
+        black_box(chunks);
+    }
+
+    if let Some(signal) = opts.kill_self {
+        use std::io::Write;
+        std::io::stdout().flush().unwrap();
+        unsafe {
+            libc::kill(libc::getpid(), signal);
+        }
     }
 
     std::process::exit(opts.exit_code);
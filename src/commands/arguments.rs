@@ -1,9 +1,14 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum, parser::ValueSource};
+use serde::Serialize;
 use std::{path::PathBuf, time::Duration};
-use tracing::error;
+use thiserror::Error;
 use url::Url;
 
+use crate::commands::run::instances::DEFAULT_INSTANCE_EXTENSIONS;
+use crate::commands::run::solver_profiles::{SolverProfile, SolverProfileError};
+
 pub const ENV_SOLVER: &str = "STRIDE_SOLVER";
+pub const ENV_SOLVER_ARGS: &str = "STRIDE_SOLVER_ARGS";
 pub const ENV_SOFT_TIMEOUT: &str = "STRIDE_TIMEOUT";
 pub const ENV_GRACE_PERIOD: &str = "STRIDE_GRACE";
 pub const ENV_PARALLEL_JOBS: &str = "STRIDE_PARALLEL";
@@ -13,20 +18,58 @@ pub const ENV_STRIDE_MAX_RUN_LOGS: &str = "STRIDE_MAX_RUN_LOGS";
 pub const ENV_STRIDE_SERVER: &str = "STRIDE_SERVER";
 pub const STRIDE_SERVER_DEFAULT: &str = "https://pace2026.imada.sdu.dk/";
 
+/// Shell executing the command given by `--shell-command`
+const SHELL_PATH: &str = "/bin/sh";
+
 #[derive(Parser, Debug)]
 pub enum Arguments {
     #[command(alias = "c", visible_alias = "verify", about = "Check a solution file")]
     Check(CommandCheckArgs),
 
     #[command(alias = "r", about = "Run solver and postprocess solution")]
-    Run(CommandRunArgs),
+    Run(Box<CommandRunArgs>),
 
     #[command(alias = "p", hide = true)]
     Profile(CommandProfileArgs),
+
+    #[command(
+        alias = "u",
+        about = "Upload results recorded in a run summary without re-running the solver"
+    )]
+    UploadSummary(CommandUploadSummaryArgs),
+
+    #[command(about = "Run the bundled test solver to check that the whole pipeline works")]
+    Selftest(CommandSelftestArgs),
+
+    #[command(
+        about = "Batch the uploads of several concurrent `stride run --upload-daemon` invocations"
+    )]
+    UploadDaemon(CommandUploadDaemonArgs),
 }
 
 #[derive(Parser, Debug, Default)]
 pub struct CommandProfileArgs {
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Limit the number of open file descriptors of the solver (RLIMIT_NOFILE)"
+    )]
+    pub max_fds: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Limit the number of processes of the solver's user (RLIMIT_NPROC)"
+    )]
+    pub max_procs: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Limit the address space of the solver (RLIMIT_AS)"
+    )]
+    pub memory_limit: Option<u64>,
+
     #[arg(help = "Solver program to execute")]
     pub solver: PathBuf,
 
@@ -36,12 +79,29 @@ pub struct CommandProfileArgs {
 
 #[derive(Parser, Debug)]
 pub struct CommandCheckArgs {
-    #[arg(help = "Path to instance file")]
+    #[arg(
+        help = "Path to instance file; if it is a directory, all *.in files therein are checked against their solutions"
+    )]
     pub instance: PathBuf,
 
     #[arg(help = "Path to solution file; if omitted, only instance is checked")]
     pub solution: Option<PathBuf>,
 
+    #[arg(
+        long,
+        value_name = "EXT",
+        conflicts_with = "solution",
+        help = "Derive the solution path from the instance path by replacing its extension with EXT"
+    )]
+    pub solution_ext: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "If the instance path is a directory, also check the instances of its subdirectories"
+    )]
+    pub recursive: bool,
+
     #[arg(short, long, help = "Produce as little output as possible")]
     pub quiet: bool,
 
@@ -63,22 +123,308 @@ pub struct CommandCheckArgs {
 
     #[arg(short = 'u', long, help = "Upload solution of stride instances")]
     pub upload: bool,
+
+    #[arg(
+        long,
+        help = "Recompute the instance's digest and fail if it differs from its `#s idigest` line"
+    )]
+    pub verify_idigest: bool,
+
+    #[arg(
+        long,
+        help = "If a solution tree cannot be matched, print it and the offending instance tree"
+    )]
+    pub explain: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct CommandSelftestArgs {
+    #[arg(
+        long,
+        help = "Path to the test_solver binary; default: next to the stride executable"
+    )]
+    pub test_solver: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct CommandUploadSummaryArgs {
+    #[arg(help = "Path to summary.json of a previous run")]
+    pub summary: PathBuf,
+
+    #[arg(short = 'S', long, env = ENV_STRIDE_SERVER, default_value = STRIDE_SERVER_DEFAULT, help = "Server to upload to")]
+    pub solution_server: Url,
+}
+
+#[derive(Parser, Debug)]
+pub struct CommandUploadDaemonArgs {
+    #[arg(help = "Path of the Unix socket to listen on")]
+    pub socket: PathBuf,
+
+    #[arg(short = 'S', long, env = ENV_STRIDE_SERVER, default_value = STRIDE_SERVER_DEFAULT, help = "Server to upload to")]
+    pub solution_server: Url,
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct CommandRunArgs {
-    #[arg(short, long, env = ENV_SOLVER, help = "Solver program to execute")]
-    pub solver: PathBuf,
+    #[arg(
+        short,
+        long,
+        env = ENV_SOLVER,
+        required_unless_present_any = ["solver_profile", "shell_command"],
+        help = "Solver program to execute"
+    )]
+    pub solver: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        conflicts_with_all = ["solver", "solver_profile", "solver_args", "no_envs"],
+        help = "UNSAFE: run `sh -c COMMAND` instead of a solver (e.g. 'preprocess | ./solver'); the instance is passed via stdin and STRIDE_INSTANCE_PATH"
+    )]
+    pub shell_command: Option<String>,
 
-    #[arg(short, long, help = "List of instance files", required = true, num_args(1..))]
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Use path, args, timeout and grace of this profile as defaults; explicit arguments take precedence"
+    )]
+    pub solver_profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "solvers.toml",
+        help = "TOML file containing the profiles selectable by --solver-profile"
+    )]
+    pub solver_profiles: PathBuf,
+
+    #[arg(
+        short,
+        long,
+        help = "List of instance files, list files, or directories (searched recursively)",
+        required = true,
+        num_args(1..)
+    )]
     pub instances: Vec<PathBuf>,
 
+    #[arg(
+        long,
+        help = "Only run instances with an idigest, i.e. those whose results can be uploaded"
+    )]
+    pub only_uploadable: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "offline",
+        help = "Only run instances that the STRIDE server reports as still open (not yet solved optimally); implies --only-uploadable"
+    )]
+    pub open_only: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "JSON file mapping idigests to proven optimal scores; used to report s_is_optimal of valid solutions"
+    )]
+    pub optimal_db: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "optimal_db",
+        help = "Do not run instances whose optimum is recorded in --optimal-db"
+    )]
+    pub skip_known_optimal: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Skip the instances named by this file (same syntax as list files, incl. globs)"
+    )]
+    pub exclude: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "REGEX",
+        value_parser = parse_regex,
+        help = "Only run instances whose name matches the regular expression (unanchored)"
+    )]
+    pub filter_name: Option<regex::Regex>,
+
+    #[arg(
+        long,
+        value_name = "REGEX",
+        value_parser = parse_regex,
+        help = "Only run instances whose path matches the regular expression (unanchored)"
+    )]
+    pub filter_path: Option<regex::Regex>,
+
+    #[arg(
+        long,
+        help = "Before running, recompute the digests of all instances with an idigest and abort on mismatches"
+    )]
+    pub verify_idigest: bool,
+
+    #[arg(
+        long,
+        help = "Derive instance names only from their paths; keeps s_name stable across runs"
+    )]
+    pub stable_names: bool,
+
+    #[arg(
+        long,
+        help = "Only run the first of several instances with the same content (digest), e.g. reached via symlinks"
+    )]
+    pub dedup_by_digest: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "path",
+        help = "Order in which the instances are dispatched; `random` is reproducible with --seed"
+    )]
+    pub order: InstanceOrder,
+
+    #[arg(
+        long,
+        value_name = "SEED",
+        help = "Seed of the shuffle with --order random; a random seed is drawn (and logged) if omitted"
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Only run the first N instances (in dispatch order, see --order); e.g. for smoke tests"
+    )]
+    pub limit: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "EXT",
+        value_delimiter = ',',
+        default_values = DEFAULT_INSTANCE_EXTENSIONS,
+        help = "Extensions of the instance files collected from directories passed to -i"
+    )]
+    pub dir_extensions: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the paths of all instances to be run (in dispatch order) into this list file"
+    )]
+    pub dump_instances: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Only resolve the instances and print an estimate of the total runtime; the solver is not executed"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        help = "At the end, list the instances that improved or regressed compared to the previous run (with the same --run-name)"
+    )]
+    pub compare_previous: bool,
+
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Run each instance this many times; the summary additionally contains an aggregated row per instance"
+    )]
+    pub repeat: usize,
+
+    #[arg(
+        long,
+        value_name = "FLAGS",
+        allow_hyphen_values = true,
+        help = "Run the solver once with FLAGS (e.g. '--version') before the instances and record its stdout as `solver_version` in report.json"
+    )]
+    pub probe_solver: Option<String>,
+
     #[arg(short='t', long="timeout", env = ENV_SOFT_TIMEOUT, value_parser = parse_duration, help = "Solver time budget in seconds (then SIGTERM)", default_value="30")]
     pub soft_timeout: Duration,
 
+    #[arg(
+        long,
+        value_name = "MS",
+        value_parser = parse_millis,
+        help = "Extend the timeout of instances to MS milliseconds per leaf (from their `#p` header) if this exceeds --timeout; recorded in s_timeout"
+    )]
+    pub timeout_per_leaf: Option<Duration>,
+
     #[arg(short='g', long="grace", env = ENV_GRACE_PERIOD, value_parser = parse_duration, help = "Seconds between SIGTERM and SIGKILL", default_value="5")]
     pub grace_period: Duration,
 
+    #[arg(
+        long,
+        value_enum,
+        ignore_case = true,
+        default_value = "TERM",
+        help = "Signal sent to the solver at the timeout; SIGKILL still follows after the grace period"
+    )]
+    pub term_signal: TermSignal,
+
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        help = "Limit the solver's CPU time in seconds (then SIGXCPU; SIGKILL after grace period)"
+    )]
+    pub cpu_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Limit the number of open file descriptors of the solver (RLIMIT_NOFILE)"
+    )]
+    pub max_fds: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Limit the number of processes of the solver's user (RLIMIT_NPROC; counts all processes of the user)"
+    )]
+    pub max_procs: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "MB",
+        help = "Limit the address space of the solver (RLIMIT_AS); crashes under the limit are reported as MemoryLimitExceeded"
+    )]
+    pub memory_limit: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Enable core dumps of the solver (running in its task folder) and report them in s_core_path"
+    )]
+    pub capture_cores: bool,
+
+    #[arg(
+        long,
+        help = "Linux only: bind each solver to the CPUs and memory of the least loaded NUMA node; recorded in s_numa_node"
+    )]
+    pub numa_bind: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "numa_bind",
+        help = "Linux only: pin each solver to a distinct CPU (reused once its solver finished); recorded in s_cpu"
+    )]
+    pub pin_cpus: bool,
+
+    #[arg(
+        long,
+        help = "Pipe instances through stride and report in s_early_output if the solver wrote to stdout before its input was completely written"
+    )]
+    pub detect_early_output: bool,
+
+    #[arg(
+        long,
+        value_name = "MB/s",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Pipe the solver's stdout through stride and terminate the solver (OutputOverflow) if it writes faster than this on average over 2s"
+    )]
+    pub max_output_rate: Option<u64>,
+
     #[arg(
         short = 'p',
         long = "parallel",
@@ -87,6 +433,45 @@ pub struct CommandRunArgs {
     )]
     pub parallel_jobs: Option<u64>,
 
+    #[arg(
+        long,
+        help = "Vary the number of parallel solvers over time between --parallel-min and --parallel-max to stress-test solvers"
+    )]
+    pub parallel_jitter: bool,
+
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "parallel_jitter",
+        help = "Minimum number of parallel solvers with --parallel-jitter"
+    )]
+    pub parallel_min: u64,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "parallel_jitter",
+        help = "Maximum number of parallel solvers with --parallel-jitter; default: --parallel"
+    )]
+    pub parallel_max: Option<u64>,
+
+    #[arg(
+        long,
+        default_value = "10",
+        value_parser = parse_duration,
+        requires = "parallel_jitter",
+        help = "Seconds between changes of the parallelism with --parallel-jitter"
+    )]
+    pub parallel_jitter_period: Duration,
+
+    #[arg(
+        long,
+        value_name = "LOAD",
+        help = "Do not start new solvers while the 1-minute load average of the machine exceeds LOAD (Linux only)"
+    )]
+    pub max_loadavg: Option<f64>,
+
     #[arg(
         short = 'o',
         long = "optimal",
@@ -95,6 +480,28 @@ pub struct CommandRunArgs {
     )]
     pub require_optimal: bool,
 
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        value_parser = parse_percent,
+        help = "Only consider solutions more than PERCENT worse than the best known score as suboptimal"
+    )]
+    pub optimal_margin: Option<f64>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "min",
+        help = "Whether smaller or larger scores are better; governs all score comparisons (best known, optimum, --compare-previous, --repeat)"
+    )]
+    pub objective: Objective,
+
+    #[arg(
+        long,
+        help = "Fail the run if a valid solution is worse than the optimum stated by its instance (`#s optimal N`)"
+    )]
+    pub check_optimal: bool,
+
     #[arg(
         short = 'k',
         long = "keep-logs",
@@ -103,6 +510,207 @@ pub struct CommandRunArgs {
     )]
     pub keep_successful_logs: bool,
 
+    #[arg(
+        long,
+        help = "Keep logs exactly of runs whose solver wrote to stderr, regardless of their result (overrides -k)"
+    )]
+    pub keep_if_stderr: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the folders of all non-valid tasks (incl. their instances), the report and the summary into a .tar.gz reproducer bundle"
+    )]
+    pub bug_bundle: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Write a GraphViz dot of each valid solution into solution.dot of its task folder (implies keeping the folder)"
+    )]
+    pub export_dot: bool,
+
+    #[arg(
+        long,
+        help = "Treat format warnings in the solver's output as errors (reported as SyntaxError)"
+    )]
+    pub strict_solution: bool,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Pipe the solver's output through this shell command (run by /bin/sh -c); its output is checked instead"
+    )]
+    pub solution_transform: Option<String>,
+
+    #[arg(
+        long,
+        help = "Report how many solution trees matched each instance tree (s_match_stats in the summary)"
+    )]
+    pub verbose_check: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "STREAM",
+        default_value = "stdout",
+        help = "Where the solver's `#s` stride lines are read from; the solution is always read from stdout"
+    )]
+    pub stride_lines_from: StrideLinesFrom,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value = "2048",
+        help = "For results other than Valid, report the last BYTES of the solver's stderr in s_stderr_tail (0 disables)"
+    )]
+    pub stderr_tail_bytes: usize,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["strict_solution", "verbose_check"],
+        help = "UNSAFE: skip the checker and take the score from the solver's `#s s_score` line (s_result: Unchecked; never uploaded)"
+    )]
+    pub trust_solver: bool,
+
+    #[arg(
+        long,
+        default_value = "2",
+        help = "Retry checking a solution this many times on IO errors (e.g. on network filesystems)"
+    )]
+    pub checker_io_retries: usize,
+
+    #[arg(
+        long,
+        value_name = "MB",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Limit the estimated memory of concurrent solution checks (derived from the instance size) to this many megabytes"
+    )]
+    pub check_memory_budget: Option<u32>,
+
+    #[arg(
+        long,
+        help = "Show an interactive terminal UI with per-instance details instead of progress bars"
+    )]
+    pub tui: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "tui",
+        help = "Print each row of summary.json to stdout as soon as it is written (NDJSON); all other output goes to stderr"
+    )]
+    pub stdout_ndjson: bool,
+
+    #[arg(
+        long,
+        help = "Store the trees of valid solutions in summary.json (s_solution_trees), so that they can be uploaded later via upload-summary"
+    )]
+    pub store_solutions: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "tui",
+        help = "Show the latest `#s score N` line the solver wrote to stderr and the best known score (if already received) in its progress bar"
+    )]
+    pub live_score: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "tui",
+        help = "Print a line per completed instance to stderr, e.g. in CI where the progress bars are invisible"
+    )]
+    pub print_completions: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "backup",
+        help = "How to proceed if the run directory already contains a summary.json"
+    )]
+    pub existing_summary: ExistingSummary,
+
+    #[arg(
+        long,
+        value_name = "SUMMARY",
+        help = "Resume an interrupted campaign: skip instances that already have a row in this summary.json"
+    )]
+    pub resume: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "resume",
+        help = "With --resume, only skip instances whose recorded result is Valid"
+    )]
+    pub resume_valid_only: bool,
+
+    #[arg(
+        long,
+        value_name = "SUMMARY",
+        help = "Reuse the verdicts of a previous summary.json for instances whose solver output is byte-identical instead of re-checking them"
+    )]
+    pub reuse_unchanged: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "reuse_unchanged",
+        help = "With --reuse-unchanged, do not even run the solver on instances with a reusable verdict"
+    )]
+    pub reuse_skip_solver: bool,
+
+    #[arg(
+        long,
+        help = "State the check key (s_check_key) of each checked solution, so that a later run can reuse its verdict via --reuse-unchanged; implied by --reuse-unchanged"
+    )]
+    pub record_check_keys: bool,
+
+    #[arg(
+        long,
+        help = "Minimize the runner's own I/O: write the summary once at the end, redraw rarely, and only log warnings"
+    )]
+    pub benchmark: bool,
+
+    #[arg(
+        long,
+        value_name = "ROWS",
+        default_value = "1",
+        conflicts_with = "benchmark",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Flush summary.json only every ROWS rows; the default of 1 flushes each row and is most robust against crashes"
+    )]
+    pub summary_flush_rows: usize,
+
+    #[arg(
+        long,
+        help = "Abort the run as soon as an instance fails because the filesystem of the run directory is full"
+    )]
+    pub abort_on_disk_full: bool,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        conflicts_with = "benchmark",
+        value_parser = parse_millis,
+        help = "Additionally flush buffered rows of summary.json every MS milliseconds"
+    )]
+    pub summary_flush_ms: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "MB",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Rotate messages.log to messages.log.1, .2, ... once it exceeds this many megabytes"
+    )]
+    pub log_rotate: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "5",
+        requires = "log_rotate",
+        help = "Number of rotated log files kept with --log-rotate"
+    )]
+    pub log_rotate_keep: usize,
+
     #[arg(
         short = 'P',
         long,
@@ -110,6 +718,20 @@ pub struct CommandRunArgs {
     )]
     pub no_profile: bool,
 
+    #[arg(
+        long,
+        help = "Do not compute the run fingerprint of report.json, which requires hashing the solver and all instances without idigest"
+    )]
+    pub no_fingerprint: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "no_profile",
+        help = "Use this stride binary to profile the solver instead of the running executable"
+    )]
+    pub profiler_bin: Option<PathBuf>,
+
     #[arg(
         short = 'E',
         long,
@@ -117,7 +739,55 @@ pub struct CommandRunArgs {
     )]
     pub no_envs: bool,
 
-    #[arg(last = true, help = "Arguments passed to solver")]
+    #[arg(
+        long,
+        default_value = "STRIDE_",
+        help = "Prefix of the enviroment variables set for the solver (e.g. STRIDE_TIMEOUT)"
+    )]
+    pub env_prefix: String,
+
+    #[arg(
+        long = "solver-env",
+        value_name = "NAME=VALUE",
+        value_parser = parse_env_assignment,
+        help = "Set an additional environment variable for the solver; VALUE may contain the placeholders {instance}, {timeout}, {grace}"
+    )]
+    pub solver_envs: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        help = "Set LC_ALL=C and TZ=UTC for the solver and remove the variables given by --deterministic-env-clear"
+    )]
+    pub deterministic_env: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        value_delimiter = ',',
+        default_value = "RANDOM_SEED,SEED",
+        help = "Environment variables removed from the solver's environment with --deterministic-env"
+    )]
+    pub deterministic_env_clear: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Do not pass the runner's environment to the solver; only variables set by stride and those matching --keep-env-glob are visible"
+    )]
+    pub clean_env: bool,
+
+    #[arg(
+        long = "keep-env-glob",
+        value_name = "PATTERN",
+        requires = "clean_env",
+        value_parser = parse_glob_pattern,
+        help = "Retain the runner's environment variables whose names match PATTERN (e.g. 'LD_*') with --clean-env; repeatable"
+    )]
+    pub keep_env_globs: Vec<glob::Pattern>,
+
+    #[arg(
+        last = true,
+        help = "Arguments passed to solver; if omitted, they are read from STRIDE_SOLVER_ARGS (shell-like quoting)"
+    )]
     pub solver_args: Vec<String>,
 
     #[arg(short = 'S', long, env = ENV_STRIDE_SERVER, default_value = STRIDE_SERVER_DEFAULT, help = "Server to upload to")]
@@ -126,8 +796,225 @@ pub struct CommandRunArgs {
     #[arg(short = 'O', long, help = "Do not communicate with STRIDE servers")]
     pub offline: bool,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value = "all",
+        help = "Result types uploaded to the STRIDE server; best known scores are fetched regardless"
+    )]
+    pub upload_only: UploadOnly,
+
+    #[arg(
+        long,
+        help = "Upload results without waiting for best known scores from the STRIDE server"
+    )]
+    pub no_upload_wait: bool,
+
+    #[arg(
+        long,
+        help = "Check at startup that the STRIDE server is reachable; abort otherwise"
+    )]
+    pub warmup_server: bool,
+
+    #[arg(
+        long,
+        value_name = "SOCKET",
+        conflicts_with = "warmup_server",
+        help = "Upload via a running `stride upload-daemon` listening on SOCKET instead of contacting the server directly"
+    )]
+    pub upload_daemon: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Attach the instance size and the --solver-tag to uploaded infeasible/timeout results"
+    )]
+    pub rich_upload: bool,
+
+    #[arg(
+        long,
+        value_name = "TAG",
+        requires = "rich_upload",
+        help = "Identifies the solver in uploads with --rich-upload"
+    )]
+    pub solver_tag: Option<String>,
+
     #[arg(short = 'r', long="max_run_logs", env = ENV_STRIDE_MAX_RUN_LOGS, help="If more run logs are in the stride-log dir, remove oldest ones")]
     pub remove_old_logs: Option<usize>,
+
+    #[arg(
+        long,
+        value_parser = parse_duration,
+        help = "Abort the run if no solver completed for this many seconds while jobs are running"
+    )]
+    pub stall_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Listen for control commands (e.g. `cancel <instance-name>`) on this Unix socket"
+    )]
+    pub control_socket: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_parser = parse_run_name,
+        help = "Label appended to the run directory; also maintains a `latest-{label}` symlink"
+    )]
+    pub run_name: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "BUCKETS",
+        help = "Print a histogram of the solution sizes of valid runs using this many buckets"
+    )]
+    pub histogram: Option<usize>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Exit with a non-zero code if any instance matches one of these outcomes"
+    )]
+    pub fail_on: Vec<FailOn>,
+}
+
+/// Handling of a summary file that already exists when a run starts
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExistingSummary {
+    /// Rename the existing file to `summary.json.bak` and start a new one
+    Backup,
+    /// Append the new rows to the existing file
+    Append,
+    /// Keep the existing file and write into `summary_1.json`, `summary_2.json`, ...
+    Unique,
+    /// Abort the run
+    Fail,
+}
+
+/// Output streams of the solver that are scanned for `#s` stride lines
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrideLinesFrom {
+    /// Only the solution on stdout
+    Stdout,
+    /// The solution on stdout and additionally the diagnostics on stderr
+    Stderr,
+}
+
+/// Signal asking the solver to terminate at the timeout (see `--term-signal`)
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TermSignal {
+    /// SIGTERM
+    #[default]
+    #[value(name = "TERM")]
+    Term,
+    /// SIGINT, e.g. for solvers that checkpoint on Ctrl+C
+    #[value(name = "INT")]
+    Int,
+    /// SIGHUP
+    #[value(name = "HUP")]
+    Hup,
+    /// SIGUSR1
+    #[value(name = "USR1")]
+    Usr1,
+}
+
+impl TermSignal {
+    pub fn signal(self) -> libc::c_int {
+        match self {
+            TermSignal::Term => libc::SIGTERM,
+            TermSignal::Int => libc::SIGINT,
+            TermSignal::Hup => libc::SIGHUP,
+            TermSignal::Usr1 => libc::SIGUSR1,
+        }
+    }
+}
+
+/// Order in which the instances are dispatched to the solver
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InstanceOrder {
+    /// Sorted by path
+    #[default]
+    Path,
+    /// Ascending by file size (small instances first)
+    Size,
+    /// Descending by file size (large instances first)
+    SizeDesc,
+    /// In the order the instances were specified (with duplicates removed)
+    Insertion,
+    /// Shuffled reproducibly using --seed
+    Random,
+}
+
+/// Direction in which solution scores (i.e. the number of trees) improve
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    /// Fewer trees are better (PACE 2026 convention)
+    #[default]
+    Min,
+    /// More trees are better
+    Max,
+}
+
+impl Objective {
+    /// Returns `true` iff `score` is strictly better than `other`
+    pub fn is_better(self, score: u64, other: u64) -> bool {
+        match self {
+            Objective::Min => score < other,
+            Objective::Max => score > other,
+        }
+    }
+
+    /// Returns the better of both scores
+    pub fn best(self, a: u64, b: u64) -> u64 {
+        if self.is_better(b, a) { b } else { a }
+    }
+
+    /// Returns `true` iff `score` is more than `margin_percent` percent worse than `reference`
+    pub fn exceeds_margin(self, score: u64, reference: u64, margin_percent: f64) -> bool {
+        let factor = margin_percent / 100.0;
+        match self {
+            Objective::Min => score as f64 > reference as f64 * (1.0 + factor),
+            Objective::Max => (score as f64) < reference as f64 * (1.0 - factor),
+        }
+    }
+}
+
+/// Run outcomes that can be selected to yield a non-zero exit code of `stride run`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailOn {
+    /// Any result other than `Valid` and `Timeout` (including `Infeasible`)
+    AnyError,
+    /// Solver returned an infeasible solution
+    Infeasible,
+    /// Solver was killed after the grace period
+    Timeout,
+    /// Solution is worse than the best known score reported by the STRIDE server
+    Suboptimal,
+}
+
+impl FailOn {
+    /// Exit codes 1 and 2 are reserved for failures of the runner and invalid arguments
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailOn::AnyError => 3,
+            FailOn::Infeasible => 4,
+            FailOn::Timeout => 5,
+            FailOn::Suboptimal => 6,
+        }
+    }
+}
+
+/// Results of `stride run` that are uploaded to the STRIDE server
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadOnly {
+    /// Only valid solutions
+    Valid,
+    /// Valid solutions and infeasibility claims
+    #[value(name = "valid+infeasible")]
+    ValidInfeasible,
+    /// Valid solutions, infeasibility claims, and timeouts
+    All,
 }
 
 fn parse_duration(s: &str) -> Result<Duration, String> {
@@ -135,31 +1022,184 @@ fn parse_duration(s: &str) -> Result<Duration, String> {
         .map(Duration::from_secs)
         .map_err(|e| format!("Invalid duration: {}", e))
 }
+
+fn parse_millis(s: &str) -> Result<Duration, String> {
+    s.parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|e| format!("Invalid duration: {}", e))
+}
+
+fn parse_percent(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if x.is_finite() && x >= 0.0 => Ok(x),
+        Ok(_) => Err(format!("Percentage must be non-negative, got `{s}`")),
+        Err(e) => Err(format!("Invalid percentage: {e}")),
+    }
+}
+
+fn parse_env_assignment(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.into(), value.into())),
+        _ => Err(format!("Expected NAME=VALUE, got `{s}`")),
+    }
+}
+
+fn parse_glob_pattern(s: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(s).map_err(|e| format!("Invalid pattern `{s}`: {e}"))
+}
+
+fn parse_regex(s: &str) -> Result<regex::Regex, String> {
+    regex::Regex::new(s).map_err(|e| format!("Invalid regex `{s}`: {e}"))
+}
+
+fn parse_run_name(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("Run name must not be empty".into());
+    }
+
+    if let Some(c) = s
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+    {
+        return Err(format!(
+            "Invalid character {c:?} in run name; only [a-zA-Z0-9_-] are supported"
+        ));
+    }
+
+    Ok(s.into())
+}
+
 fn default_parallel_jobs() -> u64 {
     num_cpus::get_physical() as u64
 }
 
-pub fn parse_prog_arguments() -> Arguments {
-    let mut opts = Arguments::parse();
+#[derive(Error, Debug)]
+pub enum ArgsError {
+    #[error(transparent)]
+    Clap(#[from] clap::Error),
+
+    #[error("Failed to parse {ENV_SOLVER_ARGS}=`{0}`: {1}")]
+    SolverArgsEnv(String, shell_words::ParseError),
+
+    #[error(transparent)]
+    SolverProfile(#[from] SolverProfileError),
+
+    #[error("No instance provided using --instance argument")]
+    NoInstances,
+
+    #[error("No solver provided; pass --solver, --solver-profile, or --shell-command")]
+    NoSolver,
+
+    #[error(
+        "It seems like you provided a relative solver path without './' prefix ({0:?}). Please add './' to the solver path or provide an absolute path."
+    )]
+    RelativeSolverPath(PathBuf),
+}
+
+/// Parses and validates the arguments of the running process
+pub fn parse_prog_arguments() -> Result<Arguments, ArgsError> {
+    parse_arguments_from(std::env::args_os())
+}
+
+/// Parses and validates the command line `args` (including the binary name)
+pub fn parse_arguments_from<I, T>(args: I) -> Result<Arguments, ArgsError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let matches = Arguments::command().try_get_matches_from(args)?;
+    let mut opts = Arguments::from_arg_matches(&matches)?;
 
     if let Arguments::Run(opts) = &mut opts {
         if opts.parallel_jobs.is_none() {
             opts.parallel_jobs = Some(default_parallel_jobs());
         }
 
+        if opts.solver_args.is_empty()
+            && let Ok(env_args) = std::env::var(ENV_SOLVER_ARGS)
+        {
+            opts.solver_args =
+                shell_words::split(&env_args).map_err(|e| ArgsError::SolverArgsEnv(env_args, e))?;
+        }
+
+        if let Some(name) = opts.solver_profile.clone() {
+            let profile = SolverProfile::load(&opts.solver_profiles, &name)?;
+
+            let run_matches = matches.subcommand().map(|(_, m)| m);
+            profile.apply_to(opts, |id| {
+                run_matches
+                    .and_then(|m| m.value_source(id))
+                    .is_some_and(|src| src != ValueSource::DefaultValue)
+            });
+        }
+
         if opts.instances.is_empty() {
-            panic!("No instance provided using --instance argument");
+            return Err(ArgsError::NoInstances);
         }
 
-        if opts.solver.parent().is_none_or(|x| x == "") && !opts.solver.starts_with("./") {
-            // TODO: We could automatically fix this instead of panicking.
+        if let Some(command) = &opts.shell_command {
+            opts.solver = Some(PathBuf::from(SHELL_PATH));
+            opts.solver_args = vec!["-c".into(), command.clone()];
+        }
+
+        let solver = opts.solver.as_ref().ok_or(ArgsError::NoSolver)?;
+        if solver.parent().is_none_or(|x| x == "") && !solver.starts_with("./") {
+            // We could automatically fix this instead of failing.
             // But it seems to be better to make the user aware of this.
-            error!("Relative solver path without ./");
-            panic!(
-                "It seems like you provided a relative solver path without './' prefix. Please add './' to the solver path or provide an absolute path."
-            );
+            return Err(ArgsError::RelativeSolverPath(solver.clone()));
         }
     }
 
-    opts
+    Ok(opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_solver_path() {
+        let result = parse_arguments_from(["stride", "run", "-s", "solver", "-i", "x.in"]);
+        assert!(matches!(result, Err(ArgsError::RelativeSolverPath(_))));
+
+        let result = parse_arguments_from(["stride", "run", "-s", "./solver", "-i", "x.in"]);
+        assert!(matches!(result, Ok(Arguments::Run(_))));
+
+        let result = parse_arguments_from(["stride", "run", "-i", "x.in", "--unknown"]);
+        assert!(matches!(result, Err(ArgsError::Clap(_))));
+    }
+
+    #[test]
+    fn objective() {
+        assert!(Objective::Min.is_better(3, 4));
+        assert!(!Objective::Min.is_better(4, 4));
+        assert!(Objective::Max.is_better(4, 3));
+        assert_eq!(Objective::Min.best(3, 4), 3);
+        assert_eq!(Objective::Max.best(3, 4), 4);
+
+        assert!(!Objective::Min.exceeds_margin(105, 100, 5.0));
+        assert!(Objective::Min.exceeds_margin(106, 100, 5.0));
+        assert!(!Objective::Max.exceeds_margin(95, 100, 5.0));
+        assert!(Objective::Max.exceeds_margin(94, 100, 5.0));
+    }
+
+    #[test]
+    fn optimal_margin() {
+        let margin = |value: &str| {
+            let args = format!("stride run -s ./solver -i x.in --optimal-margin {value}");
+            match parse_arguments_from(args.split(' ')) {
+                Ok(Arguments::Run(args)) => Ok(args.optimal_margin),
+                Ok(_) => unreachable!(),
+                Err(e) => Err(e),
+            }
+        };
+
+        assert_eq!(margin("2.5").unwrap(), Some(2.5));
+        assert!(matches!(margin("-1"), Err(ArgsError::Clap(_))));
+        assert!(matches!(margin("inf"), Err(ArgsError::Clap(_))));
+
+        // without a margin, any worse score is suboptimal
+        assert!(Objective::Min.exceeds_margin(101, 100, 0.0));
+        assert!(!Objective::Min.exceeds_margin(102, 100, 2.5));
+    }
 }
@@ -1,9 +1,18 @@
 use crate::commands::arguments::CommandCheckArgs;
+use crate::commands::run::instances::{Instances, InstancesError, read_idigest};
+use crate::job::check_and_extract::{self, CheckAndExtract};
 use pace26checker::digest::algo::{digest_instance, digest_solution};
+use pace26checker::digest::digest_output::InstanceDigest;
 use pace26checker::{checks::checker::*, io::forest_dot_writer::ForestDotWriter};
 use pace26remote::job_description::JobDescription;
 use pace26remote::upload::{Upload, UploadError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
+use tracing::info;
+
+/// Extension of solution files in directory mode, unless overwritten by `--solution-ext`
+const DEFAULT_SOLUTION_EXT: &str = "out";
 
 #[derive(Error, Debug)]
 pub enum CommandCheckError {
@@ -13,6 +22,26 @@ pub enum CommandCheckError {
     Checker(#[from] CheckerError),
     #[error(transparent)]
     Upload(#[from] UploadError),
+    #[error(transparent)]
+    Instances(#[from] InstancesError),
+    #[error("A solution path cannot be combined with an instance directory")]
+    SolutionForDirectory,
+    #[error("{0} of {1} checks failed")]
+    Failed(usize, usize),
+    #[error(transparent)]
+    Idigest(#[from] IdigestError),
+}
+
+#[derive(Error, Debug)]
+pub enum IdigestError {
+    #[error(transparent)]
+    Checker(#[from] check_and_extract::CheckerError),
+    #[error("Instance {path:?} states idigest {stated}, but its content hashes to {computed}")]
+    Mismatch {
+        path: PathBuf,
+        stated: InstanceDigest,
+        computed: InstanceDigest,
+    },
 }
 
 pub async fn command_check(args: &CommandCheckArgs) -> Result<(), CommandCheckError> {
@@ -24,13 +53,32 @@ pub async fn command_check(args: &CommandCheckArgs) -> Result<(), CommandCheckEr
             .init();
     }
 
-    if let Some(solution_path) = args.solution.as_ref() {
+    if args.instance.is_dir() {
+        return check_directory(args);
+    }
+
+    if args.verify_idigest {
+        verify_stated_idigest(&args.instance)?;
+    }
+
+    let solution = args.solution.clone().or_else(|| {
+        args.solution_ext
+            .as_ref()
+            .map(|ext| args.instance.with_extension(ext))
+    });
+
+    if let Some(solution_path) = solution.as_ref() {
         let (instance, solution, forests) = check_instance_and_solution(
             &args.instance,
             solution_path,
             args.paranoid,
             args.export_dot | args.hash | args.upload,
-        )?;
+        )
+        .inspect_err(|_| {
+            if args.explain {
+                explain_failure(&args.instance, solution_path);
+            }
+        })?;
 
         if let Some(instance) = &instance {
             if args.export_dot {
@@ -99,3 +147,171 @@ pub async fn command_check(args: &CommandCheckArgs) -> Result<(), CommandCheckEr
 
     Ok(())
 }
+
+/// Re-checks a rejected solution with [`CheckAndExtract`] and prints a description of the first
+/// solution tree that could not be matched against an instance tree (if that was the cause)
+fn explain_failure(instance_path: &Path, solution_path: &Path) {
+    let mut checker = CheckAndExtract::new();
+    checker.set_explain(true);
+    let _ = checker.process(instance_path, solution_path);
+
+    if let Some(explanation) = checker.explanation() {
+        eprintln!("{explanation}");
+    }
+}
+
+/// Checks the solution at `solution_path` and writes the GraphViz dot of its forest (as printed
+/// by `stride check -d`) into `dot_path`; used by `stride run --export-dot`
+pub fn write_solution_dot(
+    instance_path: &Path,
+    solution_path: &Path,
+    dot_path: &Path,
+) -> Result<(), CommandCheckError> {
+    let (instance, solution, forests) =
+        check_instance_and_solution(instance_path, solution_path, false, true)?;
+
+    if let Some(instance) = &instance {
+        let mut forest_writer = ForestDotWriter::new(instance);
+        forest_writer.color_leafs(&solution, &forests);
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(dot_path)?);
+        forest_writer.write(&mut writer)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Checks all `*.in` files in the directory `args.instance` against their solutions and
+/// prints one line per pair followed by a summary; fails if any of the pairs is invalid
+fn check_directory(args: &CommandCheckArgs) -> Result<(), CommandCheckError> {
+    if args.solution.is_some() {
+        return Err(CommandCheckError::SolutionForDirectory);
+    }
+
+    let mut instances = Instances::default();
+    instances.insert_from_directory_with_depth(
+        &args.instance,
+        &["in"],
+        (!args.recursive).then_some(0),
+        false,
+    )?;
+
+    let mut instances: Vec<_> = instances.into_iter().collect();
+    instances.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+
+    let solution_ext = args.solution_ext.as_deref().unwrap_or(DEFAULT_SOLUTION_EXT);
+    let mut num_failed = 0;
+
+    for instance in &instances {
+        let solution_path = instance.path().with_extension(solution_ext);
+        let display_path = instance
+            .path()
+            .strip_prefix(&args.instance)
+            .unwrap_or(instance.path());
+
+        let result = if args.verify_idigest {
+            verify_stated_idigest(instance.path()).map_err(CommandCheckError::from)
+        } else {
+            Ok(())
+        };
+
+        match result.and_then(|_| check_pair(instance.path(), &solution_path, args.paranoid)) {
+            Ok(score) => println!("{} ok {score}", display_path.display()),
+            Err(e) => {
+                println!("{} FAILED {e}", display_path.display());
+                num_failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Checked {} pairs: {} passed, {num_failed} failed",
+        instances.len(),
+        instances.len() - num_failed
+    );
+
+    if num_failed > 0 {
+        return Err(CommandCheckError::Failed(num_failed, instances.len()));
+    }
+
+    Ok(())
+}
+
+/// Returns the solution size if `solution_path` is a valid solution of `instance_path`
+fn check_pair(
+    instance_path: &Path,
+    solution_path: &Path,
+    paranoid: bool,
+) -> Result<usize, CommandCheckError> {
+    if !solution_path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("missing solution {solution_path:?}"),
+        )
+        .into());
+    }
+
+    let (_, solution, _) =
+        check_instance_and_solution(instance_path, solution_path, paranoid, false)?;
+    Ok(solution.num_trees())
+}
+
+/// Computes the digest of the instance at `path` from its content (ignoring any `#s idigest` line);
+/// gzip-compressed instances are transparently decompressed
+pub fn compute_idigest(path: &Path) -> Result<InstanceDigest, check_and_extract::CheckerError> {
+    CheckAndExtract::new().instance_digest(path)
+}
+
+/// Recomputes the digest of the instance at `path` and compares it to `stated`
+pub fn verify_idigest(path: &Path, stated: InstanceDigest) -> Result<(), IdigestError> {
+    let computed = compute_idigest(path)?;
+
+    if computed != stated {
+        return Err(IdigestError::Mismatch {
+            path: path.to_path_buf(),
+            stated,
+            computed,
+        });
+    }
+
+    Ok(())
+}
+
+/// Verifies the `#s idigest` line of the instance, if any
+fn verify_stated_idigest(path: &Path) -> Result<(), IdigestError> {
+    match read_idigest(path) {
+        Some(stated) => verify_idigest(path, stated),
+        None => {
+            info!("{path:?} states no idigest; nothing to verify");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use flate2::{Compression, write::GzEncoder};
+
+    #[test]
+    fn idigest_of_gzipped_instance() {
+        let tempdir = tempdir::TempDir::new("gz_idigest").unwrap();
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let plain = compute_idigest(&instance_path).unwrap();
+
+        let gz_path = tempdir.path().join("instance.in.gz");
+        let mut encoder = GzEncoder::new(
+            std::fs::File::create(&gz_path).unwrap(),
+            Compression::default(),
+        );
+        encoder
+            .write_all(&std::fs::read(&instance_path).unwrap())
+            .unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(compute_idigest(&gz_path).unwrap(), plain);
+        verify_idigest(&gz_path, plain).unwrap();
+    }
+}
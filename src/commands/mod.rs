@@ -2,3 +2,6 @@ pub mod arguments;
 pub mod check;
 pub mod profile;
 pub mod run;
+pub mod selftest;
+pub mod upload_daemon;
+pub mod upload_summary;
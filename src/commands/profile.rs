@@ -1,11 +1,13 @@
 use std::mem::MaybeUninit;
+use std::os::unix::process::ExitStatusExt;
 use std::process::{Stdio, exit};
 use std::time::Instant;
 
 use super::arguments::CommandProfileArgs;
+use crate::job::solver_executor::{PROFILER_TRAILER, kill_with_parent, set_resource_limits};
 use libc::rusage;
 use thiserror::Error;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::signal::unix::{SignalKind, signal};
 
 #[derive(Debug, Error)]
@@ -19,38 +21,72 @@ pub enum CommandProfileError {
 pub async fn command_profile(args: &CommandProfileArgs) -> Result<(), CommandProfileError> {
     // we are using the blocking variant here, since we have nothing else to do anyhow
     let start = Instant::now();
-    let mut child = Command::new(args.solver.clone())
+    let mut command = Command::new(args.solver.clone());
+    command
         .args(args.solver_args.clone())
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .spawn()?;
+        .stderr(Stdio::inherit());
 
+    // the limits apply to the solver only; our own runtime needs threads and memory
+    if args.max_fds.is_some() || args.max_procs.is_some() || args.memory_limit.is_some() {
+        let (max_fds, max_procs, memory_limit) = (args.max_fds, args.max_procs, args.memory_limit);
+
+        // SAFETY: setrlimit is async-signal-safe and the closure does not allocate
+        unsafe {
+            command.pre_exec(move || set_resource_limits(max_fds, max_procs, memory_limit));
+        }
+    }
+
+    // the runner kills us after its grace period; the solver must not survive us
+    // SAFETY: prctl and getppid are plain syscalls and the closure does not allocate
+    unsafe {
+        command.pre_exec(kill_with_parent());
+    }
+
+    let mut child = command.spawn()?;
+
+    // when spawned by the runner, we lead the session of the solver and the runner signals our
+    // whole process group; then the solver already received any signal we receive
+    let solver_signalled = unsafe { libc::getsid(0) } == std::process::id() as libc::pid_t;
+
+    // the runner may terminate solvers with any of these signals (see `--term-signal`)
     let mut stream_sigint = signal(SignalKind::interrupt())?;
     let mut stream_sigterm = signal(SignalKind::terminate())?;
+    let mut stream_sighup = signal(SignalKind::hangup())?;
+    let mut stream_sigusr1 = signal(SignalKind::user_defined1())?;
 
     let code = loop {
         tokio::select! {
-            _ = stream_sigint.recv() => {
+            _ = stream_sigint.recv(), if !solver_signalled => {
                 child.kill().await?;
             },
 
-            _ = stream_sigterm.recv() => {
-                if let Some(pid) = child.id() {
-                    unsafe {
-                        libc::kill(pid as i32, libc::SIGTERM);
-                    }
-                }
+            _ = stream_sigterm.recv(), if !solver_signalled => {
+                forward_signal(&child, libc::SIGTERM);
+            },
+
+            _ = stream_sighup.recv(), if !solver_signalled => {
+                forward_signal(&child, libc::SIGHUP);
+            },
+
+            _ = stream_sigusr1.recv(), if !solver_signalled => {
+                forward_signal(&child, libc::SIGUSR1);
             },
 
 
             status = child.wait() => {
-                break status?.code().unwrap_or(1);
+                // forward a terminating signal using the shell convention 128 + signal
+                let status = status?;
+                break status
+                    .code()
+                    .or_else(|| status.signal().map(|signal| 128 + signal))
+                    .unwrap_or(1);
             }
         }
     };
 
-    println!("#s s_wtime {}", start.elapsed().as_secs_f64());
+    println!("{PROFILER_TRAILER}{}", start.elapsed().as_secs_f64());
 
     assert!(
         child.id().is_none(),
@@ -63,6 +99,14 @@ pub async fn command_profile(args: &CommandProfileArgs) -> Result<(), CommandPro
     exit(code);
 }
 
+fn forward_signal(child: &Child, signal: libc::c_int) {
+    if let Some(pid) = child.id() {
+        unsafe {
+            libc::kill(pid as i32, signal);
+        }
+    }
+}
+
 fn get_rusage_children() -> rusage {
     use libc::*;
 
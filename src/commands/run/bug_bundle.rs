@@ -0,0 +1,93 @@
+use flate2::{Compression, write::GzEncoder};
+use std::fs::File;
+use std::path::Path;
+
+/// Writes a gzipped tarball to `path` containing the given result group folders of the run
+/// directory `run_dir` (e.g. `timeout/`) as well as the given files of the run directory
+/// (e.g. `report.json`). All entries are placed below a folder named like the run directory.
+///
+/// Symlinks are followed, so that the instance linked by `stdin` in each task folder is
+/// included verbatim; this makes the bundle a self-contained reproducer of the failing runs.
+pub fn write_bug_bundle<'a>(
+    path: &Path,
+    run_dir: &Path,
+    groups: impl IntoIterator<Item = &'a str>,
+    files: impl IntoIterator<Item = &'a Path>,
+) -> Result<(), std::io::Error> {
+    let root = Path::new(run_dir.file_name().unwrap_or_default());
+
+    let encoder = GzEncoder::new(File::create(path)?, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    builder.follow_symlinks(true);
+
+    for group in groups {
+        builder.append_dir_all(root.join(group), run_dir.join(group))?;
+    }
+
+    for file in files {
+        if let Some(name) = file.file_name()
+            && file.is_file()
+        {
+            builder.append_path_with_name(file, root.join(name))?;
+        }
+    }
+
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+
+    #[test]
+    fn bundle_contains_groups_and_files() {
+        let tempdir = tempdir::TempDir::new("bug_bundle").unwrap();
+        let run_dir = tempdir.path().join("run_1");
+        let task_dir = run_dir.join("timeout").join("inst");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::create_dir_all(run_dir.join("valid").join("other")).unwrap();
+        std::fs::write(task_dir.join("stderr"), "oops").unwrap();
+        std::fs::write(run_dir.join("report.json"), "{}").unwrap();
+
+        let instance = tempdir.path().join("inst.in");
+        std::fs::write(&instance, "#p 1 1\n(1);\n").unwrap();
+        std::os::unix::fs::symlink(&instance, task_dir.join("stdin")).unwrap();
+
+        let bundle = tempdir.path().join("bundle.tar.gz");
+        let report = run_dir.join("report.json");
+        let missing = run_dir.join("missing.json");
+        write_bug_bundle(
+            &bundle,
+            &run_dir,
+            ["timeout"],
+            [report.as_path(), missing.as_path()],
+        )
+        .unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&bundle).unwrap()));
+        let mut entries: Vec<_> = archive
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let e = e.unwrap();
+                (
+                    e.path().unwrap().display().to_string(),
+                    e.header().size().unwrap(),
+                )
+            })
+            .filter(|(_, size)| *size > 0)
+            .collect();
+        entries.sort();
+
+        assert_eq!(
+            entries,
+            [
+                ("run_1/report.json".to_string(), 2),
+                ("run_1/timeout/inst/stderr".to_string(), 4),
+                ("run_1/timeout/inst/stdin".to_string(), 12),
+            ]
+        );
+    }
+}
@@ -1,41 +1,180 @@
 use crate::{
     commands::{
-        arguments::CommandRunArgs,
+        arguments::{CommandRunArgs, FailOn, InstanceOrder, StrideLinesFrom, UploadOnly},
         run::{
-            display::{JobProgressBar, ProgressDisplay},
+            display::{JobProgressBar, ProgressDisplay, ResultCounts},
+            histogram::{format_histogram, read_valid_scores},
             instances::*,
             summary_writer::SummaryWriter,
+            tui::{InstanceEvent, Tui},
         },
     },
-    job::job_processor::{JobProcessorBuilder, JobResult},
+    job::{
+        job_processor::{
+            JobProcessorBuilder, JobResult, PATH_TRANSFORMED_STDOUT, PriorVerdict, is_disk_full,
+        },
+        solver_executor::{PATH_STDERR, PATH_STDOUT},
+    },
     run_directory::*,
 };
-use std::collections::hash_set::IntoIter;
-use std::path::PathBuf;
-use std::{fs::File, sync::Arc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::time::Instant;
+use std::{
+    fs::File,
+    sync::{Arc, Mutex, mpsc::Sender},
+};
 use thiserror::Error;
-use tracing::{error, info, trace};
+use tracing::{debug, error, info, trace, warn};
+use url::Url;
 
-use crate::commands::run::upload::{JobResultUploadAggregation, UploadToStride};
-use crate::job::check_and_extract::SolutionInfos;
+use crate::commands::check::{compute_idigest, verify_idigest, write_solution_dot};
+use crate::commands::run::bug_bundle::write_bug_bundle;
+use crate::commands::run::compare::{Comparison, read_results};
+use crate::commands::run::control::{CancelRegistry, ControlSocket};
+use crate::commands::run::estimate::{RuntimeEstimate, read_mean_wall_time};
+use crate::commands::run::fingerprint::FingerprintInputs;
+use crate::commands::run::jitter::ParallelismJitter;
+use crate::commands::run::live_score::{LIVE_SCORE_POLL_INTERVAL, LiveScoreTail};
+use crate::commands::run::load_throttle::LoadThrottle;
+use crate::commands::run::optimal_db::{OptimalDb, OptimalDbError};
+use crate::commands::run::probe::{probe_solver, verify_profiler};
+use crate::commands::run::repeat::RepeatAggregator;
+use crate::commands::run::report::{REPORT_FILENAME, ReportCollector};
+use crate::commands::run::resume::read_completed_paths;
+use crate::commands::run::reuse::read_prior_verdicts;
+use crate::commands::run::rotating_log::RotatingFile;
+use crate::commands::run::summary_writer::{
+    JSON_KEY_BEATS_OPTIMUM, JSON_KEY_CPU, JSON_KEY_IS_OPTIMAL, JSON_KEY_NUMA_NODE,
+    JSON_KEY_QUEUE_WAIT, JSON_KEY_REPEAT_INDEX, JSON_KEY_RUNTIME, JSON_KEY_TIMEOUT,
+};
+use crate::commands::run::upload::{
+    JobResultUploadAggregation, UploadContext, UploadJob, UploadStatus, UploadToStride, Uploader,
+};
+use crate::commands::upload_daemon::UploadViaDaemon;
+use crate::job::check_and_extract::{
+    ESTIMATED_BYTES_PER_NODE, INSTANCE_INFO_PREFIX, INSTANCE_KEY_OPTIMAL, SolutionInfos,
+    estimate_check_memory,
+};
+use crate::job::cpu_affinity::{CpuScheduler, allowed_cpus};
+use crate::job::numa::{NumaScheduler, detect_nodes};
 use pace26checker::digest::digest_output::InstanceDigest;
 use pace26remote::job_description;
 use pace26remote::job_description::JobDescription;
 use pace26remote::upload::UploadError;
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::task::{JoinError, JoinHandle, JoinSet};
 use tokio::time::timeout;
 use tokio::time::{Duration, sleep};
 
 const DISPLAY_TICK_MIN_WAIT: Duration = Duration::from_millis(25);
+const BENCHMARK_TICK_MIN_WAIT: Duration = Duration::from_secs(1);
+const SUMMARY_FILENAME: &str = "summary.json";
+const SOLUTION_DOT_FILENAME: &str = "solution.dot";
+const GROUP_LIST_FILENAME: &str = "instances.lst";
+/// Result group (folder) of kept work dirs of valid instances
+const VALID_GROUP: &str = "valid";
+const CORE_PATTERN_PATH: &str = "/proc/sys/kernel/core_pattern";
+/// Time granted to cancelled tasks for post-processing (e.g. writing the summary) after the
+/// grace period of their solver
+const SHUTDOWN_SLACK: Duration = Duration::from_secs(2);
 
 pub async fn command_run(args: &CommandRunArgs) -> Result<(), CommandRunError> {
-    let mut task_context = TaskContext::new(args.clone()).await?;
+    // needs to be read before the new run directory becomes the latest one
+    let prior_wall_time = prior_wall_time(args);
+    let previous_summary = args.compare_previous.then(|| {
+        RunDirectory::latest(args.run_name.as_deref()).map(|dir| dir.join(SUMMARY_FILENAME))
+    });
+    // the path may use the `latest` symlink, which is updated by the new run directory
+    let prior_verdicts = match &args.reuse_unchanged {
+        Some(path) => read_prior_verdicts(path)
+            .map_err(|e| CommandRunError::ReuseUnchanged(path.clone(), e))?,
+        None => HashMap::new(),
+    };
+    let resumed = match &args.resume {
+        Some(path) => Some(
+            read_completed_paths(path, args.resume_valid_only)
+                .map_err(|e| CommandRunError::Resume(path.clone(), e))?,
+        ),
+        None => None,
+    };
+    let optimal_db = load_optimal_db(args)?;
+    if args.dry_run {
+        return dry_run(args, prior_wall_time, optimal_db.as_ref(), resumed.as_ref()).await;
+    }
+
+    // set by the argument parser unless the arguments are built programmatically
+    let solver = args.solver.clone().ok_or(CommandRunError::NoSolver)?;
+    let mut task_context = TaskContext::new(args.clone(), solver).await?;
+    if args.reuse_unchanged.is_some() {
+        info!(
+            "Found reusable verdicts of {} instances (--reuse-unchanged)",
+            prior_verdicts.len()
+        );
+        task_context.prior_verdicts = prior_verdicts;
+    }
 
     initialize_logger(&task_context)?;
-    let (mut instances, instances_with_digest) = collect_instances(&args.instances)?;
-    task_context.display.set_total_instance(instances.len());
+    debug!(
+        "Terminate solvers with {:?} (signal {}) at the timeout, SIGKILL after the grace period",
+        args.term_signal,
+        args.term_signal.signal()
+    );
+    let open_instances = if args.open_only {
+        let uploader = UploadToStride::new_with_server(args.solution_server.clone())?;
+        Some(fetch_open_instances(&uploader).await?)
+    } else {
+        None
+    };
+    let (instances, instances_with_digest) = collect_instances(
+        args,
+        open_instances.as_ref(),
+        optimal_db.as_ref(),
+        resumed.as_ref(),
+    )
+    .await?;
+    task_context.optimal_db = optimal_db;
+    if let Some(path) = &args.dump_instances {
+        dump_instances(path, &instances)?;
+    }
+
+    if !args.no_fingerprint {
+        // hashes the solver and all instances without idigest
+        let inputs = {
+            let (solver, args, instances) =
+                (task_context.solver.clone(), args.clone(), instances.clone());
+            tokio::task::spawn_blocking(move || {
+                FingerprintInputs::collect(&solver, &args, &instances)
+            })
+            .await
+        };
+        match inputs {
+            Ok(Ok(inputs)) => {
+                let fingerprint = inputs.fingerprint();
+                info!("Run fingerprint: {fingerprint}");
+                task_context.run_fingerprint = Some(fingerprint);
+            }
+            Ok(Err(e)) => warn!("Failed to compute run fingerprint: {e}"),
+            Err(e) => warn!("Failed to compute run fingerprint: {e}"),
+        }
+    }
+
+    if !args.no_profile {
+        task_context.profiler = Some(resolve_profiler(args).await?);
+    }
+
+    if let Some(flags) = &args.probe_solver {
+        task_context.solver_version = probe_solver(&task_context.solver, flags).await;
+    }
+
+    let num_tasks = instances.len() * args.repeat;
+    task_context.display.set_total_instance(num_tasks);
+    info!("{}", estimate_runtime(args, &instances, prior_wall_time));
     if !args.offline && instances_with_digest > 0 {
-        task_context.enable_uploader()?;
+        task_context.enable_uploader().await?;
         task_context
             .display
             .set_num_stride_instance(instances_with_digest);
@@ -45,25 +184,87 @@ pub async fn command_run(args: &CommandRunArgs) -> Result<(), CommandRunError> {
         let _ = task_context.run_dir.remove_old_run_logs_only_keep(num_keep);
     }
 
+    if let Some(stall_timeout) = args.stall_timeout
+        && stall_timeout <= args.soft_timeout + args.grace_period
+    {
+        warn!("Stall timeout is not larger than timeout + grace; a slow solver may abort the run");
+    }
+
+    if args.capture_cores {
+        warn_on_unsuitable_core_pattern();
+    }
+
+    if args.trust_solver {
+        warn!("Solutions are not checked (--trust-solver); results are reported as Unchecked");
+    }
+
+    let control_socket = args
+        .control_socket
+        .as_deref()
+        .map(|path| ControlSocket::bind(path, task_context.cancellations.clone()))
+        .transpose()?;
+
+    let tui = args.tui.then(|| {
+        let tui = Tui::start(task_context.display.clone(), num_tasks);
+        task_context.events = Some(tui.sender());
+        tui
+    });
+
     let task_context = Arc::new(task_context);
+    let tick_wait = task_context.tick_wait;
+    let status_dumper = spawn_status_dumper(task_context.clone())?;
+    let interrupt_handler = spawn_interrupt_handler(task_context.clone())?;
+
+    // with `--repeat`, each instance is scheduled multiple times in a row
+    let num_repeats = args.repeat;
+    let mut tasks = instances
+        .into_iter()
+        .flat_map(|instance| std::iter::repeat_n(instance, num_repeats).enumerate())
+        .map(|(repeat_index, instance)| (instance, (num_repeats > 1).then_some(repeat_index)));
 
     // We will spawn upto `num_parallel_jobs` in parallel. This rate limit is enforced using the
     // Semaphore `parallel_jobs_sema`. Each task gets sequenced using an own Tokio task, spawned
     // from `task_main`. We pass the semaphore's permit into this task, in general, the task
     // may live much longer than the solver. For instance, the task also handles communication
     // with the stride server and writing into the summary.
-    let num_parallel_jobs = args.parallel_jobs.unwrap() as usize;
+    let num_parallel_jobs = num_parallel_jobs(args);
     let parallel_jobs_sema = Arc::new(Semaphore::new(num_parallel_jobs));
-    let mut join_handles = Vec::with_capacity((100 * num_parallel_jobs).min(instances.len()));
 
-    loop {
-        if let Ok(permit) = timeout(
-            DISPLAY_TICK_MIN_WAIT,
-            parallel_jobs_sema.clone().acquire_owned(),
+    // with `--parallel-jitter`, some of the permits are temporarily withheld from the solvers
+    let jitter = args.parallel_jitter.then(|| {
+        let min_parallel_jobs = (args.parallel_min as usize).min(num_parallel_jobs);
+        ParallelismJitter::start(
+            parallel_jobs_sema.clone(),
+            min_parallel_jobs,
+            num_parallel_jobs,
+            args.parallel_jitter_period,
         )
-        .await
+    });
+    let num_running = || {
+        let withheld = jitter.as_ref().map_or(0, ParallelismJitter::withheld);
+        num_parallel_jobs.saturating_sub(parallel_jobs_sema.available_permits() + withheld)
+    };
+
+    let mut join_handles = Vec::with_capacity((100 * num_parallel_jobs).min(num_tasks));
+
+    // with `--max-loadavg`, no permits are acquired (and hence no solvers started) while the machine is busy
+    let mut load_throttle = args.max_loadavg.map(LoadThrottle::new);
+
+    // all instances are enqueued at once; the queue wait of an instance is the time until its solver starts
+    let enqueued_at = Instant::now();
+
+    loop {
+        // the interrupt handler already cancelled the running solvers; we only wait for them below
+        if task_context.interrupted().is_some() {
+            break;
+        }
+
+        if load_throttle.as_mut().is_some_and(LoadThrottle::is_paused) {
+            sleep(tick_wait).await;
+        } else if let Ok(permit) =
+            timeout(tick_wait, parallel_jobs_sema.clone().acquire_owned()).await
         {
-            let Some(instance) = instances.next() else {
+            let Some((instance, repeat_index)) = tasks.next() else {
                 break;
             };
 
@@ -71,7 +272,9 @@ pub async fn command_run(args: &CommandRunArgs) -> Result<(), CommandRunError> {
                 join_handles.push(tokio::spawn(task_main(
                     task_context.clone(),
                     instance,
+                    repeat_index,
                     permit,
+                    enqueued_at,
                 )));
             } else {
                 error!("Semaphore closed");
@@ -80,34 +283,121 @@ pub async fn command_run(args: &CommandRunArgs) -> Result<(), CommandRunError> {
         }
 
         join_handles.retain(|h| !h.is_finished());
-        task_context
-            .display
-            .tick(num_parallel_jobs - parallel_jobs_sema.available_permits());
+        task_context.display.tick(num_running());
+
+        if let Err(e) =
+            abort_if_stalled(&task_context).and_then(|()| abort_if_disk_full(&task_context))
+        {
+            shutdown(&task_context, join_handles).await;
+            flush_summary(&task_context).await;
+            return Err(e);
+        }
     }
 
+    // returns the withheld permits, so the loop below only waits for the solvers
+    drop(jitter);
+
     // at this point, no instance remain to be started, but some solvers can run
     while parallel_jobs_sema.available_permits() < num_parallel_jobs {
         task_context
             .display
             .tick(num_parallel_jobs - parallel_jobs_sema.available_permits());
 
-        sleep(DISPLAY_TICK_MIN_WAIT).await;
+        if let Err(e) =
+            abort_if_stalled(&task_context).and_then(|()| abort_if_disk_full(&task_context))
+        {
+            shutdown(&task_context, join_handles).await;
+            flush_summary(&task_context).await;
+            return Err(e);
+        }
+
+        sleep(tick_wait).await;
     }
 
-    task_context.display.switch_to_postprocessing();
+    let num_postprocessing_steps = join_handles.len();
+    task_context
+        .display
+        .switch_to_postprocessing(num_postprocessing_steps);
 
-    for mut h in join_handles {
+    for (completed, mut h) in join_handles.into_iter().enumerate() {
         loop {
-            task_context.display.post_processing_tick();
-            if timeout(DISPLAY_TICK_MIN_WAIT, &mut h).await.is_ok() {
+            task_context.display.post_processing_tick(completed);
+            if timeout(tick_wait, &mut h).await.is_ok() {
                 break;
             }
         }
     }
 
-    sleep(DISPLAY_TICK_MIN_WAIT).await;
-    task_context.display.post_processing_tick();
-    task_context.display.final_message();
+    flush_summary(&task_context).await;
+
+    if let Err(e) = write_group_lists(&task_context) {
+        error!("Failed to write instance lists of result groups: {e}");
+    }
+
+    if let Err(e) = write_report(&task_context) {
+        error!("Failed to write run report: {e}");
+    }
+
+    if let Some(path) = &args.bug_bundle {
+        match bundle_failures(&task_context, path) {
+            Ok(0) => info!("No failing instances; skip bug bundle"),
+            Ok(num) => info!("Wrote {num} failing instance(s) into bug bundle {path:?}"),
+            Err(e) => error!("Failed to write bug bundle {path:?}: {e}"),
+        }
+    }
+
+    sleep(tick_wait).await;
+    task_context
+        .display
+        .post_processing_tick(num_postprocessing_steps);
+    if let Some(tui) = tui {
+        tui.finish();
+    }
+    drop(control_socket);
+    status_dumper.abort();
+    interrupt_handler.abort();
+    task_context.display.final_message(args.stdout_ndjson);
+
+    let num_disk_full = task_context.num_disk_full.load(Ordering::Acquire);
+    if num_disk_full > 0 {
+        eprintln!(
+            "{num_disk_full} instance(s) failed as the filesystem of {:?} is full; free disk space and rerun them",
+            task_context.run_dir.path()
+        );
+    }
+
+    if let Some(queue_waits) = task_context.queue_waits.lock().unwrap().summary() {
+        print_message(args, &queue_waits);
+    }
+
+    if let Some(num_buckets) = args.histogram {
+        print_score_histogram(&task_context, num_buckets);
+    }
+
+    if let Some(previous_summary) = previous_summary {
+        print_comparison(&task_context, previous_summary.as_deref());
+    }
+
+    let result_counts = task_context.display.result_counts();
+    let interrupted = task_context.interrupted();
+
+    // make sure that results queued for upload are transmitted before we exit
+    if let Ok(task_context) = Arc::try_unwrap(task_context)
+        && let Some(uploader) = task_context.uploader
+        && let Err(e) = uploader.join().await
+    {
+        error!("Uploader failed to complete: {e}");
+    }
+
+    if let Some(signal) = interrupted {
+        return Err(CommandRunError::Interrupted(signal));
+    }
+
+    check_fail_on(&args.fail_on, &result_counts)?;
+
+    if args.check_optimal && result_counts.not_optimal > 0 {
+        return Err(CommandRunError::NotOptimal(result_counts.not_optimal));
+    }
 
     Ok(())
 }
@@ -125,40 +415,313 @@ pub enum CommandRunError {
 
     #[error(transparent)]
     Io(#[from] std::io::Error),
+
+    #[error("No solver completed within the last {}s; abort run", .0.as_secs())]
+    Stalled(Duration),
+
+    #[error("STRIDE server {0} failed the connectivity check: {1}")]
+    ServerUnreachable(Url, UploadError),
+
+    #[error("The filesystem of the run directory is full; abort run (--abort-on-disk-full)")]
+    DiskFull,
+
+    #[error("Failed to fetch the open instances from the STRIDE server: {0}")]
+    OpenInstances(UploadError),
+
+    #[error("The uploader cannot query the open instances (--open-only)")]
+    OpenInstancesUnsupported,
+
+    #[error("Failed to detect NUMA nodes for --numa-bind: {0}")]
+    NumaNodes(std::io::Error),
+
+    #[error("Failed to read the summary {0:?} of --reuse-unchanged: {1}")]
+    ReuseUnchanged(PathBuf, std::io::Error),
+
+    #[error("Failed to read the summary {0:?} of --resume: {1}")]
+    Resume(PathBuf, std::io::Error),
+
+    #[error("Interrupted by signal {0}; all solvers were terminated")]
+    Interrupted(i32),
+
+    #[error("Failed to read the optimal-score database {0:?}: {1}")]
+    OptimalDb(PathBuf, OptimalDbError),
+
+    #[error("{0} instance(s) failed --verify-idigest")]
+    IdigestMismatch(usize),
+
+    #[error("{num} instance(s) matched --fail-on {reason:?}")]
+    FailOn { reason: FailOn, num: u64 },
+
+    #[error("Profiler {0:?} is unusable: {1}")]
+    ProfilerUnusable(PathBuf, String),
+
+    #[error("{0} instance(s) yielded a score worse than the stated optimum")]
+    NotOptimal(u64),
+
+    #[error("No solver given (--solver, --solver-profile, or --shell-command)")]
+    NoSolver,
+}
+
+impl CommandRunError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandRunError::FailOn { reason, .. } => reason.exit_code(),
+            CommandRunError::Interrupted(signal) => 128 + signal,
+            _ => 1,
+        }
+    }
+}
+
+/// Keeps track of the instances currently processed by a solver and the point in time the
+/// most recent solver completed. It is used to detect runs that stall (see `--stall-timeout`).
+struct StallWatchdog {
+    /// maps the names of the running instances to the point in time their solver started
+    in_flight: HashMap<String, Instant>,
+    last_completion: Instant,
+}
+
+impl StallWatchdog {
+    fn new() -> Self {
+        Self {
+            in_flight: HashMap::new(),
+            last_completion: Instant::now(),
+        }
+    }
+
+    fn start(&mut self, instance_name: &str) {
+        self.in_flight
+            .insert(instance_name.to_owned(), Instant::now());
+    }
+
+    fn complete(&mut self, instance_name: &str) {
+        self.in_flight.remove(instance_name);
+        self.last_completion = Instant::now();
+    }
+
+    /// Returns `true` iff jobs are running but none completed within the last `stall_timeout`
+    fn is_stalled(&self, stall_timeout: Duration) -> bool {
+        !self.in_flight.is_empty() && self.last_completion.elapsed() > stall_timeout
+    }
+}
+
+/// Aggregates the time instances waited for a solver slot (see `s_queue_wait_ms`)
+#[derive(Default)]
+struct QueueWaitStats {
+    min: Option<Duration>,
+    max: Duration,
+    sum: Duration,
+    count: u32,
+}
+
+impl QueueWaitStats {
+    fn add(&mut self, wait: Duration) {
+        self.min = Some(self.min.map_or(wait, |min| min.min(wait)));
+        self.max = self.max.max(wait);
+        self.sum += wait;
+        self.count += 1;
+    }
+
+    fn summary(&self) -> Option<String> {
+        let min = self.min?;
+        Some(format!(
+            "Queue wait: min {:.1}s | mean {:.1}s | max {:.1}s",
+            min.as_secs_f64(),
+            (self.sum / self.count).as_secs_f64(),
+            self.max.as_secs_f64()
+        ))
+    }
 }
 
 struct TaskContext {
     args: CommandRunArgs,
-    display: ProgressDisplay,
+    /// `args.solver`, which is only optional until the arguments are parsed
+    solver: PathBuf,
+    display: Arc<ProgressDisplay>,
     run_dir: Arc<RunDirectory>,
     uploader: Option<JobResultUploadAggregation>,
     summary_writer: SummaryWriter,
+    /// differs from `summary.json` in the run directory with `--existing-summary unique`
+    summary_path: PathBuf,
+    watchdog: Mutex<StallWatchdog>,
+    events: Option<Sender<InstanceEvent>>,
+    /// interval between display updates; long in `--benchmark` mode
+    tick_wait: Duration,
+    cancellations: Arc<CancelRegistry>,
+    queue_waits: Mutex<QueueWaitStats>,
+    repeats: RepeatAggregator,
+    /// instances whose work dirs were kept, grouped by the name of the result folder
+    kept_groups: Mutex<BTreeMap<String, BTreeSet<PathBuf>>>,
+    started: chrono::DateTime<chrono::Local>,
+    report: Mutex<ReportCollector>,
+    run_fingerprint: Option<String>,
+    /// reported by the solver if `--probe-solver` is set
+    solver_version: Option<String>,
+    /// `--profiler-bin` or the running executable; `None` with `--no-profile`
+    profiler: Option<PathBuf>,
+    /// number of instances that failed as the filesystem is full
+    num_disk_full: AtomicU64,
+    /// permits in MB shared by all concurrent checks (see `--check-memory-budget`)
+    check_memory_budget: Option<Arc<Semaphore>>,
+    /// assigns the NUMA node of each solver with `--numa-bind`
+    numa: Option<Arc<NumaScheduler>>,
+    /// assigns the CPU of each solver with `--pin-cpus`
+    cpus: Option<Arc<CpuScheduler>>,
+    /// best known scores received from the STRIDE server during this run (see `--live-score`)
+    best_knowns: Mutex<HashMap<InstanceDigest, u32>>,
+    /// verdicts of a previous run indexed by instance name (see `--reuse-unchanged`)
+    prior_verdicts: HashMap<String, PriorVerdict>,
+    /// user-maintained optimal scores (see `--optimal-db`)
+    optimal_db: Option<OptimalDb>,
+    /// set by `cancel_all`; tasks starting afterwards cancel their solver right away
+    shutting_down: AtomicBool,
+    /// the signal that interrupted the run (0 if none)
+    interrupted: AtomicI32,
 }
 
 impl TaskContext {
-    async fn new(args: CommandRunArgs) -> Result<Self, CommandRunError> {
-        let run_dir = RunDirectory::new()?;
+    async fn new(args: CommandRunArgs, solver: PathBuf) -> Result<Self, CommandRunError> {
+        let run_dir = RunDirectory::new(args.run_name.as_deref())?;
+
+        let mut display = ProgressDisplay::new(0);
+        display.set_print_completions(args.print_completions);
+        let display = Arc::new(display);
+
+        let (mut summary_writer, summary_path) = SummaryWriter::new_or_recover(
+            &run_dir.path().join(SUMMARY_FILENAME),
+            args.existing_summary,
+        )
+        .await?;
+        if args.benchmark {
+            summary_writer.set_batched(true);
+        } else {
+            summary_writer.set_buffered(args.summary_flush_rows, args.summary_flush_ms);
+        }
+        summary_writer.set_mirror_stdout(args.stdout_ndjson);
+        summary_writer.set_store_solutions(args.store_solutions);
+        summary_writer.spawn_flush_timer();
 
-        let display = ProgressDisplay::new(0);
+        let tick_wait = if args.benchmark {
+            display.redraw_rarely();
+            BENCHMARK_TICK_MIN_WAIT
+        } else {
+            DISPLAY_TICK_MIN_WAIT
+        };
 
-        let summary_writer = SummaryWriter::new(&run_dir.path().join("summary.json")).await?;
+        let repeats = RepeatAggregator::new(args.repeat, args.objective);
+
+        let numa = if args.numa_bind {
+            let nodes = detect_nodes().map_err(CommandRunError::NumaNodes)?;
+            if nodes.is_empty() {
+                return Err(CommandRunError::NumaNodes(
+                    std::io::ErrorKind::NotFound.into(),
+                ));
+            }
+            info!("Bind solvers to {} NUMA nodes", nodes.len());
+            Some(Arc::new(NumaScheduler::new(nodes)))
+        } else {
+            None
+        };
+
+        let cpus = args.pin_cpus.then(|| {
+            let scheduler = CpuScheduler::new(allowed_cpus());
+            info!("Pin solvers to {} CPUs", scheduler.num_cpus());
+            if num_parallel_jobs(&args) > scheduler.num_cpus() {
+                warn!("More parallel solvers than CPUs; some solvers share their CPU");
+            }
+            Arc::new(scheduler)
+        });
 
         Ok(Self {
             args,
+            solver,
             display,
             run_dir: Arc::new(run_dir),
             uploader: None,
             summary_writer,
+            summary_path,
+            watchdog: Mutex::new(StallWatchdog::new()),
+            events: None,
+            tick_wait,
+            cancellations: Default::default(),
+            queue_waits: Default::default(),
+            repeats,
+            kept_groups: Default::default(),
+            started: chrono::Local::now(),
+            report: Default::default(),
+            run_fingerprint: None,
+            solver_version: None,
+            profiler: None,
+            num_disk_full: Default::default(),
+            check_memory_budget: args
+                .check_memory_budget
+                .map(|mb| Arc::new(Semaphore::new(mb as usize))),
+            numa,
+            cpus,
+            best_knowns: Default::default(),
+            prior_verdicts: Default::default(),
+            optimal_db: None,
+            shutting_down: AtomicBool::new(false),
+            interrupted: AtomicI32::new(0),
         })
     }
 
-    fn enable_uploader(&mut self) -> Result<(), CommandRunError> {
+    /// Asks all running solvers to terminate (SIGTERM, then SIGKILL after the grace period)
+    fn cancel_all(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+        for cancel in self.cancellations.lock().unwrap().values() {
+            cancel.notify_one();
+        }
+    }
+
+    /// Returns the signal that interrupted the run, if any
+    fn interrupted(&self) -> Option<i32> {
+        match self.interrupted.load(Ordering::Acquire) {
+            0 => None,
+            signal => Some(signal),
+        }
+    }
+
+    /// Records an instance that failed due to a full filesystem; the first one is logged
+    /// prominently, as all following instances are likely to fail as well
+    fn report_disk_full(&self) {
+        if self.num_disk_full.fetch_add(1, Ordering::AcqRel) == 0 {
+            error!(
+                "The filesystem of {:?} is full (ENOSPC); affected instances are reported as SystemError",
+                self.run_dir.path()
+            );
+        }
+    }
+
+    /// Forwards `event` to the TUI, if enabled
+    fn emit(&self, event: InstanceEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        }
+    }
+
+    async fn enable_uploader(&mut self) -> Result<(), CommandRunError> {
         assert!(self.uploader.is_none());
 
+        if let Some(socket) = &self.args.upload_daemon {
+            info!("Uploading via daemon {socket:?}");
+            self.uploader = Some(JobResultUploadAggregation::new(Arc::new(
+                UploadViaDaemon::new(socket),
+            )));
+            return Ok(());
+        }
+
         let uploader = Arc::new(UploadToStride::new_with_server(
             self.args.solution_server.clone(),
         )?);
 
+        if self.args.warmup_server {
+            // fail before any compute is spent, rather than after the first instance completes
+            uploader.probe().await.map_err(|e| {
+                CommandRunError::ServerUnreachable(self.args.solution_server.clone(), e)
+            })?;
+            info!("STRIDE server {} is reachable", self.args.solution_server);
+        }
+
         self.uploader = Some(JobResultUploadAggregation::new(uploader));
 
         Ok(())
@@ -168,30 +731,124 @@ impl TaskContext {
 async fn task_main(
     context: Arc<TaskContext>,
     instance: Instance,
+    repeat_index: Option<usize>,
     permit: OwnedSemaphorePermit,
+    enqueued_at: Instant,
 ) -> Result<(), CommandRunError> {
-    let work_dir = context
+    // repetitions of an instance may run concurrently; so each needs its own name
+    let task_name = match repeat_index {
+        Some(index) => format!("{}_r{index}", instance.name()),
+        None => instance.name().to_owned(),
+    };
+
+    let work_dir = match context
         .run_dir
-        .create_task_dir_for(&PathBuf::from(instance.name()))?;
+        .create_task_dir_for(&PathBuf::from(&task_name))
+    {
+        Ok(work_dir) => work_dir,
+        Err(e) => {
+            if let CreateInstanceDirError::Io(e) = &e
+                && is_disk_full(e)
+            {
+                context.report_disk_full();
+            }
+            // otherwise, the aggregated row of the instance would be missing
+            if repeat_index.is_some() {
+                record_repetition(&context, &instance, JobResult::SystemError, None, None).await;
+            }
+            return Err(e.into());
+        }
+    };
+
+    let cancel = Arc::new(Notify::new());
+    context
+        .cancellations
+        .lock()
+        .unwrap()
+        .insert(task_name.clone(), cancel.clone());
+    // `cancel_all` sets the flag before notifying the registered tasks; so either it sees
+    // our entry or we see the flag
+    if context.shutting_down.load(Ordering::Acquire) {
+        cancel.notify_one();
+    }
+
+    // held until the task completes, so that the node counts as busy meanwhile
+    let numa_lease = context.numa.as_ref().map(|numa| numa.acquire());
+    // released along with the permit below, so that the next solver may use the CPU
+    let cpu_lease = context.cpus.as_ref().map(|cpus| cpus.acquire());
+    let pinned_cpu = cpu_lease.as_ref().map(|lease| lease.cpu());
 
     let processor = Arc::new(
         JobProcessorBuilder::default()
             .work_dir(work_dir.clone())
-            .solver(context.args.solver.clone())
+            .solver(context.solver.clone())
             .solver_args(context.args.solver_args.clone())
-            .soft_timeout(context.args.soft_timeout)
+            .soft_timeout(instance_timeout(&context.args, &instance))
             .grace_period(context.args.grace_period)
+            .term_signal(context.args.term_signal.signal())
+            .cpu_timeout(context.args.cpu_timeout)
+            .max_fds(context.args.max_fds)
+            .max_procs(context.args.max_procs)
+            .memory_limit(
+                context
+                    .args
+                    .memory_limit
+                    .map(|mb| mb.saturating_mul(1024 * 1024)),
+            )
+            .capture_cores(context.args.capture_cores)
+            .numa_node(numa_lease.as_ref().map(|lease| lease.node().clone()))
+            .cpu(pinned_cpu)
+            .detect_early_output(context.args.detect_early_output)
+            .max_output_rate(context.args.max_output_rate.map(|mb| mb * 1024 * 1024))
+            .strict_solution(context.args.strict_solution)
+            .solution_transform(context.args.solution_transform.clone())
+            .verbose_check(context.args.verbose_check)
+            .stride_lines_from_stderr(context.args.stride_lines_from == StrideLinesFrom::Stderr)
+            .stderr_tail_bytes(context.args.stderr_tail_bytes)
+            .trust_solver(context.args.trust_solver)
+            .checker_io_retries(context.args.checker_io_retries)
+            .prior_verdict(
+                repeat_index
+                    .is_none()
+                    .then(|| context.prior_verdicts.get(instance.name()).cloned())
+                    .flatten(),
+            )
+            .reuse_without_solver(context.args.reuse_skip_solver)
+            .record_check_key(
+                context.args.record_check_keys || context.args.reuse_unchanged.is_some(),
+            )
+            .check_memory(context.check_memory_budget.clone().map(|budget| {
+                let weight = check_memory_weight(&instance, context.args.check_memory_budget);
+                (budget, weight)
+            }))
+            .cancel(Some(cancel))
             .instance_path(instance.path().to_path_buf())
-            .profiler(!context.args.no_profile)
+            .profiler(context.profiler.is_some())
+            .profiler_executable(context.profiler.clone())
             .set_stride_envs(!context.args.no_envs)
+            .env_prefix(context.args.env_prefix.clone())
+            .extra_envs(context.args.solver_envs.clone())
+            .deterministic_env(context.args.deterministic_env)
+            .cleared_envs(context.args.deterministic_env_clear.clone())
+            .clean_env(context.args.clean_env)
+            .kept_envs(context.args.keep_env_globs.clone())
             .build()
             .unwrap(),
     );
 
-    let task = {
+    let queue_wait = enqueued_at.elapsed();
+    context.queue_waits.lock().unwrap().add(queue_wait);
+
+    context.watchdog.lock().unwrap().start(&task_name);
+    context.emit(InstanceEvent::Started {
+        name: task_name.clone(),
+        path: instance.path().to_path_buf(),
+    });
+    // if this task is aborted, the solver is killed along with the processor (`kill_on_drop`)
+    let mut task = AbortOnDrop({
         let processor = processor.clone();
         tokio::spawn(async move { processor.run().await })
-    };
+    });
 
     let mut job_progress_bar = JobProgressBar::new(
         String::from(
@@ -205,79 +862,210 @@ async fn task_main(
         processor.grace_period(),
     );
 
-    while !task.is_finished() {
+    let mut live_score = context
+        .args
+        .live_score
+        .then(|| LiveScoreTail::new(work_dir.join(PATH_STDERR), LIVE_SCORE_POLL_INTERVAL));
+
+    let mut previous_progress = None;
+    let (job_result, mut opt_info) = loop {
         let progress = processor.progress();
+        if let Some(live_score) = &mut live_score {
+            let best_known = instance
+                .idigest()
+                .and_then(|idigest| context.best_knowns.lock().unwrap().get(&idigest).copied());
+            job_progress_bar.set_scores(live_score.poll().await, best_known);
+        }
         job_progress_bar.update_progress_bar(&context.display, progress);
 
-        sleep(DISPLAY_TICK_MIN_WAIT).await;
-    }
+        if previous_progress != Some(progress) {
+            previous_progress = Some(progress);
+            context.emit(InstanceEvent::Progress {
+                name: task_name.clone(),
+                progress,
+            });
+        }
 
-    // we only reach this point, if the task finished; so awaiting it should be fast
-    let (job_result, mut opt_info) = task.await.unwrap();
+        // returns as soon as the solver finishes, but redraws at most once per tick
+        if let Ok(result) = timeout(context.tick_wait, &mut task.0).await {
+            break result.unwrap();
+        }
+    };
     job_progress_bar.finish(&context.display, job_result);
+    if processor.disk_full() {
+        context.report_disk_full();
+    }
+    context.cancellations.lock().unwrap().remove(&task_name);
+    context.watchdog.lock().unwrap().complete(&task_name);
+    context.emit(InstanceEvent::Finished {
+        name: task_name.clone(),
+        result: job_result,
+    });
 
     // all remaining steps require very little compute -- so we drop the rate limit permit
     // to free the resources needed for a new solver run
     drop(permit);
+    drop(cpu_lease);
 
     let mut keep_work_dir = context.args.keep_successful_logs;
     keep_work_dir |= !job_result.is_valid();
 
-    // upload and fetch best known
-    let upload_desc = if !context.args.offline
+    let mut dot_exported = false;
+    if context.args.export_dot && job_result.is_valid() {
+        let instance_path = instance.path().to_path_buf();
+        let solution_path = work_dir.join(if context.args.solution_transform.is_some() {
+            PATH_TRANSFORMED_STDOUT
+        } else {
+            PATH_STDOUT
+        });
+        let dot_path = work_dir.join(SOLUTION_DOT_FILENAME);
+
+        // the checker is blocking; see `JobProcessor::run_checker`
+        match tokio::task::spawn_blocking(move || {
+            write_solution_dot(&instance_path, &solution_path, &dot_path)
+        })
+        .await
+        {
+            Ok(Ok(())) => dot_exported = true,
+            Ok(Err(e)) => error!("{:?} Failed to export dot: {e}", instance.path()),
+            Err(e) => error!("{:?} Failed to export dot: {e}", instance.path()),
+        }
+    }
+
+    // runtime should be set if the child terminated, independently of successes; but we rather
+    // lose this data point than the instance's result
+    let runtime = processor.runtime();
+    if runtime.is_none() && !processor.reused() {
+        warn!("{:?} Solver runtime was not recorded", instance.path());
+    }
+    context
+        .display
+        .log_completion(&task_name, job_result, runtime);
+    context.report.lock().unwrap().record(job_result, runtime);
+
+    let mut upload_desc = if !context.args.offline
         && let Some(idigest) = instance.idigest()
     {
-        let runtime = processor.runtime().expect("failed to get runtime"); // runtime will always be set if the child terminated, independently of successes
-        prepare_upload_descriptor(idigest, runtime, job_result, &mut opt_info)
+        prepare_upload_descriptor(
+            idigest,
+            runtime,
+            job_result,
+            &opt_info,
+            context.args.upload_only,
+        )
     } else {
         None
     };
 
-    let score = if let Some(desc) = &upload_desc
-        && let job_description::JobResult::Valid { score, .. } = desc.result
-    {
-        context.display.stride_inc_queued();
-        Some(score)
+    // upload and fetch best known; repetitions are uploaded once all of them completed
+    let upload = if repeat_index.is_none() {
+        let upload = upload_result(&context, &instance, upload_desc.take()).await;
+        keep_work_dir |= upload.suboptimal && context.args.require_optimal;
+        upload
     } else {
-        None
+        UploadOutcome::default()
     };
 
-    let best_known = if let Some(uploader) = context.uploader.as_ref()
-        && let Some(desc) = upload_desc
-    {
-        let response = uploader.upload_and_fetch_best_known(desc).await;
-        let score = score.unwrap();
-
-        if let Some(best_known) = response {
-            if best_known > score {
-                context.display.stride_new_best_known();
-            } else if best_known == score {
-                context.display.stride_inc_best_known();
-            } else {
-                context.display.stride_suboptimal();
-                keep_work_dir |= context.args.require_optimal;
-            }
-        } else {
-            context.display.stride_inc_no_response();
+    // the instance's own `#s optimal` line takes precedence over `--optimal-db`
+    let known_optimum = stated_optimum(&opt_info).or_else(|| {
+        let idigest = instance.idigest()?;
+        context.optimal_db.as_ref()?.optimum(&idigest)
+    });
+    let objective = context.args.objective;
+    let is_optimal = match job_result {
+        JobResult::Valid { size } => {
+            known_optimum.map(|optimal| !objective.is_better(optimal, size as u64))
         }
-
-        response
+        _ => None,
+    };
+    // a solution beating a proven optimum indicates a wrong optimum (or checker); it is flagged
+    // in the summary, as it would otherwise count as optimal
+    let beats_optimum = if let JobResult::Valid { size } = job_result
+        && let Some(optimal) = known_optimum
+        && objective.is_better(size as u64, optimal)
+    {
+        warn!(
+            "{:?} Solution of size {size} is better than the known optimum {optimal}; is the optimum wrong?",
+            instance.path()
+        );
+        true
     } else {
-        None
+        false
     };
+    if is_optimal == Some(false) {
+        warn!(
+            "{:?} Solution is worse than the known optimum",
+            instance.path()
+        );
+        context.display.inc_not_optimal();
+    }
+
+    {
+        let infos = &mut opt_info.get_or_insert_default().1;
+        if let Some(is_optimal) = is_optimal {
+            infos.push((JSON_KEY_IS_OPTIMAL.into(), is_optimal.into()));
+        }
+        if beats_optimum {
+            infos.push((JSON_KEY_BEATS_OPTIMUM.into(), true.into()));
+        }
+        infos.push((
+            JSON_KEY_QUEUE_WAIT.into(),
+            (queue_wait.as_millis() as u64).into(),
+        ));
+        if let Some(runtime) = runtime {
+            infos.push((JSON_KEY_RUNTIME.into(), runtime.as_secs_f64().into()));
+        }
+        if let Some(index) = repeat_index {
+            infos.push((JSON_KEY_REPEAT_INDEX.into(), index.into()));
+        }
+        if let Some(lease) = &numa_lease {
+            infos.push((JSON_KEY_NUMA_NODE.into(), lease.node().id.into()));
+        }
+        if let Some(cpu) = pinned_cpu {
+            infos.push((JSON_KEY_CPU.into(), cpu.into()));
+        }
+        if context.args.timeout_per_leaf.is_some() {
+            infos.push((
+                JSON_KEY_TIMEOUT.into(),
+                processor.soft_timeout().as_secs_f64().into(),
+            ));
+        }
+    }
 
     if let Err(e) = context
         .summary_writer
-        .add_entry(&instance, job_result, opt_info, best_known)
+        .add_entry(
+            &instance,
+            job_result,
+            opt_info,
+            upload.best_known,
+            upload.status.as_ref(),
+        )
         .await
     {
         error!("SummaryWriter error: {e:?}");
     }
 
+    if repeat_index.is_some() {
+        // the logs of the repetition completing the instance are kept
+        let suboptimal =
+            record_repetition(&context, &instance, job_result, runtime, upload_desc).await;
+        keep_work_dir |= suboptimal && context.args.require_optimal;
+    }
+
+    // replaces the result-based retention policy
+    if context.args.keep_if_stderr {
+        keep_work_dir = tokio::fs::metadata(work_dir.join(PATH_STDERR))
+            .await
+            .is_ok_and(|meta| meta.len() > 0);
+    }
+    keep_work_dir |= dot_exported;
+    keep_work_dir |= context.args.bug_bundle.is_some() && !job_result.is_valid();
+
     if keep_work_dir {
         let group = job_result.to_string().to_lowercase();
         let parent = context.run_dir.path().join(group.as_str());
-        let target = parent.join(instance.name());
+        let target = parent.join(&task_name);
         trace!(
             "Move workdir {} to {}",
             work_dir.display(),
@@ -285,7 +1073,16 @@ async fn task_main(
         );
         tokio::fs::create_dir_all(&parent).await?;
         tokio::fs::rename(work_dir, &target).await?;
-        tokio::fs::symlink(instance.path().canonicalize()?, target.join("stdin")).await?;
+        let instance_path = instance.path().canonicalize()?;
+        tokio::fs::symlink(&instance_path, target.join("stdin")).await?;
+
+        context
+            .kept_groups
+            .lock()
+            .unwrap()
+            .entry(group)
+            .or_default()
+            .insert(instance_path);
     } else {
         trace!("Remove workdir {}", work_dir.display());
         tokio::fs::remove_dir_all(work_dir).await?;
@@ -294,38 +1091,657 @@ async fn task_main(
     Ok(())
 }
 
+/// Estimates the runtime of the run from the timeouts of all its tasks (see [`instance_timeout`])
+fn estimate_runtime(
+    args: &CommandRunArgs,
+    instances: &[Instance],
+    prior_wall_time: Option<Duration>,
+) -> RuntimeEstimate {
+    let timeouts: Vec<_> = instances
+        .iter()
+        .flat_map(|instance| std::iter::repeat_n(instance_timeout(args, instance), args.repeat))
+        .collect();
+    RuntimeEstimate::new(&timeouts, num_parallel_jobs(args), prior_wall_time)
+}
+
+/// Returns `--timeout`, extended to `--timeout-per-leaf` times the number of leaves of the
+/// instance if this is larger; instances without `#p` header keep the base timeout
+fn instance_timeout(args: &CommandRunArgs, instance: &Instance) -> Duration {
+    scaled_timeout(
+        args.soft_timeout,
+        args.timeout_per_leaf,
+        instance.size().map(|size| size.num_leaves),
+    )
+}
+
+/// See [`instance_timeout`]; saturates instead of overflowing for absurd headers
+fn scaled_timeout(base: Duration, per_leaf: Option<Duration>, num_leaves: Option<u32>) -> Duration {
+    let scaled = per_leaf
+        .zip(num_leaves)
+        .map(|(per_leaf, num_leaves)| per_leaf.checked_mul(num_leaves).unwrap_or(Duration::MAX));
+    scaled.map_or(base, |scaled| scaled.max(base))
+}
+
+/// Returns the estimated memory in MB of checking a solution of `instance` (see
+/// `--check-memory-budget`); it is capped by the budget, so that each check can run eventually
+fn check_memory_weight(instance: &Instance, budget_mb: Option<u32>) -> u32 {
+    let bytes = match instance.size() {
+        Some(size) => estimate_check_memory(size.num_trees, size.num_leaves),
+        // without header, each byte of the instance may yield a node
+        None => instance.file_size().unwrap_or(0) * ESTIMATED_BYTES_PER_NODE,
+    };
+
+    let mb = bytes.div_ceil(1 << 20).clamp(1, u32::MAX as u64) as u32;
+    mb.min(budget_mb.unwrap_or(u32::MAX))
+}
+
+/// Writes an `instances.lst` into each result folder (e.g. `timeout/`) listing the instances
+/// whose work dirs were moved there; it can be passed to `-i` to rerun them
+fn write_group_lists(context: &TaskContext) -> Result<(), std::io::Error> {
+    for (group, paths) in context.kept_groups.lock().unwrap().iter() {
+        let path = context.run_dir.path().join(group).join(GROUP_LIST_FILENAME);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for instance_path in paths {
+            writeln!(writer, "{}", instance_path.display())?;
+        }
+        writer.flush()?;
+        debug!("Wrote {} instance paths to {path:?}", paths.len());
+    }
+
+    Ok(())
+}
+
+/// Writes the work dirs of all non-valid instances, together with the run report and the
+/// summary, into the archive `path` (see `--bug-bundle`); returns the number of instances
+fn bundle_failures(context: &TaskContext, path: &Path) -> Result<usize, std::io::Error> {
+    let kept_groups = context.kept_groups.lock().unwrap();
+    let failing: Vec<&str> = kept_groups
+        .keys()
+        .map(String::as_str)
+        .filter(|&group| group != VALID_GROUP)
+        .collect();
+
+    let num_instances = failing.iter().map(|group| kept_groups[*group].len()).sum();
+    if num_instances == 0 {
+        return Ok(0);
+    }
+
+    let report = context.run_dir.path().join(REPORT_FILENAME);
+    write_bug_bundle(
+        path,
+        context.run_dir.path(),
+        failing,
+        [report.as_path(), context.summary_path.as_path()],
+    )?;
+
+    Ok(num_instances)
+}
+
+/// Writes the rows still buffered by the summary writer (see `--summary-flush-rows`) to disk
+async fn flush_summary(context: &TaskContext) {
+    if let Err(e) = context.summary_writer.flush().await {
+        error!("SummaryWriter error: {e:?}");
+    }
+}
+
+/// Writes the run-level `report.json` from the results collected so far
+fn write_report(context: &TaskContext) -> Result<(), std::io::Error> {
+    let args = &context.args;
+    let config = serde_json::json!({
+        "solver": args.solver,
+        "solver_args": args.solver_args,
+        "solver_profile": args.solver_profile,
+        "timeout_secs": args.soft_timeout.as_secs_f64(),
+        "grace_secs": args.grace_period.as_secs_f64(),
+        "cpu_timeout_secs": args.cpu_timeout.map(|t| t.as_secs_f64()),
+        "memory_limit_mb": args.memory_limit,
+        "parallel": args.parallel_jobs,
+        "repeat": args.repeat,
+        "objective": args.objective,
+        "offline": args.offline,
+        "profiler": !args.no_profile,
+        "trust_solver": args.trust_solver,
+    });
+
+    let uploads = context
+        .uploader
+        .is_some()
+        .then(|| context.display.stride_counts());
+
+    let mut report = std::mem::take(&mut *context.report.lock().unwrap()).into_report(
+        context.started,
+        uploads,
+        config,
+        context.run_fingerprint.clone(),
+    );
+    report.solver_version = context.solver_version.clone();
+
+    let path = context.run_dir.path().join(REPORT_FILENAME);
+    report.write(&path)?;
+    debug!("Wrote run report to {path:?}");
+    Ok(())
+}
+
+/// Returns the optimal score stated by the instance's `#s optimal` line (as reported by the checker)
+fn stated_optimum(opt_info: &Option<SolutionInfos>) -> Option<u64> {
+    let key = format!("{INSTANCE_INFO_PREFIX}{INSTANCE_KEY_OPTIMAL}");
+    opt_info
+        .as_ref()?
+        .1
+        .iter()
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, value)| value.as_u64())
+}
+
+/// Returns an error for the first condition in `fail_on` matched by any instance
+fn check_fail_on(fail_on: &[FailOn], counts: &ResultCounts) -> Result<(), CommandRunError> {
+    for &reason in fail_on {
+        let num = match reason {
+            FailOn::AnyError => counts.infeasible + counts.errors,
+            FailOn::Infeasible => counts.infeasible,
+            FailOn::Timeout => counts.timeout,
+            FailOn::Suboptimal => counts.suboptimal,
+        };
+
+        if num > 0 {
+            return Err(CommandRunError::FailOn { reason, num });
+        }
+    }
+
+    Ok(())
+}
+
+fn print_score_histogram(context: &TaskContext, num_buckets: usize) {
+    let scores = match read_valid_scores(&context.summary_path) {
+        Ok(scores) => scores,
+        Err(e) => {
+            error!("Failed to read summary for histogram: {e}");
+            return;
+        }
+    };
+
+    if let Some(histogram) = format_histogram(&scores, num_buckets) {
+        let message = format!(
+            "Solution sizes of {} valid runs:\n{histogram}",
+            scores.len()
+        );
+        print_message(&context.args, message.trim_end());
+    }
+}
+
+/// Prints the instances that improved or regressed compared to `previous_summary`
+fn print_comparison(context: &TaskContext, previous_summary: Option<&Path>) {
+    let Some(previous_summary) = previous_summary else {
+        warn!("No previous run found to compare against (--compare-previous)");
+        return;
+    };
+
+    let objective = context.args.objective;
+    let results = read_results(previous_summary, objective).and_then(|previous| {
+        let current = read_results(&context.summary_path, objective)?;
+        Ok(Comparison::new(&previous, &current, objective))
+    });
+
+    match results {
+        Ok(comparison) => print_message(&context.args, &comparison.format()),
+        Err(e) => error!("Failed to compare against {previous_summary:?}: {e}"),
+    }
+}
+
+/// Prints `message` to stdout, or to stderr if stdout is reserved for `--stdout-ndjson`
+fn print_message(args: &CommandRunArgs, message: &str) {
+    if args.stdout_ndjson {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Cancels all solvers on the first `SIGINT` or `SIGTERM`, so that the run ends once they
+/// terminated; a second signal exits immediately (the solvers are then killed by the kernel,
+/// see `solver_executor::kill_with_parent`)
+fn spawn_interrupt_handler(context: Arc<TaskContext>) -> Result<JoinHandle<()>, std::io::Error> {
+    let mut stream_sigint = signal(SignalKind::interrupt())?;
+    let mut stream_sigterm = signal(SignalKind::terminate())?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let signal = tokio::select! {
+                _ = stream_sigint.recv() => libc::SIGINT,
+                _ = stream_sigterm.recv() => libc::SIGTERM,
+            };
+
+            if context.interrupted().is_some() {
+                error!("Received signal {signal} again; exit immediately");
+                std::process::exit(128 + signal);
+            }
+
+            warn!("Received signal {signal}; terminate all solvers and stop the run");
+            context.interrupted.store(signal, Ordering::Release);
+            context.cancel_all();
+        }
+    }))
+}
+
+/// Terminates all running solvers and waits until their tasks completed, so that no solver
+/// outlives the runner; tasks not completing in time (e.g. as they are stuck in the checker)
+/// are aborted
+async fn shutdown(
+    context: &TaskContext,
+    join_handles: Vec<JoinHandle<Result<(), CommandRunError>>>,
+) {
+    context.cancel_all();
+
+    let deadline = Instant::now() + context.args.grace_period + SHUTDOWN_SLACK;
+    for mut h in join_handles {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if timeout(remaining, &mut h).await.is_err() {
+            h.abort();
+        }
+    }
+}
+
+/// Aborts the task once dropped
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Writes a status snapshot into the log whenever the process receives `SIGUSR1`
+fn spawn_status_dumper(context: Arc<TaskContext>) -> Result<JoinHandle<()>, std::io::Error> {
+    let mut stream_sigusr1 = signal(SignalKind::user_defined1())?;
+
+    Ok(tokio::spawn(async move {
+        while stream_sigusr1.recv().await.is_some() {
+            log_status(&context);
+        }
+    }))
+}
+
+fn log_status(context: &TaskContext) {
+    let counts = context.display.result_counts();
+    info!(
+        "Status: valid={} infeasible={} timeout={} errors={} suboptimal={}",
+        counts.valid, counts.infeasible, counts.timeout, counts.errors, counts.suboptimal
+    );
+
+    let watchdog = context.watchdog.lock().unwrap();
+    let mut running: Vec<_> = watchdog.in_flight.iter().collect();
+    running.sort_unstable_by_key(|(_, start)| **start);
+
+    info!("Status: {} running instances", running.len());
+    for (name, start) in running {
+        info!(
+            "Status:   {name} running for {:.1}s",
+            start.elapsed().as_secs_f64()
+        );
+    }
+}
+
+/// Returns an error if the `--stall-timeout` elapsed without any solver completing
+fn abort_if_stalled(context: &TaskContext) -> Result<(), CommandRunError> {
+    let Some(stall_timeout) = context.args.stall_timeout else {
+        return Ok(());
+    };
+
+    let watchdog = context.watchdog.lock().unwrap();
+    if !watchdog.is_stalled(stall_timeout) {
+        return Ok(());
+    }
+
+    let mut in_flight: Vec<_> = watchdog.in_flight.iter().collect();
+    in_flight.sort_unstable();
+    error!(
+        "No solver completed within the last {}s; {} instances in flight: {:?}",
+        stall_timeout.as_secs(),
+        in_flight.len(),
+        in_flight
+    );
+
+    Err(CommandRunError::Stalled(stall_timeout))
+}
+
+/// Returns an error if an instance failed due to a full filesystem and `--abort-on-disk-full` is set
+fn abort_if_disk_full(context: &TaskContext) -> Result<(), CommandRunError> {
+    if !context.args.abort_on_disk_full || context.num_disk_full.load(Ordering::Acquire) == 0 {
+        return Ok(());
+    }
+
+    Err(CommandRunError::DiskFull)
+}
+
+/// Result of [`upload_result`]
+#[derive(Debug, Default)]
+struct UploadOutcome {
+    best_known: Option<u32>,
+    /// whether the uploaded solution is suboptimal
+    suboptimal: bool,
+    /// `None` if nothing was uploaded or the response was not awaited (`--no-upload-wait`)
+    status: Option<UploadStatus>,
+}
+
+/// Records the outcome of a repetition (see `--repeat`); once all repetitions of the instance
+/// completed, the best one is uploaded and the aggregated row is added to the summary. Returns
+/// whether the uploaded solution is suboptimal.
+async fn record_repetition(
+    context: &TaskContext,
+    instance: &Instance,
+    job_result: JobResult,
+    runtime: Option<Duration>,
+    upload_desc: Option<JobDescription>,
+) -> bool {
+    let Some(mut aggregate) =
+        context
+            .repeats
+            .record(instance.name(), job_result, runtime, upload_desc)
+    else {
+        return false;
+    };
+
+    let upload = upload_result(context, instance, aggregate.upload_desc.take()).await;
+    if let Err(e) = context
+        .summary_writer
+        .add_aggregate_entry(
+            instance,
+            &aggregate,
+            upload.best_known,
+            upload.status.as_ref(),
+        )
+        .await
+    {
+        error!("SummaryWriter error: {e:?}");
+    }
+
+    upload.suboptimal
+}
+
+/// Uploads `upload_desc` (if any) and updates the STRIDE counters of the display
+async fn upload_result(
+    context: &TaskContext,
+    instance: &Instance,
+    upload_desc: Option<JobDescription>,
+) -> UploadOutcome {
+    let Some(uploader) = context.uploader.as_ref() else {
+        return UploadOutcome::default();
+    };
+    let Some(desc) = upload_desc else {
+        return UploadOutcome::default();
+    };
+
+    if context.args.no_upload_wait {
+        uploader.upload_only(prepare_upload_job(&context.args, instance, desc));
+        return UploadOutcome::default();
+    }
+
+    // only valid results are counted as queued; each is resolved exactly once below
+    let score = match desc.result {
+        job_description::JobResult::Valid { score, .. } => {
+            context.display.stride_inc_queued();
+            Some(score)
+        }
+        _ => None,
+    };
+
+    let response = uploader
+        .upload_and_wait(prepare_upload_job(&context.args, instance, desc))
+        .await;
+    let status = Some(response.clone().map(|_| ()));
+
+    let Some(score) = score else {
+        // there is no score to compare against
+        return UploadOutcome {
+            status,
+            ..Default::default()
+        };
+    };
+
+    let response = response.ok().flatten();
+    let mut suboptimal = false;
+    if let Some(best_known) = response {
+        if let Some(idigest) = instance.idigest() {
+            context
+                .best_knowns
+                .lock()
+                .unwrap()
+                .insert(idigest, best_known);
+        }
+
+        let objective = context.args.objective;
+        let margin = context.args.optimal_margin.unwrap_or(0.0);
+        if objective.is_better(score.into(), best_known.into()) {
+            context.display.stride_new_best_known();
+        } else if objective.exceeds_margin(score.into(), best_known.into(), margin) {
+            context.display.stride_suboptimal();
+            suboptimal = true;
+        } else {
+            // either matches the best known score or is within the tolerated margin
+            context.display.stride_inc_best_known();
+        }
+    } else {
+        context.display.stride_inc_no_response();
+    }
+
+    UploadOutcome {
+        best_known: response,
+        suboptimal,
+        status,
+    }
+}
+
+/// Attaches the instance size and solver tag to non-valid results if `--rich-upload` is set
+fn prepare_upload_job(
+    args: &CommandRunArgs,
+    instance: &Instance,
+    job: JobDescription,
+) -> UploadJob {
+    let is_valid = matches!(job.result, job_description::JobResult::Valid { .. });
+    if !args.rich_upload || is_valid {
+        return job.into();
+    }
+
+    let size = instance.size();
+    UploadJob {
+        job,
+        context: Some(UploadContext {
+            num_trees: size.map(|s| s.num_trees),
+            num_leaves: size.map(|s| s.num_leaves),
+            solver_tag: args.solver_tag.clone(),
+        }),
+    }
+}
+
+/// Builds the descriptor uploaded to the STRIDE server. If `runtime` is unknown, it is omitted;
+/// timeouts are only uploaded with a known runtime.
 fn prepare_upload_descriptor(
     idigest: InstanceDigest,
-    runtime: Duration,
+    runtime: Option<Duration>,
     job_result: JobResult,
-    opt_info: &mut Option<SolutionInfos>,
+    opt_info: &Option<SolutionInfos>,
+    upload_only: UploadOnly,
 ) -> Option<JobDescription> {
     match job_result {
         JobResult::Valid { .. } => {
-            if let Some(opt_info) = opt_info {
-                let mut trees = std::mem::take(&mut opt_info.0);
+            // a reused verdict lacks the trees unless the previous summary stored them
+            if let Some(opt_info) = opt_info
+                && !opt_info.0.is_empty()
+            {
+                // the trees may also be stored in the summary; hence we cannot take them
+                let mut trees = opt_info.0.clone();
                 Some(JobDescription::valid_from_strings(
-                    idigest,
-                    &mut trees,
-                    Some(runtime),
+                    idigest, &mut trees, runtime,
                 ))
             } else {
                 None
             }
         }
-        JobResult::Infeasible => Some(JobDescription::infeasible(idigest, Some(runtime))),
-        JobResult::Timeout => Some(JobDescription::timeout(idigest, runtime)),
+        JobResult::Infeasible if upload_only != UploadOnly::Valid => {
+            Some(JobDescription::infeasible(idigest, runtime))
+        }
+        JobResult::Timeout if upload_only == UploadOnly::All => {
+            runtime.map(|runtime| JobDescription::timeout(idigest, runtime))
+        }
         _ => None,
     }
 }
 
-fn collect_instances(
-    args_instances: &[PathBuf],
-) -> Result<(IntoIter<Instance>, usize), CommandRunError> {
+/// Number of solvers executed concurrently (at most, with `--parallel-jitter`)
+fn num_parallel_jobs(args: &CommandRunArgs) -> usize {
+    if args.parallel_jitter {
+        args.parallel_max.or(args.parallel_jobs).unwrap() as usize
+    } else {
+        args.parallel_jobs.unwrap() as usize
+    }
+}
+
+/// Mean wall time per instance in the summary of the latest run (with the same `--run-name`)
+fn prior_wall_time(args: &CommandRunArgs) -> Option<Duration> {
+    let run_dir = RunDirectory::latest(args.run_name.as_deref())?;
+    read_mean_wall_time(&run_dir.join(SUMMARY_FILENAME)).ok()?
+}
+
+/// Resolves the instances as a regular run would and prints the runtime estimate (`--dry-run`)
+async fn dry_run(
+    args: &CommandRunArgs,
+    prior_wall_time: Option<Duration>,
+    optimal_db: Option<&OptimalDb>,
+    resumed: Option<&HashSet<PathBuf>>,
+) -> Result<(), CommandRunError> {
+    let open_instances = if args.open_only {
+        let uploader = UploadToStride::new_with_server(args.solution_server.clone())?;
+        Some(fetch_open_instances(&uploader).await?)
+    } else {
+        None
+    };
+    let (instances, _) =
+        collect_instances(args, open_instances.as_ref(), optimal_db, resumed).await?;
+    if let Some(path) = &args.dump_instances {
+        dump_instances(path, &instances)?;
+    }
+
+    let num_tasks = instances.len() * args.repeat;
+    println!("{} instances, {num_tasks} tasks", instances.len());
+    println!("{}", estimate_runtime(args, &instances, prior_wall_time));
+
+    Ok(())
+}
+
+/// Reads the database of `--optimal-db`, if given
+fn load_optimal_db(args: &CommandRunArgs) -> Result<Option<OptimalDb>, CommandRunError> {
+    let Some(path) = &args.optimal_db else {
+        return Ok(None);
+    };
+
+    let db = OptimalDb::from_file(path).map_err(|e| CommandRunError::OptimalDb(path.clone(), e))?;
+    info!(
+        "Read {} optimal scores ({} further entries are not marked optimal) from {path:?}",
+        db.num_optima(),
+        db.num_best_seen()
+    );
+    Ok(Some(db))
+}
+
+/// Queries the STRIDE server for the instances still accepting improvements (see `--open-only`)
+async fn fetch_open_instances(
+    uploader: &impl Uploader,
+) -> Result<HashSet<InstanceDigest>, CommandRunError> {
+    let open = uploader
+        .open_instances()
+        .await
+        .map_err(CommandRunError::OpenInstances)?
+        .ok_or(CommandRunError::OpenInstancesUnsupported)?;
+    info!("STRIDE server reports {} open instances", open.len());
+    Ok(open)
+}
+
+async fn collect_instances(
+    args: &CommandRunArgs,
+    open_instances: Option<&HashSet<InstanceDigest>>,
+    optimal_db: Option<&OptimalDb>,
+    resumed: Option<&HashSet<PathBuf>>,
+) -> Result<(Vec<Instance>, usize), CommandRunError> {
     let mut instances = Instances::default();
-    for p in args_instances {
+    instances.set_stable_names(args.stable_names);
+    instances.set_directory_extensions(args.dir_extensions.clone());
+    for p in &args.instances {
         instances.parse_and_insert_path(p)?;
     }
+
+    if let Some(path) = &args.exclude {
+        let num_removed = instances.exclude_from_list_file(path)?;
+        info!("Dropped {num_removed} instances listed in {path:?} (--exclude)");
+    }
+
+    if let Some(resumed) = resumed {
+        let num_removed =
+            instances.retain(|i| absolute_path(i.path()).is_none_or(|p| !resumed.contains(&p)));
+        info!("Skipped {num_removed} instances already recorded in the resumed summary (--resume)");
+    }
+
+    if let Some(regex) = &args.filter_name {
+        let num_removed = instances.retain(|i| regex.is_match(i.name()));
+        info!("Dropped {num_removed} instances whose name does not match {regex} (--filter-name)");
+    }
+
+    if let Some(regex) = &args.filter_path {
+        let num_removed = instances.retain(|i| regex.is_match(&i.path().to_string_lossy()));
+        info!("Dropped {num_removed} instances whose path does not match {regex} (--filter-path)");
+    }
+
+    if args.only_uploadable {
+        let num_removed = instances.retain(|i| i.idigest().is_some());
+        info!("Dropped {num_removed} instances without idigest (--only-uploadable)");
+    }
+
+    if let Some(open) = open_instances {
+        // instances without idigest are unknown to the server, so they are never open
+        let num_removed =
+            instances.retain(|i| i.idigest().is_some_and(|idigest| open.contains(&idigest)));
+        info!("Dropped {num_removed} instances not open on the STRIDE server (--open-only)");
+    }
+
+    if args.skip_known_optimal
+        && let Some(db) = optimal_db
+    {
+        let num_removed = instances.retain(|i| {
+            i.idigest()
+                .is_none_or(|idigest| db.optimum(&idigest).is_none())
+        });
+        info!("Dropped {num_removed} instances with a known optimum (--skip-known-optimal)");
+    }
+
+    if args.verify_idigest {
+        verify_idigests(&instances)?;
+    }
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    if args.order == InstanceOrder::Random {
+        info!("Shuffle instances with seed {seed} (reproduce with --seed {seed})");
+    }
+
+    // the order of this vector is the dispatch order
+    let mut instances = instances.into_ordered(args.order, seed);
+    if args.dedup_by_digest {
+        let num_before = instances.len();
+        instances = dedup_by_digest(instances).await;
+        info!(
+            "Dropped {} instances with the same digest as a preceding one (--dedup-by-digest)",
+            num_before - instances.len()
+        );
+    }
+
+    if let Some(limit) = args.limit
+        && instances.len() > limit
+    {
+        info!(
+            "Dropped {} instances beyond the first {limit} (--limit)",
+            instances.len() - limit
+        );
+        instances.truncate(limit);
+    }
+
     let instances_with_digest = instances.iter().filter_map(|i| i.idigest()).count();
     info!(
         "Found {} instances. Of those {} have an idigest",
@@ -333,15 +1749,250 @@ fn collect_instances(
         instances_with_digest
     );
 
-    Ok((instances.into_iter(), instances_with_digest))
+    Ok((instances, instances_with_digest))
+}
+
+/// Keeps only the first instance per digest computed from the instance's content; instances
+/// whose digest cannot be computed are kept. Each file (after resolving symlinks) is digested
+/// once, using at most one blocking task per CPU.
+async fn dedup_by_digest(instances: Vec<Instance>) -> Vec<Instance> {
+    let canonical = |i: &Instance| {
+        i.path()
+            .canonicalize()
+            .unwrap_or_else(|_| i.path().to_path_buf())
+    };
+
+    let files: BTreeSet<PathBuf> = instances.iter().map(canonical).collect();
+    let max_tasks = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let mut digests = HashMap::with_capacity(files.len());
+    let mut tasks = JoinSet::new();
+    let mut record = |joined: Result<_, JoinError>| match joined {
+        Ok((file, Some(digest))) => {
+            digests.insert(file, digest);
+        }
+        Ok((_, None)) => {}
+        Err(e) => error!("Digest task failed: {e}"),
+    };
+
+    for file in files {
+        if tasks.len() >= max_tasks
+            && let Some(joined) = tasks.join_next().await
+        {
+            record(joined);
+        }
+        tasks.spawn_blocking(move || {
+            let digest = compute_idigest(&file)
+                .inspect_err(|e| warn!("{file:?} Failed to compute digest: {e}"))
+                .ok();
+            (file, digest)
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        record(joined);
+    }
+
+    let mut first_paths: HashMap<InstanceDigest, PathBuf> = HashMap::new();
+    instances
+        .into_iter()
+        .filter(|instance| {
+            let Some(digest) = digests.get(&canonical(instance)) else {
+                return true;
+            };
+
+            match first_paths.get(digest) {
+                Some(kept) => {
+                    info!(
+                        "Dropped {:?} as it has the same digest {digest} as {kept:?}",
+                        instance.path()
+                    );
+                    false
+                }
+                None => {
+                    first_paths.insert(*digest, instance.path().to_path_buf());
+                    true
+                }
+            }
+        })
+        .collect()
+}
+
+/// Recomputes the digests of all instances stating an idigest; a mismatch indicates an edited
+/// instance file, whose results would otherwise be uploaded for the wrong instance
+fn verify_idigests(instances: &Instances) -> Result<(), CommandRunError> {
+    let mut num_failed = 0;
+    for instance in instances.iter() {
+        let Some(stated) = instance.idigest() else {
+            continue;
+        };
+
+        if let Err(e) = verify_idigest(instance.path(), stated) {
+            error!("{e}");
+            num_failed += 1;
+        }
+    }
+
+    if num_failed > 0 {
+        return Err(CommandRunError::IdigestMismatch(num_failed));
+    }
+
+    info!("Verified the idigests of all instances");
+    Ok(())
+}
+
+/// Returns `--profiler-bin` or, if unset, the running executable, after checking that it
+/// supports the profiling subcommand
+async fn resolve_profiler(args: &CommandRunArgs) -> Result<PathBuf, CommandRunError> {
+    let profiler = match &args.profiler_bin {
+        Some(path) => path.clone(),
+        None => std::env::current_exe()?,
+    };
+
+    verify_profiler(&profiler)
+        .await
+        .map_err(|reason| CommandRunError::ProfilerUnusable(profiler.clone(), reason))?;
+
+    debug!("Use profiler {profiler:?}");
+    Ok(profiler)
+}
+
+/// Core dumps only end up in the solver's working directory, if the kernel's `core_pattern`
+/// is a relative path; we cannot change it without root privileges
+fn warn_on_unsuitable_core_pattern() {
+    match std::fs::read_to_string(CORE_PATTERN_PATH) {
+        Ok(pattern) if pattern.starts_with('|') || pattern.contains('/') => warn!(
+            "core_pattern {:?} does not write core dumps into the working directory; --capture-cores will not find them",
+            pattern.trim()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to read {CORE_PATTERN_PATH}: {e}"),
+    }
+}
+
+/// Writes the paths of `instances` (one per line) into a list file that can be passed to `-i`
+fn dump_instances(path: &Path, instances: &[Instance]) -> Result<(), std::io::Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for instance in instances {
+        writeln!(writer, "{}", instance.path().display())?;
+    }
+    writer.flush()?;
+
+    info!("Wrote {} instance paths to {path:?}", instances.len());
+    Ok(())
 }
 
 fn initialize_logger(task_context: &TaskContext) -> Result<(), CommandRunError> {
-    let log_file = File::create(task_context.run_dir.path().join("messages.log"))?;
-    tracing_subscriber::fmt()
+    let log_path = task_context.run_dir.path().join("messages.log");
+    let max_level = if task_context.args.benchmark {
+        tracing::Level::WARN
+    } else {
+        tracing::Level::TRACE
+    };
+
+    let subscriber = tracing_subscriber::fmt()
         .with_ansi(false)
-        .with_writer(log_file)
-        .with_max_level(tracing::Level::TRACE)
-        .init();
+        .with_max_level(max_level);
+
+    if let Some(max_mb) = task_context.args.log_rotate {
+        let log_file = RotatingFile::create(
+            &log_path,
+            max_mb * 1024 * 1024,
+            task_context.args.log_rotate_keep,
+        )?;
+        subscriber.with_writer(Mutex::new(log_file)).init();
+    } else {
+        subscriber.with_writer(File::create(log_path)?).init();
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_per_leaf() {
+        let secs = Duration::from_secs;
+        let ms = Duration::from_millis;
+
+        assert_eq!(scaled_timeout(secs(10), None, Some(1000)), secs(10));
+        assert_eq!(scaled_timeout(secs(10), Some(ms(100)), None), secs(10));
+        assert_eq!(scaled_timeout(secs(10), Some(ms(100)), Some(50)), secs(10));
+        assert_eq!(scaled_timeout(secs(10), Some(ms(100)), Some(500)), secs(50));
+        assert_eq!(
+            scaled_timeout(secs(10), Some(Duration::MAX / 2), Some(3)),
+            Duration::MAX
+        );
+    }
+
+    struct OpenInstancesUploader(Option<HashSet<InstanceDigest>>);
+
+    impl Uploader for OpenInstancesUploader {
+        async fn upload(
+            &self,
+            _jobs: &[UploadJob],
+        ) -> Result<HashMap<InstanceDigest, u32>, UploadError> {
+            Ok(HashMap::new())
+        }
+
+        async fn open_instances(&self) -> Result<Option<HashSet<InstanceDigest>>, UploadError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn open_instances() {
+        let idigest: InstanceDigest = "00000000000000000000000000000003".try_into().unwrap();
+        let open = fetch_open_instances(&OpenInstancesUploader(Some([idigest].into())))
+            .await
+            .unwrap();
+        assert_eq!(open, [idigest].into());
+
+        // an uploader that cannot be queried must not be mistaken for "no open instances"
+        let result = fetch_open_instances(&OpenInstancesUploader(None)).await;
+        assert!(matches!(
+            result,
+            Err(CommandRunError::OpenInstancesUnsupported)
+        ));
+    }
+
+    #[tokio::test]
+    async fn dedup_by_digest_keeps_first() {
+        let tempdir = tempdir::TempDir::new("dedup").unwrap();
+        let dir = tempdir.path();
+        let mut sources = crate::test_helpers::test_cases_glob("valid_solutions");
+        let (source, other) = (sources.next().unwrap(), sources.next().unwrap());
+
+        std::fs::copy(&source, dir.join("original.in")).unwrap();
+        std::fs::copy(&source, dir.join("copy.in")).unwrap();
+        std::os::unix::fs::symlink(dir.join("original.in"), dir.join("link.in")).unwrap();
+        std::fs::copy(&other, dir.join("other.in")).unwrap();
+
+        let mut instances = Instances::default();
+        for name in ["link.in", "copy.in", "original.in", "other.in"] {
+            instances.insert_instace_by_path(dir.join(name));
+        }
+        let instances = instances.into_ordered(InstanceOrder::Insertion, 0);
+
+        let kept = dedup_by_digest(instances)
+            .await
+            .into_iter()
+            .map(|i| i.path().to_path_buf())
+            .collect::<Vec<_>>();
+        assert_eq!(kept, [dir.join("link.in"), dir.join("other.in")]);
+    }
+
+    #[tokio::test]
+    async fn missing_solver() {
+        use clap::Parser;
+
+        // bypasses `parse_arguments_from`, which resolves the shell command into the solver
+        let args = CommandRunArgs::try_parse_from(["run", "--shell-command", "true", "-i", "x.in"])
+            .unwrap();
+        assert!(args.solver.is_none());
+
+        let result = command_run(&args).await;
+        assert!(matches!(result, Err(CommandRunError::NoSolver)));
+    }
+}
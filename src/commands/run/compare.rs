@@ -0,0 +1,208 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::commands::arguments::Objective;
+use crate::commands::run::summary_writer::{
+    JSON_KEY_INSTANCE_NAME, JSON_KEY_JOB_RESULT, JSON_KEY_SOLUTION_SIZE, RESULT_AGGREGATE,
+};
+
+/// Result and score of an instance as recorded in a summary file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedResult {
+    pub result: String,
+    pub score: Option<u64>,
+}
+
+impl RecordedResult {
+    fn is_valid(&self) -> bool {
+        self.result == "Valid"
+    }
+}
+
+/// Reads the results of all instances of a summary file, indexed by instance name; if an
+/// instance occurs multiple times (e.g., with `--repeat`), its best valid row is kept
+pub fn read_results(
+    summary_path: &Path,
+    objective: Objective,
+) -> Result<BTreeMap<String, RecordedResult>, std::io::Error> {
+    let reader = BufReader::new(std::fs::File::open(summary_path)?);
+
+    let mut results = BTreeMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        let (Some(name), Some(result)) = (
+            row.get(JSON_KEY_INSTANCE_NAME).and_then(|v| v.as_str()),
+            row.get(JSON_KEY_JOB_RESULT).and_then(|v| v.as_str()),
+        ) else {
+            continue;
+        };
+
+        if result == RESULT_AGGREGATE {
+            continue;
+        }
+
+        let recorded = RecordedResult {
+            result: result.to_string(),
+            score: row.get(JSON_KEY_SOLUTION_SIZE).and_then(|v| v.as_u64()),
+        };
+
+        match results.get(name) {
+            Some(known) if !is_better(&recorded, known, objective) => {}
+            _ => {
+                results.insert(name.to_string(), recorded);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Returns true if `a` is a valid result with a better score than `b` or `b` is not valid
+fn is_better(a: &RecordedResult, b: &RecordedResult, objective: Objective) -> bool {
+    match (a.is_valid(), b.is_valid()) {
+        (true, true) => a
+            .score
+            .zip(b.score)
+            .is_some_and(|(a, b)| objective.is_better(a, b)),
+        (valid_a, valid_b) => valid_a && !valid_b,
+    }
+}
+
+/// Instances whose outcome changed between two runs
+#[derive(Debug, Default)]
+pub struct Comparison {
+    pub improved: Vec<(String, RecordedResult, RecordedResult)>,
+    pub regressed: Vec<(String, RecordedResult, RecordedResult)>,
+    /// number of instances present in both runs
+    pub num_common: usize,
+}
+
+impl Comparison {
+    /// Compares the instances contained in both `previous` and `current`
+    pub fn new(
+        previous: &BTreeMap<String, RecordedResult>,
+        current: &BTreeMap<String, RecordedResult>,
+        objective: Objective,
+    ) -> Self {
+        let mut comparison = Self::default();
+        for (name, now) in current {
+            let Some(before) = previous.get(name) else {
+                continue;
+            };
+            comparison.num_common += 1;
+
+            let entry = (name.clone(), before.clone(), now.clone());
+            if is_better(now, before, objective) {
+                comparison.improved.push(entry);
+            } else if is_better(before, now, objective) || (!now.is_valid() && before != now) {
+                comparison.regressed.push(entry);
+            }
+        }
+        comparison
+    }
+
+    /// Renders the comparison as a human-readable report
+    pub fn format(&self) -> String {
+        let describe = |r: &RecordedResult| match r.score {
+            Some(score) if r.is_valid() => format!("{score}"),
+            _ => r.result.clone(),
+        };
+
+        let mut out = format!(
+            "Compared to the previous run ({} common instances): {} improved, {} regressed",
+            self.num_common,
+            self.improved.len(),
+            self.regressed.len()
+        );
+        for (label, entries) in [("+", &self.improved), ("-", &self.regressed)] {
+            for (name, before, now) in entries {
+                let _ = write!(
+                    out,
+                    "\n {label} {name}: {} -> {}",
+                    describe(before),
+                    describe(now)
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(result: &str, score: Option<u64>) -> RecordedResult {
+        RecordedResult {
+            result: result.into(),
+            score,
+        }
+    }
+
+    #[test]
+    fn test_comparison() {
+        let previous = BTreeMap::from([
+            ("a".to_string(), recorded("Valid", Some(5))),
+            ("b".to_string(), recorded("Valid", Some(5))),
+            ("c".to_string(), recorded("Timeout", None)),
+            ("d".to_string(), recorded("Valid", Some(3))),
+            ("e".to_string(), recorded("Timeout", None)),
+            ("f".to_string(), recorded("Timeout", None)),
+        ]);
+        let current = BTreeMap::from([
+            ("a".to_string(), recorded("Valid", Some(4))),
+            ("b".to_string(), recorded("Valid", Some(6))),
+            ("c".to_string(), recorded("Valid", Some(7))),
+            ("d".to_string(), recorded("Infeasible", None)),
+            ("e".to_string(), recorded("Timeout", None)),
+            ("f".to_string(), recorded("SystemError", None)),
+            ("g".to_string(), recorded("Valid", Some(1))),
+        ]);
+
+        let comparison = Comparison::new(&previous, &current, Objective::Min);
+        assert_eq!(comparison.num_common, 6);
+
+        let names = |entries: &[(String, RecordedResult, RecordedResult)]| {
+            entries
+                .iter()
+                .map(|(n, _, _)| n.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&comparison.improved[..]), ["a", "c"]);
+        assert_eq!(names(&comparison.regressed[..]), ["b", "d", "f"]);
+
+        let report = comparison.format();
+        assert!(report.contains("2 improved, 3 regressed"), "{report}");
+        assert!(report.contains(" + a: 5 -> 4"), "{report}");
+        assert!(report.contains(" - d: 3 -> Infeasible"), "{report}");
+    }
+
+    #[test]
+    fn test_read_results_keeps_best() {
+        let tempdir = tempdir::TempDir::new("compare").unwrap();
+        let path = tempdir.path().join("summary.json");
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"s_name\": \"a\", \"s_result\": \"Valid\", \"s_score\": 4}\n",
+                "{\"s_name\": \"a\", \"s_result\": \"Valid\", \"s_score\": 3}\n",
+                "{\"s_name\": \"a\", \"s_result\": \"Timeout\"}\n",
+                "{\"s_name\": \"a\", \"s_result\": \"Aggregate\"}\n",
+            ),
+        )
+        .unwrap();
+
+        let results = read_results(&path, Objective::Min).unwrap();
+        assert_eq!(results["a"], recorded("Valid", Some(3)));
+
+        let results = read_results(&path, Objective::Max).unwrap();
+        assert_eq!(results["a"], recorded("Valid", Some(4)));
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Maps the names of running instances to the handle used to cancel their solver
+pub type CancelRegistry = Mutex<HashMap<String, Arc<Notify>>>;
+
+/// Line-based Unix socket to control a running `stride run`. Each line is a command;
+/// each command is answered by a single line. Supported commands:
+///  - `cancel <instance-name>`: terminates the solver of the instance (SIGTERM, then SIGKILL after the grace period)
+pub struct ControlSocket {
+    path: PathBuf,
+    join_handle: JoinHandle<()>,
+}
+
+impl ControlSocket {
+    pub fn bind(path: &Path, registry: Arc<CancelRegistry>) -> Result<Self, std::io::Error> {
+        let listener = UnixListener::bind(path)?;
+        info!("Listening for control commands on {path:?}");
+
+        let join_handle = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(stream, registry.clone()));
+                    }
+                    Err(e) => {
+                        warn!("Control socket failed to accept connection: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            join_handle,
+        })
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.join_handle.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn handle_connection(stream: UnixStream, registry: Arc<CancelRegistry>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = execute_command(&line, &registry);
+        if writer
+            .write_all(format!("{response}\n").as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn execute_command(line: &str, registry: &CancelRegistry) -> String {
+    let line = line.trim();
+
+    if let Some(name) = line.strip_prefix("cancel ").map(str::trim) {
+        return match registry.lock().unwrap().get(name) {
+            Some(cancel) => {
+                // `notify_one` stores a permit, so the cancellation is not lost if the
+                // solver has not been spawned yet
+                cancel.notify_one();
+                info!("Cancel instance {name} via control socket");
+                String::from("ok")
+            }
+            None => format!("error: no running instance named `{name}`"),
+        };
+    }
+
+    format!("error: unknown command `{line}`; supported: cancel <instance-name>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel() {
+        let registry = CancelRegistry::default();
+        let cancel = Arc::new(Notify::new());
+        registry
+            .lock()
+            .unwrap()
+            .insert("inst".into(), cancel.clone());
+
+        assert_eq!(execute_command("cancel inst\n", &registry), "ok");
+        cancel.notified().await; // completes immediately due to the stored permit
+
+        assert!(execute_command("cancel other", &registry).starts_with("error"));
+        assert!(execute_command("stop inst", &registry).starts_with("error"));
+    }
+}
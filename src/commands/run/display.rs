@@ -1,11 +1,36 @@
 use console::{Attribute, Style};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::time::Instant;
+use tracing::info;
 
 use crate::job::job_processor::{JobProgress, JobResult};
 
+/// Snapshot of the number of completed jobs per (aggregated) outcome
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResultCounts {
+    pub valid: u64,
+    pub infeasible: u64,
+    pub timeout: u64,
+    /// all non-valid results except of `Infeasible`, `Timeout`, and `Cancelled`
+    pub errors: u64,
+    pub suboptimal: u64,
+    /// valid solutions worse than the optimum stated by the instance (`#s optimal`)
+    pub not_optimal: u64,
+}
+
+/// Snapshot of the outcomes of uploads to the STRIDE server
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct StrideCounts {
+    pub instances: u64,
+    pub best_known: u64,
+    pub new_best_known: u64,
+    pub suboptimal: u64,
+    pub no_response: u64,
+}
+
 pub struct ProgressDisplay {
     mpb: MultiProgress,
     status_line: ProgressBar,
@@ -20,6 +45,8 @@ pub struct ProgressDisplay {
     num_systemerror: AtomicU64,
     num_solvererror: AtomicU64,
     num_timeout: AtomicU64,
+    num_cancelled: AtomicU64,
+    num_unchecked: AtomicU64,
 
     num_stride_instances: AtomicU64,
     num_stride_queued: AtomicU64,
@@ -27,6 +54,10 @@ pub struct ProgressDisplay {
     num_stride_new_best_known: AtomicU64,
     num_stride_no_response: AtomicU64,
     num_stride_suboptimal: AtomicU64,
+    num_not_optimal: AtomicU64,
+
+    /// print each completion to stderr (see [`ProgressDisplay::set_print_completions`])
+    print_completions: bool,
 }
 
 impl ProgressDisplay {
@@ -61,6 +92,8 @@ impl ProgressDisplay {
             num_solvererror: Default::default(),
             num_timeout: Default::default(),
             num_emptysolution: Default::default(),
+            num_cancelled: Default::default(),
+            num_unchecked: Default::default(),
 
             num_stride_instances: Default::default(),
             num_stride_queued: Default::default(),
@@ -68,9 +101,29 @@ impl ProgressDisplay {
             num_stride_new_best_known: Default::default(),
             num_stride_no_response: Default::default(),
             num_stride_suboptimal: Default::default(),
+            num_not_optimal: Default::default(),
+
+            print_completions: false,
         }
     }
 
+    /// If set, each completed instance is also printed as a line to stderr; intended for runs
+    /// whose stderr is no terminal (e.g., in CI), where the progress bars are invisible
+    pub fn set_print_completions(&mut self, enabled: bool) {
+        self.print_completions = enabled;
+    }
+
+    /// Suppresses all progress bars, e.g. if another UI renders the progress
+    pub fn hide(&self) {
+        self.mpb.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    /// Limits redraws of the progress bars to once per second, e.g. to reduce overhead in benchmarks
+    pub fn redraw_rarely(&self) {
+        self.mpb
+            .set_draw_target(ProgressDrawTarget::stderr_with_hz(1));
+    }
+
     pub fn set_total_instance(&self, num_instances: usize) {
         self.pb_total.set_length(num_instances as u64);
     }
@@ -79,21 +132,23 @@ impl ProgressDisplay {
         &self.mpb
     }
 
-    pub fn switch_to_postprocessing(&self) {
-        self.pb_total.set_length(100000000);
+    /// Repurposes the total progress bar to track `num_steps` postprocessing steps
+    pub fn switch_to_postprocessing(&self, num_steps: usize) {
+        self.pb_total.set_position(0);
+        self.pb_total.set_length(num_steps as u64);
         self.pb_total.set_style(
             ProgressStyle::default_bar()
-                .template("{spinner:.green} {msg}")
+                .template("{spinner:.green} {msg} [{bar:40.cyan/blue}] {pos}/{len}")
                 .unwrap()
                 .progress_chars("#>-"),
         );
-        self.pb_total
-            .set_message("Postprocessing ... this may take a few seconds");
+        self.pb_total.set_message("Postprocessing");
     }
 
-    pub fn post_processing_tick(&self) {
+    /// Redraws the display where `completed` of the postprocessing steps are done
+    pub fn post_processing_tick(&self, completed: usize) {
         self.tick(0);
-        self.pb_total.inc(1);
+        self.pb_total.set_position(completed as u64);
         self.pb_total.tick();
     }
 
@@ -125,16 +180,22 @@ impl ProgressDisplay {
 
         const CRITICAL: [Attribute; 2] = [Attribute::Bold, Attribute::Underlined];
         {
-            let parts = [
+            let mut parts = vec![
                 format_num!(num_valid, "Valid", green),
                 format_num!(num_emptysolution, "Empty   ", yellow),
                 format_num!(num_infeasible, "Infeas", yellow, CRITICAL),
                 format_num!(num_syntaxerror, "SyntErr", red),
                 format_num!(num_solvererror, "SolvErr ", red),
                 format_num!(num_systemerror, "SysErr", red),
+                format_num!(num_cancelled, "Cancel", yellow),
                 format!("Running: {running}"),
             ];
 
+            // only shown with `--trust-solver` to keep the line short otherwise
+            if self.num_unchecked.load(Ordering::Acquire) > 0 {
+                parts.insert(1, format_num!(num_unchecked, "Unchecked", yellow, CRITICAL));
+            }
+
             self.status_line.set_message(parts.join(" | "));
         }
 
@@ -172,10 +233,10 @@ impl ProgressDisplay {
             JobResult::SyntaxError => {
                 self.num_syntaxerror.fetch_add(1, Ordering::AcqRel);
             }
-            JobResult::SystemError => {
+            JobResult::SystemError | JobResult::CheckerPanic => {
                 self.num_systemerror.fetch_add(1, Ordering::AcqRel);
             }
-            JobResult::SolverError => {
+            JobResult::SolverError | JobResult::OutputOverflow | JobResult::MemoryLimitExceeded => {
                 self.num_solvererror.fetch_add(1, Ordering::AcqRel);
             }
             JobResult::Timeout => {
@@ -184,11 +245,67 @@ impl ProgressDisplay {
             JobResult::EmptySolution => {
                 self.num_emptysolution.fetch_add(1, Ordering::AcqRel);
             }
+            JobResult::Cancelled => {
+                self.num_cancelled.fetch_add(1, Ordering::AcqRel);
+            }
+            JobResult::Unchecked { .. } => {
+                self.num_unchecked.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Logs a single line per completed instance, so that the log becomes a timeline of the run;
+    /// if enabled, the line is also printed to stderr with the result color-coded
+    pub fn log_completion(&self, name: &str, result: JobResult, runtime: Option<Duration>) {
+        let score = match result {
+            JobResult::Valid { size } | JobResult::Unchecked { size } => size.to_string(),
+            _ => String::from("-"),
+        };
+        let runtime = runtime.map_or(String::from("-"), |t| format!("{:.3}s", t.as_secs_f64()));
+
+        info!("Completed {name}: result={result} score={score} runtime={runtime}");
+
+        if self.print_completions {
+            let result = result_style(result).apply_to(format!("{result:<15}"));
+            eprintln!("{result} {name} score={score} runtime={runtime}");
         }
     }
 
-    pub fn final_message(&self) {
-        println!("{}", self.status_line.message());
+    pub fn result_counts(&self) -> ResultCounts {
+        let load = |x: &AtomicU64| x.load(Ordering::Acquire);
+
+        ResultCounts {
+            valid: load(&self.num_valid),
+            infeasible: load(&self.num_infeasible),
+            timeout: load(&self.num_timeout),
+            errors: load(&self.num_emptysolution)
+                + load(&self.num_invalidinstance)
+                + load(&self.num_syntaxerror)
+                + load(&self.num_systemerror)
+                + load(&self.num_solvererror),
+            suboptimal: load(&self.num_stride_suboptimal),
+            not_optimal: load(&self.num_not_optimal),
+        }
+    }
+
+    pub fn stride_counts(&self) -> StrideCounts {
+        let load = |x: &AtomicU64| x.load(Ordering::Acquire);
+
+        StrideCounts {
+            instances: load(&self.num_stride_instances),
+            best_known: load(&self.num_stride_best_known),
+            new_best_known: load(&self.num_stride_new_best_known),
+            suboptimal: load(&self.num_stride_suboptimal),
+            no_response: load(&self.num_stride_no_response),
+        }
+    }
+
+    pub fn final_message(&self, to_stderr: bool) {
+        if to_stderr {
+            eprintln!("{}", self.status_line.message());
+        } else {
+            println!("{}", self.status_line.message());
+        }
     }
 
     /////////////// STRIDE
@@ -207,27 +324,65 @@ impl ProgressDisplay {
         self.num_stride_queued.fetch_add(1, Ordering::AcqRel);
     }
 
+    // Each of the following methods resolves exactly one upload previously announced by
+    // `stride_inc_queued`
+
     pub fn stride_inc_best_known(&self) {
-        self.num_stride_queued.fetch_sub(1, Ordering::AcqRel);
+        self.stride_dequeue();
         self.num_stride_best_known.fetch_add(1, Ordering::AcqRel);
     }
 
+    /// A new best known solution also counts as best known
     pub fn stride_new_best_known(&self) {
-        self.num_stride_queued.fetch_sub(1, Ordering::AcqRel);
+        self.stride_dequeue();
         self.num_stride_new_best_known
             .fetch_add(1, Ordering::AcqRel);
-        self.stride_inc_best_known();
+        self.num_stride_best_known.fetch_add(1, Ordering::AcqRel);
     }
 
     pub fn stride_inc_no_response(&self) {
-        self.num_stride_queued.fetch_sub(1, Ordering::AcqRel);
+        self.stride_dequeue();
         self.num_stride_no_response.fetch_add(1, Ordering::AcqRel);
     }
 
     pub fn stride_suboptimal(&self) {
-        self.num_stride_queued.fetch_sub(1, Ordering::AcqRel);
+        self.stride_dequeue();
         self.num_stride_suboptimal.fetch_add(1, Ordering::AcqRel);
     }
+
+    /// Decrements the number of queued uploads; saturates at 0 to never wrap around
+    fn stride_dequeue(&self) {
+        let _ = self
+            .num_stride_queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                queued.checked_sub(1)
+            });
+    }
+
+    pub fn inc_not_optimal(&self) {
+        self.num_not_optimal.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Colors of the results, consistent with the status line; colors are only emitted if stderr is a
+/// terminal (or `CLICOLOR_FORCE` is set)
+fn result_style(result: JobResult) -> Style {
+    let style = Style::new().for_stderr();
+    match result {
+        JobResult::Valid { .. } => style.green(),
+        JobResult::Infeasible | JobResult::Unchecked { .. } => style
+            .yellow()
+            .attr(Attribute::Bold)
+            .attr(Attribute::Underlined),
+        JobResult::EmptySolution | JobResult::Timeout | JobResult::Cancelled => style.yellow(),
+        JobResult::InvalidInstance
+        | JobResult::SyntaxError
+        | JobResult::SystemError
+        | JobResult::SolverError
+        | JobResult::CheckerPanic
+        | JobResult::OutputOverflow
+        | JobResult::MemoryLimitExceeded => style.red(),
+    }
 }
 
 pub struct JobProgressBar {
@@ -239,6 +394,10 @@ pub struct JobProgressBar {
     previous_progress: Option<JobProgress>,
     start: Instant,
     max_time_millis: u64,
+
+    /// latest score reported by the running solver and best known score (see `--live-score`)
+    live_score: Option<u32>,
+    best_known: Option<u32>,
 }
 
 impl JobProgressBar {
@@ -267,6 +426,23 @@ impl JobProgressBar {
             pb: None,
             previous_progress: None,
             soft_timeout,
+            live_score: None,
+            best_known: None,
+        }
+    }
+
+    /// Scores shown next to the state of a running solver; `None` values are omitted
+    pub fn set_scores(&mut self, live_score: Option<u32>, best_known: Option<u32>) {
+        self.live_score = live_score;
+        self.best_known = best_known;
+    }
+
+    fn format_scores(&self) -> String {
+        match (self.live_score, self.best_known) {
+            (Some(score), Some(best)) => format!(" score {score} (best known {best})"),
+            (Some(score), None) => format!(" score {score}"),
+            (None, Some(best)) => format!(" best known {best}"),
+            (None, None) => String::new(),
         }
     }
 
@@ -298,11 +474,12 @@ impl JobProgressBar {
         let message: String = match progress {
             JobProgress::Starting => "starting".into(),
             JobProgress::Running => {
-                if elapsed > self.soft_timeout.as_millis() as u64 {
+                let state = if elapsed > self.soft_timeout.as_millis() as u64 {
                     Style::new().red().apply_to("grace").to_string()
                 } else {
                     "running".into()
-                }
+                };
+                state + &self.format_scores()
             }
             JobProgress::Checking => "checking".into(),
             JobProgress::Finished => "done".into(),
@@ -346,3 +523,58 @@ impl JobProgressBar {
         pb.set_style(ProgressStyle::default_bar().template(&template).unwrap());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_stride_counters() {
+        const NUM_THREADS: u64 = 16;
+        const NUM_UPLOADS: u64 = 1000;
+
+        let display = ProgressDisplay::new(0);
+        display.hide();
+
+        std::thread::scope(|scope| {
+            for thread in 0..NUM_THREADS {
+                let display = &display;
+                scope.spawn(move || {
+                    for i in 0..NUM_UPLOADS {
+                        display.stride_inc_queued();
+                        match (thread + i) % 4 {
+                            0 => display.stride_inc_best_known(),
+                            1 => display.stride_new_best_known(),
+                            2 => display.stride_inc_no_response(),
+                            _ => display.stride_suboptimal(),
+                        }
+                    }
+                });
+            }
+        });
+
+        let quarter = NUM_THREADS * NUM_UPLOADS / 4;
+        let counts = display.stride_counts();
+        assert_eq!(display.num_stride_queued.load(Ordering::Acquire), 0);
+        assert_eq!(counts.best_known, 2 * quarter);
+        assert_eq!(counts.new_best_known, quarter);
+        assert_eq!(counts.no_response, quarter);
+        assert_eq!(counts.suboptimal, quarter);
+
+        // an unmatched resolution must not wrap the queue counter around
+        display.stride_inc_no_response();
+        assert_eq!(display.num_stride_queued.load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn job_scores() {
+        let mut pb = JobProgressBar::new("x".into(), Duration::from_secs(1), Duration::ZERO);
+        assert_eq!(pb.format_scores(), "");
+
+        pb.set_scores(Some(12), None);
+        assert_eq!(pb.format_scores(), " score 12");
+
+        pb.set_scores(Some(12), Some(10));
+        assert_eq!(pb.format_scores(), " score 12 (best known 10)");
+    }
+}
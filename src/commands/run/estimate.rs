@@ -0,0 +1,126 @@
+use serde_json::Value;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::commands::run::summary_writer::JSON_KEY_WALL_TIME;
+
+/// Estimated total runtime of a run, printed by `--dry-run` and at the start of each run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuntimeEstimate {
+    /// all tasks exhaust their soft timeouts
+    pub worst_case: Duration,
+    /// all tasks take as long as the average task of a prior run
+    pub nominal: Option<Duration>,
+}
+
+impl RuntimeEstimate {
+    /// Estimates the runtime of tasks with the given soft timeouts (in the order they are
+    /// started) executed on `parallel` slots; the nominal estimate is only available if
+    /// `prior_wall_time` (see [`read_mean_wall_time`]) is given
+    pub fn new(timeouts: &[Duration], parallel: usize, prior_wall_time: Option<Duration>) -> Self {
+        // each task is started on the slot that becomes free first
+        let makespan = |runtime: &dyn Fn(Duration) -> Duration| {
+            let mut slots = vec![Duration::ZERO; parallel.max(1)];
+            for &timeout in timeouts {
+                let slot = slots.iter_mut().min().unwrap();
+                *slot = slot.saturating_add(runtime(timeout));
+            }
+            slots.into_iter().max().unwrap()
+        };
+
+        Self {
+            worst_case: makespan(&|timeout| timeout),
+            nominal: prior_wall_time.map(|wall| makespan(&|timeout| wall.min(timeout))),
+        }
+    }
+}
+
+impl Display for RuntimeEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Estimated runtime: at most {} if all instances time out",
+            format_duration(self.worst_case)
+        )?;
+        if let Some(nominal) = self.nominal {
+            write!(
+                f,
+                ", about {} based on the previous run",
+                format_duration(nominal)
+            )?;
+        }
+        write!(f, " (actual runtime is typically lower)")
+    }
+}
+
+/// Returns the mean wall time of all rows of a summary file that report one
+pub fn read_mean_wall_time(summary_path: &Path) -> Result<Option<Duration>, std::io::Error> {
+    let reader = BufReader::new(std::fs::File::open(summary_path)?);
+
+    let mut sum = 0.0;
+    let mut count = 0;
+    for line in reader.lines() {
+        let line = line?;
+        let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        if let Some(secs) = row.get(JSON_KEY_WALL_TIME).and_then(|v| v.as_f64()) {
+            sum += secs;
+            count += 1;
+        }
+    }
+
+    Ok((count > 0).then(|| Duration::from_secs_f64(sum / count as f64)))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}h{:02}m{:02}s", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let minute = Duration::from_secs(60);
+        let estimate = RuntimeEstimate::new(&[minute; 10], 4, None);
+        assert_eq!(estimate.worst_case, Duration::from_secs(180));
+        assert_eq!(estimate.nominal, None);
+        assert_eq!(
+            estimate.to_string(),
+            "Estimated runtime: at most 0h03m00s if all instances time out (actual runtime is typically lower)"
+        );
+
+        let estimate = RuntimeEstimate::new(&[minute; 8], 4, Some(Duration::from_secs(5)));
+        assert_eq!(estimate.worst_case, Duration::from_secs(120));
+        assert_eq!(estimate.nominal, Some(Duration::from_secs(10)));
+
+        // e.g. with `--timeout-per-leaf`, a single long task may dominate
+        let secs = Duration::from_secs;
+        let estimate = RuntimeEstimate::new(&[secs(100), secs(10), secs(10), secs(10)], 2, None);
+        assert_eq!(estimate.worst_case, secs(100));
+        let estimate = RuntimeEstimate::new(&[secs(10), secs(10), secs(10), secs(100)], 2, None);
+        assert_eq!(estimate.worst_case, secs(120));
+    }
+
+    #[test]
+    fn test_read_mean_wall_time() {
+        let tempdir = tempdir::TempDir::new("estimate").unwrap();
+        let path = tempdir.path().join("summary.json");
+        std::fs::write(
+            &path,
+            "{\"s_wtime\": 1.0}\n{\"s_result\": \"Timeout\"}\nnot json\n{\"s_wtime\": 3.0}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_mean_wall_time(&path).unwrap(),
+            Some(Duration::from_secs(2))
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::commands::{arguments::CommandRunArgs, run::instances::Instance};
+
+/// Everything that determines the results of a run (see `run_fingerprint` in `report.json`);
+/// two runs with the same fingerprint are expected to produce comparable results
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerprintInputs {
+    /// SHA-256 of the solver binary
+    pub solver_hash: String,
+    pub solver_args: Vec<String>,
+    pub timeout: Duration,
+    pub grace: Duration,
+    /// instance digests (or content hashes of instances without one); their order is irrelevant
+    pub instance_digests: Vec<String>,
+    /// `None` if offline
+    pub server: Option<String>,
+}
+
+impl FingerprintInputs {
+    /// Hashes the solver binary and all instances used by the run; as this reads all files
+    /// without idigest, it is blocking
+    pub fn collect(
+        solver: &Path,
+        args: &CommandRunArgs,
+        instances: &[Instance],
+    ) -> std::io::Result<Self> {
+        let solver_hash = sha256_of_file(solver)?;
+
+        let instance_digests = instances
+            .iter()
+            .map(|instance| match instance.idigest() {
+                Some(idigest) => Ok(idigest.to_string()),
+                None => Ok(format!("sha256:{}", sha256_of_file(instance.path())?)),
+            })
+            .collect::<std::io::Result<_>>()?;
+
+        Ok(Self {
+            solver_hash,
+            solver_args: args.solver_args.clone(),
+            timeout: args.soft_timeout,
+            grace: args.grace_period,
+            instance_digests,
+            server: (!args.offline).then(|| args.solution_server.to_string()),
+        })
+    }
+
+    /// Hashes the inputs in a canonical order and returns the hash as hex string
+    pub fn fingerprint(&self) -> String {
+        let mut instance_digests = self.instance_digests.clone();
+        instance_digests.sort_unstable();
+
+        let canonical = serde_json::json!([
+            self.solver_hash,
+            self.solver_args,
+            self.timeout.as_millis() as u64,
+            self.grace.as_millis() as u64,
+            instance_digests,
+            self.server,
+        ]);
+
+        to_hex(&Sha256::digest(canonical.to_string().as_bytes()))
+    }
+}
+
+fn sha256_of_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint() {
+        let inputs = FingerprintInputs {
+            solver_hash: "1234".into(),
+            solver_args: vec!["--fast".into()],
+            timeout: Duration::from_secs(30),
+            grace: Duration::from_secs(5),
+            instance_digests: vec!["a".into(), "b".into()],
+            server: None,
+        };
+
+        let mut reordered = inputs.clone();
+        reordered.instance_digests.reverse();
+        assert_eq!(inputs.fingerprint(), reordered.fingerprint());
+
+        let mut longer = inputs.clone();
+        longer.timeout = Duration::from_secs(60);
+        assert_ne!(inputs.fingerprint(), longer.fingerprint());
+
+        // argument boundaries matter
+        let mut split = inputs.clone();
+        split.solver_args = vec!["--".into(), "fast".into()];
+        assert_ne!(inputs.fingerprint(), split.fingerprint());
+    }
+}
@@ -0,0 +1,90 @@
+use serde_json::Value;
+use std::fmt::Write;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::commands::run::summary_writer::{JSON_KEY_JOB_RESULT, JSON_KEY_SOLUTION_SIZE};
+
+const MAX_BAR_WIDTH: usize = 50;
+
+/// Extracts the solution sizes of all valid entries of a summary file
+pub fn read_valid_scores(summary_path: &Path) -> Result<Vec<u64>, std::io::Error> {
+    let reader = BufReader::new(std::fs::File::open(summary_path)?);
+
+    let mut scores = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        if row.get(JSON_KEY_JOB_RESULT).and_then(|v| v.as_str()) != Some("Valid") {
+            continue;
+        }
+
+        if let Some(score) = row.get(JSON_KEY_SOLUTION_SIZE).and_then(|v| v.as_u64()) {
+            scores.push(score);
+        }
+    }
+
+    Ok(scores)
+}
+
+/// Renders a text bar chart of the distribution of `scores` using (at most) `num_buckets`
+/// buckets of equal width; returns `None` if there are no scores
+pub fn format_histogram(scores: &[u64], num_buckets: usize) -> Option<String> {
+    let min = *scores.iter().min()?;
+    let max = *scores.iter().max()?;
+
+    let num_buckets = num_buckets.max(1) as u64;
+    let width = (max - min) / num_buckets + 1;
+    let num_buckets = ((max - min) / width + 1) as usize;
+
+    let mut counts = vec![0usize; num_buckets];
+    for &s in scores {
+        counts[((s - min) / width) as usize] += 1;
+    }
+
+    let max_count = *counts.iter().max().unwrap();
+    let mut result = String::new();
+    for (i, count) in counts.into_iter().enumerate() {
+        let lower = min + i as u64 * width;
+        let upper = lower + width - 1;
+        let range = if width == 1 {
+            format!("{lower}")
+        } else {
+            format!("{lower}-{upper}")
+        };
+
+        let bar = "#".repeat((count * MAX_BAR_WIDTH).div_ceil(max_count));
+        let _ = writeln!(result, "{range:>13} | {bar:<MAX_BAR_WIDTH$} {count}");
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert!(format_histogram(&[], 10).is_none());
+    }
+
+    #[test]
+    fn buckets() {
+        let histogram = format_histogram(&[1, 2, 2, 3, 10], 3).unwrap();
+        let lines: Vec<_> = histogram.lines().collect();
+        assert_eq!(lines.len(), 3, "{histogram}");
+        assert!(lines[0].trim_start().starts_with("1-4 |"), "{histogram}");
+        assert!(lines[0].ends_with(" 4"), "{histogram}");
+        assert!(lines[2].ends_with(" 1"), "{histogram}");
+    }
+
+    #[test]
+    fn single_value() {
+        let histogram = format_histogram(&[7, 7], 5).unwrap();
+        assert_eq!(histogram.lines().count(), 1);
+    }
+}
@@ -1,17 +1,39 @@
+use flate2::read::GzDecoder;
 use pace26checker::digest::digest_output::InstanceDigest;
-use std::collections::HashSet;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::fs::File;
 use std::hash::Hash;
 use std::io::{BufRead, BufReader};
 use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
 use thiserror::Error;
 use tracing::{debug, warn};
 
+use crate::commands::arguments::InstanceOrder;
+use crate::job::check_and_extract::{is_gzipped, open_instance};
+
 #[derive(Clone, Debug, Eq)]
 pub struct Instance {
     name: String,
     path: PathBuf,
     idigest: Option<InstanceDigest>,
+    size: Option<InstanceSize>,
+    /// position among all inserted instances (see [`InstanceOrder::Insertion`])
+    insertion_index: usize,
+    /// length of the instance file in bytes, determined on first use (see [`Instance::file_size`])
+    file_size: OnceLock<Option<u64>>,
+}
+
+/// Size of an instance as stated in its `#p <trees> <leaves>` header line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstanceSize {
+    pub num_trees: u32,
+    pub num_leaves: u32,
 }
 
 impl Hash for Instance {
@@ -20,6 +42,13 @@ impl Hash for Instance {
     }
 }
 
+/// Allows to look up instances by path, which is consistent with `Hash` and `PartialEq`
+impl Borrow<Path> for Instance {
+    fn borrow(&self) -> &Path {
+        &self.path
+    }
+}
+
 impl PartialEq for Instance {
     fn eq(&self, other: &Self) -> bool {
         self.path == other.path
@@ -38,6 +67,24 @@ impl Instance {
     pub fn idigest(&self) -> Option<InstanceDigest> {
         self.idigest
     }
+
+    pub fn size(&self) -> Option<InstanceSize> {
+        self.size
+    }
+
+    /// Returns the length of the instance file in bytes; the file is only stat'ed once.
+    /// If this fails, a warning is logged and `None` returned.
+    pub fn file_size(&self) -> Option<u64> {
+        *self
+            .file_size
+            .get_or_init(|| match std::fs::metadata(&self.path) {
+                Ok(meta) => Some(meta.len()),
+                Err(e) => {
+                    warn!("Failed to stat instance {:?}: {e}", self.path);
+                    None
+                }
+            })
+    }
 }
 
 #[derive(Error, Debug)]
@@ -45,9 +92,6 @@ pub enum InstancesError {
     #[error("Path not found: {0}")]
     PathNotFound(PathBuf),
 
-    #[error("Path points to directory: {0}")]
-    DirectoryFound(PathBuf),
-
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -55,19 +99,47 @@ pub enum InstancesError {
     Serde(#[from] serde_json::Error),
 }
 
+/// Extensions of the instance files collected from directories, unless configured otherwise
+pub const DEFAULT_INSTANCE_EXTENSIONS: &[&str] = &["gr", "in"];
+
 #[derive(Default, Debug, Clone)]
 pub struct Instances {
     names: HashSet<String>,
     instances: HashSet<Instance>,
+    stable_names: bool,
+    /// canonical paths of the instances named by [`Instances::stable_name_from_path`]
+    stable_name_paths: HashMap<String, PathBuf>,
+    /// extensions of the files collected from directories; if empty, [`DEFAULT_INSTANCE_EXTENSIONS`]
+    directory_extensions: Vec<String>,
+    num_inserted: usize,
 }
 
 impl Instances {
+    /// If enabled, names of subsequently inserted instances are derived only from their
+    /// path (see [`Instances::stable_name_from_path`]) and do not depend on other instances
+    pub fn set_stable_names(&mut self, stable_names: bool) {
+        self.stable_names = stable_names;
+    }
+
+    /// Sets the extensions of the instance files collected from directories passed to
+    /// [`Instances::parse_and_insert_path`]
+    pub fn set_directory_extensions(&mut self, extensions: Vec<String>) {
+        self.directory_extensions = extensions;
+    }
+
     pub fn parse_and_insert_path(&mut self, path: &Path) -> Result<(), InstancesError> {
         if path.is_dir() {
-            return Err(InstancesError::DirectoryFound(path.to_path_buf()));
+            debug!("Interpret path {path:?} as directory");
+            let extensions = self.directory_extensions.clone();
+            return if extensions.is_empty() {
+                self.insert_from_directory(path, DEFAULT_INSTANCE_EXTENSIONS)
+            } else {
+                let extensions: Vec<_> = extensions.iter().map(String::as_str).collect();
+                self.insert_from_directory(path, &extensions)
+            };
         }
 
-        if path.extension().and_then(|e| e.to_str()) == Some("lst") {
+        if is_list_file(path) {
             debug!("Interpret path {path:?} as list");
             self.insert_from_list_file(path)
         } else {
@@ -76,84 +148,122 @@ impl Instances {
         }
     }
 
-    pub fn insert_from_list_file(&mut self, path: &Path) -> Result<(), InstancesError> {
-        let file = File::open(path)?;
-        let canon_path = path.canonicalize()?;
-        let relative_to = canon_path
-            .parent()
-            .expect("Parent needs to exists, since path is canonical");
-        self.insert_from_list(BufReader::new(file), relative_to)
+    /// Recursively inserts all files below `dir` with one of the given `extensions`, skipping
+    /// hidden entries (dotfiles)
+    pub fn insert_from_directory(
+        &mut self,
+        dir: &Path,
+        extensions: &[&str],
+    ) -> Result<(), InstancesError> {
+        self.insert_from_directory_with_depth(dir, extensions, None, true)
     }
 
-    pub fn insert_from_list(
+    /// Inserts all files below `dir` with one of the given `extensions`, descending at most
+    /// `max_depth` levels into subdirectories (unlimited if `None`). Hidden entries (dotfiles)
+    /// are skipped if `skip_hidden` is set, and each directory is visited at most once to guard
+    /// against symlink loops.
+    pub fn insert_from_directory_with_depth(
         &mut self,
-        reader: impl BufRead,
-        relative_to: &Path,
+        dir: &Path,
+        extensions: &[&str],
+        max_depth: Option<usize>,
+        skip_hidden: bool,
     ) -> Result<(), InstancesError> {
-        for line in reader.lines() {
-            let line = if let Ok(line) = line {
-                line
-            } else {
-                continue;
-            };
-
-            let line = line.trim();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(dir.to_path_buf(), 0)];
 
-            if line.is_empty() || line.starts_with("#") {
+        while let Some((dir, depth)) = stack.pop() {
+            if !visited.insert(dir.canonicalize()?) {
+                debug!("Skip directory {dir:?} that was already visited");
                 continue;
             }
 
-            let canonical = if line.starts_with('/') {
-                PathBuf::from(line)
-            } else if let Some(c) = normalize_path(&relative_to.join(line)) {
-                c
-            } else {
-                warn!("Failed to canonicalize line `{line}`");
-                continue;
-            };
+            for entry in std::fs::read_dir(&dir)? {
+                let entry_path = entry?.path();
 
-            if let Some(pattern) = canonical.to_str()
-                && (pattern.contains('*') || pattern.contains('?'))
-            {
-                // treat as glob string
-                debug!("glob {pattern}");
-                match glob::glob(pattern) {
-                    Ok(paths) => {
-                        for p in paths.filter_map(|p| p.ok()) {
-                            self.parse_and_insert_path(&p)?;
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Pattern error: {e}");
-                        continue;
+                let is_hidden = entry_path
+                    .file_name()
+                    .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."));
+                if skip_hidden && is_hidden {
+                    continue;
+                }
+
+                if entry_path.is_dir() {
+                    if max_depth.is_none_or(|max_depth| depth < max_depth) {
+                        stack.push((entry_path, depth + 1));
                     }
+                } else if extensions
+                    .iter()
+                    .any(|ext| has_instance_extension(&entry_path, ext))
+                {
+                    self.insert_instace_by_path(entry_path);
                 }
-            } else {
-                self.parse_and_insert_path(&canonical)?;
             }
         }
 
         Ok(())
     }
 
+    /// Reads a list file; if its extension is `.gz`, it is transparently decompressed
+    pub fn insert_from_list_file(&mut self, path: &Path) -> Result<(), InstancesError> {
+        let (reader, relative_to) = open_list_file(path)?;
+        self.insert_from_list(reader, &relative_to)
+    }
+
+    pub fn insert_from_list(
+        &mut self,
+        reader: impl BufRead,
+        relative_to: &Path,
+    ) -> Result<(), InstancesError> {
+        for path in list_entries(reader, relative_to) {
+            self.parse_and_insert_path(&path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes all instances named by the exclusion list `path`, which uses the syntax of list
+    /// files (incl. globs and nested lists); returns the number of removed instances.
+    /// Paths are compared after normalization, so relative and absolute spellings match.
+    pub fn exclude_from_list_file(&mut self, path: &Path) -> Result<usize, InstancesError> {
+        let mut excluded = HashSet::new();
+        collect_exclusions(path, &mut excluded)?;
+
+        let num_removed =
+            self.retain(|i| absolute_path(i.path()).is_none_or(|p| !excluded.contains(&p)));
+        debug!(
+            "Excluded {num_removed} instances listed in {path:?} ({} entries)",
+            excluded.len()
+        );
+
+        Ok(num_removed)
+    }
+
     /// Attempts to insert a new instance fully described by its path;
     /// returns `true` iff the path was not yet in the data set
     pub fn insert_instace_by_path(&mut self, path: PathBuf) -> bool {
-        // we optimize for the good case, where the path is new
-        let name = self.unique_name_from_path(&path);
-        let idigest = scan_for_idigest(&path).ok().flatten();
+        if self.instances.contains(path.as_path()) {
+            return false;
+        }
+
+        let name = if self.stable_names {
+            self.stable_name_from_path(&path)
+        } else {
+            self.unique_name_from_path(&path)
+        };
+        let (idigest, size) = scan_header(&path).unwrap_or_default();
 
-        let newly_inserted = self.instances.insert(Instance {
+        self.instances.insert(Instance {
             path,
-            name: name.clone(),
+            name,
             idigest,
+            size,
+            insertion_index: self.num_inserted,
+            file_size: OnceLock::new(),
         });
+        self.num_inserted += 1;
 
-        if !newly_inserted {
-            self.names.remove(&name);
-        }
-
-        newly_inserted
+        true
     }
 
     /// Returns number of elements in collection
@@ -166,15 +276,48 @@ impl Instances {
         self.instances.is_empty()
     }
 
+    /// Removes all instances for which `keep` returns `false`; returns the number of removed instances
+    pub fn retain(&mut self, mut keep: impl FnMut(&Instance) -> bool) -> usize {
+        let before = self.len();
+        self.instances.retain(|i| keep(i));
+        before - self.len()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Instance> {
         self.instances.iter()
     }
 
+    /// Returns all instances in the given order; `seed` is only used by [`InstanceOrder::Random`]
+    pub fn into_ordered(self, order: InstanceOrder, seed: u64) -> Vec<Instance> {
+        let mut instances: Vec<_> = self.instances.into_iter().collect();
+
+        match order {
+            InstanceOrder::Path => instances.sort_unstable_by(|a, b| a.path.cmp(&b.path)),
+            InstanceOrder::Insertion => instances.sort_unstable_by_key(|i| i.insertion_index),
+            InstanceOrder::Size | InstanceOrder::SizeDesc => {
+                // instances that cannot be stat'ed come last; ties are broken by path
+                let descending = order == InstanceOrder::SizeDesc;
+                instances.sort_by_cached_key(|i| {
+                    let size = i.file_size();
+                    let key = size.map(|s| if descending { u64::MAX - s } else { s });
+                    (key.is_none(), key, i.path.clone())
+                });
+            }
+            InstanceOrder::Random => {
+                // shuffle a canonical order, so that the seed alone determines the result
+                instances.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+                instances.shuffle(&mut StdRng::seed_from_u64(seed));
+            }
+        }
+
+        instances
+    }
+
     /// Constructs a unique name `filestem_parent_parent_parent...` where a minimal
     /// number of parents is select; if a complete traversal of parents does not yet
     /// yield a unique name, a number suffix is added using [`Instances::unique_by_counter`]
     fn unique_name_from_path(&mut self, mut path: &Path) -> String {
-        let mut name = if let Some(stem) = path.file_stem() {
+        let mut name = if let Some(stem) = instance_stem(path) {
             String::from(stem.to_string_lossy())
         } else {
             return self.unique_by_counter("unnamed");
@@ -198,6 +341,31 @@ impl Instances {
         }
     }
 
+    /// Constructs the name `filestem_hash` where hash is derived from the canonical path.
+    /// Hence, the name does not depend on the insertion order or other instances.
+    fn stable_name_from_path(&mut self, path: &Path) -> String {
+        let stem = instance_stem(path).map_or_else(|| "unnamed".into(), |s| s.to_string_lossy());
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let name = format!(
+            "{stem}_{:08x}",
+            fnv1a_hash(canonical.as_os_str().as_encoded_bytes()) as u32
+        );
+
+        if self.names.insert(name.clone()) {
+            self.stable_name_paths.insert(name.clone(), canonical);
+            return name;
+        }
+
+        // the same file may be given by different paths, e.g. `a.in` and `./a.in`
+        if self.stable_name_paths.get(&name) == Some(&canonical) {
+            debug!("{path:?} was already inserted by another path as {name}; fall back to counter");
+        } else {
+            warn!("Hash collision for stable name {name}; fall back to counter");
+        }
+        self.unique_by_counter(&name)
+    }
+
     /// Constructs a unique name by adding a numeric suffix `-i` where i>2 is the
     /// smallest possible choice
     fn unique_by_counter(&mut self, name_prefix: &str) -> String {
@@ -211,16 +379,137 @@ impl Instances {
     }
 }
 
+/// Yields the instances sorted by path
 impl IntoIterator for Instances {
     type Item = Instance;
 
-    type IntoIter = <HashSet<Instance> as IntoIterator>::IntoIter;
+    type IntoIter = std::vec::IntoIter<Instance>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.instances.into_iter()
+        self.into_ordered(InstanceOrder::Path, 0).into_iter()
+    }
+}
+
+/// Opens a list file (transparently decompressing `.gz`) and returns a reader together with
+/// the directory its relative entries are resolved against
+fn open_list_file(path: &Path) -> Result<(Box<dyn BufRead>, PathBuf), InstancesError> {
+    let file = File::open(path)?;
+    let canon_path = path.canonicalize()?;
+    let relative_to = canon_path
+        .parent()
+        .expect("Parent needs to exists, since path is canonical")
+        .to_path_buf();
+
+    let reader: Box<dyn BufRead> = if has_extension(path, "gz") {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    Ok((reader, relative_to))
+}
+
+/// Yields the paths named by the lines of a list file; comments and empty lines are skipped
+/// and glob patterns are expanded
+fn list_entries(reader: impl BufRead, relative_to: &Path) -> impl Iterator<Item = PathBuf> {
+    reader.lines().flat_map(move |line| {
+        let Ok(line) = line else {
+            return Vec::new();
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with("#") {
+            return Vec::new();
+        }
+
+        let canonical = if line.starts_with('/') {
+            PathBuf::from(line)
+        } else if let Some(c) = normalize_path(&relative_to.join(line)) {
+            c
+        } else {
+            warn!("Failed to canonicalize line `{line}`");
+            return Vec::new();
+        };
+
+        if let Some(pattern) = canonical.to_str()
+            && (pattern.contains('*') || pattern.contains('?'))
+        {
+            // treat as glob string
+            debug!("glob {pattern}");
+            match glob::glob(pattern) {
+                Ok(paths) => paths.filter_map(|p| p.ok()).collect(),
+                Err(e) => {
+                    warn!("Pattern error: {e}");
+                    Vec::new()
+                }
+            }
+        } else {
+            vec![canonical]
+        }
+    })
+}
+
+/// Adds the normalized paths named by the exclusion list `path` to `excluded`
+fn collect_exclusions(path: &Path, excluded: &mut HashSet<PathBuf>) -> Result<(), InstancesError> {
+    let (reader, relative_to) = open_list_file(path)?;
+    for entry in list_entries(reader, &relative_to) {
+        if is_list_file(&entry) {
+            collect_exclusions(&entry, excluded)?;
+        } else if let Some(normalized) = normalize_path(&entry) {
+            excluded.insert(normalized);
+        }
+    }
+
+    Ok(())
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some(ext)
+}
+
+/// Returns `true` for paths ending in `.{ext}` or `.{ext}.gz` (a gzip-compressed instance)
+fn has_instance_extension(path: &Path, ext: &str) -> bool {
+    if has_extension(path, "gz") {
+        path.file_stem()
+            .is_some_and(|stem| has_extension(Path::new(stem), ext))
+    } else {
+        has_extension(path, ext)
     }
 }
 
+/// Returns the file stem of `path` after stripping the `.gz` extension of compressed instances,
+/// so that `tiny01.gr.gz` and `tiny01.gr` share the stem `tiny01`
+fn instance_stem(path: &Path) -> Option<&OsStr> {
+    let stem = path.file_stem()?;
+    if is_gzipped(path) {
+        Path::new(stem).file_stem()
+    } else {
+        Some(stem)
+    }
+}
+
+/// Returns `true` for paths ending in `.lst` or `.lst.gz`
+fn is_list_file(path: &Path) -> bool {
+    has_instance_extension(path, "lst")
+}
+
+/// 64-bit FNV-1a hash; in contrast to std's hashers it is guaranteed to be stable across versions
+pub(crate) fn fnv1a_hash(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    data.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Resolves `path` against the current working directory and normalizes it (see [`normalize_path`]),
+/// so that relative and absolute spellings of the same path compare equal
+pub fn absolute_path(path: &Path) -> Option<PathBuf> {
+    normalize_path(&std::env::current_dir().ok()?.join(path))
+}
+
 fn normalize_path(path: &Path) -> Option<PathBuf> {
     let mut stack: Vec<_> = Vec::new();
 
@@ -250,34 +539,58 @@ fn normalize_path(path: &Path) -> Option<PathBuf> {
     Some(out)
 }
 
-fn scan_for_idigest(file: &Path) -> Result<Option<InstanceDigest>, InstancesError> {
+/// Returns the idigest stated by the `#s idigest` line of the instance at `path`, if any
+pub fn read_idigest(path: &Path) -> Option<InstanceDigest> {
+    scan_header(path).ok().and_then(|(idigest, _)| idigest)
+}
+
+/// Scans the lines preceding the first tree for the instance's idigest and its `#p` header
+fn scan_header(
+    file: &Path,
+) -> Result<(Option<InstanceDigest>, Option<InstanceSize>), InstancesError> {
     // TODO: I used a very simplistic parser here; we might want to switch to the generic
     // visitor pattern at some point; benchmark!
-    let reader = BufReader::new(File::open(file)?);
+    let reader = open_instance(file)?;
+    let mut idigest = None;
 
     for line in reader.lines() {
         let Ok(line) = line else { continue };
         let content = line.trim_start();
 
-        if content.starts_with("#s idigest ") {
-            let digest = content["#p idigest ".len()..].trim();
-            let idigest: InstanceDigest = serde_json::from_str(digest)?;
-            return Ok(Some(idigest));
+        if let Some(digest) = content.strip_prefix("#s idigest ") {
+            let digest = digest.trim();
+            idigest = Some(serde_json::from_str(digest)?);
+            continue;
         }
 
-        if content.starts_with("#p") || content.starts_with('(') {
+        if let Some(header) = content.strip_prefix("#p") {
             // by convention the idigest has to appear before the header line
+            return Ok((idigest, parse_size(header)));
+        }
+
+        if content.starts_with('(') {
             break;
         }
     }
 
-    Ok(None)
+    Ok((idigest, None))
+}
+
+fn parse_size(header: &str) -> Option<InstanceSize> {
+    let mut fields = header.split_whitespace().map(str::parse::<u32>);
+    let num_trees = fields.next()?.ok()?;
+    let num_leaves = fields.next()?.ok()?;
+    Some(InstanceSize {
+        num_trees,
+        num_leaves,
+    })
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::test_helpers::*;
+    use std::collections::HashMap;
     use tracing_test::traced_test;
 
     #[test]
@@ -314,6 +627,130 @@ mod test {
         assert_eq!(names.len(), 2, "{names:?}");
     }
 
+    #[test]
+    fn test_retain() {
+        let mut instances = Instances::default();
+        for p in ["/data/a.in", "/data/b.in", "/data/c.gr"] {
+            instances.insert_instace_by_path(PathBuf::from(p));
+        }
+
+        let removed = instances.retain(|i| i.path().extension().is_some_and(|e| e == "in"));
+        assert_eq!(removed, 1);
+        assert_eq!(instances.len(), 2);
+    }
+
+    #[test]
+    fn test_into_ordered() {
+        let paths = ["/data/c.in", "/data/a.in", "/data/b.in"];
+        let instances = || {
+            let mut instances = Instances::default();
+            for p in paths {
+                instances.insert_instace_by_path(PathBuf::from(p));
+            }
+            instances
+        };
+        let ordered = |order, seed| {
+            instances()
+                .into_ordered(order, seed)
+                .into_iter()
+                .map(|i| i.path.display().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ordered(InstanceOrder::Path, 0),
+            ["/data/a.in", "/data/b.in", "/data/c.in"]
+        );
+        assert_eq!(ordered(InstanceOrder::Insertion, 0), paths);
+        assert_eq!(
+            ordered(InstanceOrder::Random, 5),
+            ordered(InstanceOrder::Random, 5)
+        );
+
+        // the files do not exist, so the sizes are unknown and only the path decides
+        assert_eq!(
+            ordered(InstanceOrder::SizeDesc, 0),
+            ordered(InstanceOrder::Path, 0)
+        );
+
+        let mut shuffled = ordered(InstanceOrder::Random, 7);
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, ordered(InstanceOrder::Path, 0));
+    }
+
+    #[test]
+    fn test_stable_names() {
+        let path1 = PathBuf::from("/home/user/data/file.txt");
+        let path2 = PathBuf::from("/home/user/other/file.txt");
+
+        let names = |paths: &[&PathBuf]| {
+            let mut instances = Instances::default();
+            instances.set_stable_names(true);
+            for p in paths {
+                instances.insert_instace_by_path(p.to_path_buf());
+            }
+            instances
+                .into_iter()
+                .map(|i| (i.path, i.name))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let both = names(&[&path1, &path2]);
+        let reversed = names(&[&path2, &path1]);
+        let single = names(&[&path2]);
+
+        assert_ne!(both[&path1], both[&path2]);
+        assert_eq!(both, reversed);
+        assert_eq!(both[&path2], single[&path2]);
+        assert!(both[&path1].starts_with("file_"));
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_stable_names_of_repeated_paths() {
+        let tempdir = tempdir::TempDir::new("stable_names").unwrap();
+        let path = tempdir.path().join("file.in");
+        std::fs::write(&path, "").unwrap();
+
+        let mut instances = Instances::default();
+        instances.set_stable_names(true);
+        assert!(instances.insert_instace_by_path(path.clone()));
+        assert!(!instances.insert_instace_by_path(path.clone()));
+
+        // another spelling of the same file
+        std::fs::create_dir(tempdir.path().join("sub")).unwrap();
+        let dotted = tempdir.path().join("sub/../file.in");
+        assert!(instances.insert_instace_by_path(dotted.clone()));
+
+        let names = instances
+            .into_iter()
+            .map(|i| (i.path, i.name))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(names[&dotted], format!("{}-2", names[&path]));
+        assert!(!logs_contain("Hash collision"));
+    }
+
+    #[test]
+    fn test_gzipped_names() {
+        let names = |stable_names| {
+            let mut instances = Instances::default();
+            instances.set_stable_names(stable_names);
+            instances.insert_instace_by_path(PathBuf::from("/data/tiny01.gr.gz"));
+            instances.insert_instace_by_path(PathBuf::from("/data/tiny02.gr"));
+            instances
+                .into_ordered(InstanceOrder::Insertion, 0)
+                .into_iter()
+                .map(|i| i.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(false), ["tiny01", "tiny02"]);
+
+        let stable = names(true);
+        assert!(stable[0].starts_with("tiny01_"), "{stable:?}");
+        assert!(stable[1].starts_with("tiny02_"), "{stable:?}");
+    }
+
     #[test]
     #[traced_test]
     fn test_insert_from_list_files_only() {
@@ -366,6 +803,28 @@ mod test {
         assert!(instances.len() > 3);
     }
 
+    #[test]
+    #[traced_test]
+    fn test_insert_from_gzipped_list_file() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let tempdir = tempdir::TempDir::new("gz_list").unwrap();
+        let list_path = tempdir.path().join("test.lst.gz");
+        assert!(is_list_file(&list_path));
+
+        let mut encoder = GzEncoder::new(File::create(&list_path).unwrap(), Compression::default());
+        for name in ["valid", "busywait", "idlewait"] {
+            let instance = test_testcases_dir().join(format!("test_solver_valid/{name}.in"));
+            writeln!(encoder, "{}", instance.display()).unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let mut instances = Instances::default();
+        instances.parse_and_insert_path(&list_path).unwrap();
+        assert_eq!(instances.len(), 3);
+    }
+
     #[test]
     #[traced_test]
 
@@ -376,4 +835,121 @@ mod test {
             .unwrap();
         assert!(instances.len() > 3, "{instances:?}");
     }
+
+    #[test]
+    fn test_exclude_from_list_file() {
+        let tempdir = tempdir::TempDir::new("exclude").unwrap();
+        let dir = tempdir.path().canonicalize().unwrap();
+        for name in ["a.in", "b.in", "c1.in", "c2.in"] {
+            File::create(dir.join(name)).unwrap();
+        }
+
+        let excl_path = dir.join("broken.excl");
+        std::fs::write(&excl_path, "# known to be broken\nb.in\nsub/../c*.in\n").unwrap();
+
+        let mut instances = Instances::default();
+        for name in ["a.in", "b.in", "c1.in", "c2.in"] {
+            instances.insert_instace_by_path(dir.join(".").join(name));
+        }
+
+        assert_eq!(instances.exclude_from_list_file(&excl_path).unwrap(), 3);
+        let names: Vec<_> = instances.iter().map(|i| i.name()).collect();
+        assert_eq!(names, ["a"]);
+    }
+
+    #[test]
+    fn test_order_by_file_size() {
+        let tempdir = tempdir::TempDir::new("file_size").unwrap();
+        let mut instances = Instances::default();
+        for (name, len) in [("a.in", 30), ("b.in", 10), ("c.in", 20)] {
+            let path = tempdir.path().join(name);
+            std::fs::write(&path, vec![b'x'; len]).unwrap();
+            instances.insert_instace_by_path(path);
+        }
+        instances.insert_instace_by_path(tempdir.path().join("missing.in"));
+
+        let names = |order| {
+            instances
+                .clone()
+                .into_ordered(order, 0)
+                .iter()
+                .map(|i| i.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(InstanceOrder::Size), ["b", "c", "a", "missing"]);
+        assert_eq!(names(InstanceOrder::SizeDesc), ["a", "c", "b", "missing"]);
+    }
+
+    #[test]
+    fn test_scan_header_size() {
+        let (_, size) =
+            scan_header(&test_testcases_dir().join("test_solver_valid/valid.in")).unwrap();
+        assert_eq!(
+            size,
+            Some(InstanceSize {
+                num_trees: 2,
+                num_leaves: 6
+            })
+        );
+
+        assert_eq!(parse_size(" 2"), None);
+        assert_eq!(parse_size(" x 5"), None);
+    }
+
+    #[test]
+    fn test_insert_from_directory() {
+        let dir = test_testcases_dir();
+
+        let mut flat = Instances::default();
+        flat.insert_from_directory_with_depth(
+            &dir.join("test_solver_valid"),
+            &["in"],
+            Some(0),
+            false,
+        )
+        .unwrap();
+        assert!(flat.len() > 3);
+        assert!(flat.iter().all(|i| has_extension(i.path(), "in")));
+
+        let mut recursive = Instances::default();
+        recursive.insert_from_directory(&dir, &["in"]).unwrap();
+        assert!(recursive.len() > flat.len());
+    }
+
+    #[test]
+    fn test_parse_and_insert_directory() {
+        let tempdir = tempdir::TempDir::new("instance_dir").unwrap();
+        let dir = tempdir.path();
+        std::fs::create_dir_all(dir.join("sub/.hidden")).unwrap();
+        for name in [
+            "a.gr",
+            "b.in",
+            "c.out",
+            ".d.in",
+            "sub/e.in",
+            "sub/.hidden/f.in",
+        ] {
+            File::create(dir.join(name)).unwrap();
+        }
+        // a symlink loop must not be followed forever
+        std::os::unix::fs::symlink(dir, dir.join("sub/loop")).unwrap();
+
+        let mut instances = Instances::default();
+        instances.parse_and_insert_path(dir).unwrap();
+        let mut names: Vec<_> = instances.iter().map(|i| i.name().to_owned()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["a", "b", "e"]);
+
+        // hidden files are only taken on request (e.g., by `stride check`)
+        let mut instances = Instances::default();
+        instances
+            .insert_from_directory_with_depth(dir, &["in"], None, false)
+            .unwrap();
+        assert_eq!(instances.len(), 4);
+
+        let mut instances = Instances::default();
+        instances.set_directory_extensions(vec!["out".into()]);
+        instances.parse_and_insert_path(dir).unwrap();
+        assert_eq!(instances.len(), 1);
+    }
 }
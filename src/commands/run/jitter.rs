@@ -0,0 +1,86 @@
+use rand::Rng;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Varies the number of concurrently running solvers (see `--parallel-jitter`). Every `period`,
+/// a new target in `[min, max]` is drawn uniformly at random; the jitter then withholds
+/// `max - target` permits of the semaphore limiting the parallelism. Withheld permits are
+/// returned once the jitter is dropped.
+pub struct ParallelismJitter {
+    withheld: Arc<AtomicUsize>,
+    join_handle: JoinHandle<()>,
+}
+
+impl ParallelismJitter {
+    /// `semaphore` is expected to have `max` permits
+    pub fn start(semaphore: Arc<Semaphore>, min: usize, max: usize, period: Duration) -> Self {
+        assert!(min <= max);
+
+        let withheld = Arc::new(AtomicUsize::new(0));
+        let join_handle = {
+            let withheld = withheld.clone();
+            tokio::spawn(async move {
+                let mut held = Vec::with_capacity(max - min);
+
+                loop {
+                    let target = rand::rng().random_range(min..=max);
+                    debug!("Parallelism jitter: target {target} concurrent solvers");
+
+                    held.truncate(max - target);
+                    withheld.store(held.len(), Ordering::Release);
+
+                    // permits of running solvers become available once they complete
+                    while held.len() < max - target {
+                        let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                            return;
+                        };
+                        held.push(permit);
+                        withheld.store(held.len(), Ordering::Release);
+                    }
+
+                    tokio::time::sleep(period).await;
+                }
+            })
+        };
+
+        Self {
+            withheld,
+            join_handle,
+        }
+    }
+
+    /// Number of permits currently withheld from the solvers
+    pub fn withheld(&self) -> usize {
+        self.withheld.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for ParallelismJitter {
+    fn drop(&mut self) {
+        self.join_handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn withheld_permits_are_returned() {
+        let semaphore = Arc::new(Semaphore::new(4));
+        let jitter = ParallelismJitter::start(semaphore.clone(), 1, 4, Duration::from_secs(60));
+
+        // no solver holds a permit, so the jitter instantly withholds `4 - target` of them
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(jitter.withheld() <= 3);
+        assert_eq!(jitter.withheld() + semaphore.available_permits(), 4);
+
+        drop(jitter);
+        let all = tokio::time::timeout(Duration::from_secs(5), semaphore.acquire_many(4)).await;
+        assert!(all.is_ok());
+    }
+}
@@ -0,0 +1,136 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::trace;
+
+/// Key of the stride line by which solvers report intermediate scores on stderr (`#s score N`)
+const LIVE_SCORE_KEY: &str = "score";
+
+/// Upper bound on the bytes read per poll, so that a chatty solver cannot stall the runner
+const MAX_BYTES_PER_POLL: u64 = 1 << 20;
+
+/// Minimal time between two reads of the stderr file; polls in between report the latest score
+pub const LIVE_SCORE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Follows the stderr file of a running solver and extracts the latest score it reported
+/// (see `--live-score`)
+pub struct LiveScoreTail {
+    path: PathBuf,
+    poll_interval: Duration,
+    last_read: Option<Instant>,
+    offset: u64,
+    partial_line: Vec<u8>,
+    latest: Option<u32>,
+}
+
+impl LiveScoreTail {
+    pub fn new(path: PathBuf, poll_interval: Duration) -> Self {
+        Self {
+            path,
+            poll_interval,
+            last_read: None,
+            offset: 0,
+            partial_line: Vec::new(),
+            latest: None,
+        }
+    }
+
+    /// Reads the lines appended since the last read (if it is older than the poll interval) and
+    /// returns the latest reported score
+    pub async fn poll(&mut self) -> Option<u32> {
+        if self
+            .last_read
+            .is_none_or(|last_read| last_read.elapsed() >= self.poll_interval)
+        {
+            self.last_read = Some(Instant::now());
+            if let Err(e) = self.read_new_lines().await {
+                trace!("Failed to follow {:?}: {e}", self.path);
+            }
+        }
+        self.latest
+    }
+
+    async fn read_new_lines(&mut self) -> std::io::Result<()> {
+        let mut file = File::open(&self.path).await?;
+        file.seek(SeekFrom::Start(self.offset)).await?;
+
+        let mut buffer = Vec::new();
+        let num_read = file
+            .take(MAX_BYTES_PER_POLL)
+            .read_to_end(&mut buffer)
+            .await?;
+        self.offset += num_read as u64;
+
+        for byte in buffer {
+            if byte != b'\n' {
+                self.partial_line.push(byte);
+                continue;
+            }
+
+            if let Some(score) = parse_score_line(&self.partial_line) {
+                self.latest = Some(score);
+            }
+            self.partial_line.clear();
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_score_line(line: &[u8]) -> Option<u32> {
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.trim().strip_prefix("#s")?.split_whitespace();
+    if parts.next()? != LIVE_SCORE_KEY {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn follows_appended_scores() {
+        let tempdir = tempdir::TempDir::new("live_score").unwrap();
+        let path = tempdir.path().join("stderr");
+
+        let mut tail = LiveScoreTail::new(path.clone(), Duration::ZERO);
+        assert_eq!(tail.poll().await, None); // file does not exist yet
+
+        let mut file = std::fs::File::create(&path).unwrap();
+        write!(file, "log line\n#s score 12\n#s score 1").unwrap();
+        assert_eq!(tail.poll().await, Some(12));
+
+        // the incomplete line is only taken once it is terminated
+        write!(file, "0\n#s other 3\n").unwrap();
+        assert_eq!(tail.poll().await, Some(10));
+        assert_eq!(tail.poll().await, Some(10));
+    }
+
+    #[tokio::test]
+    async fn rate_limited() {
+        let tempdir = tempdir::TempDir::new("live_score").unwrap();
+        let path = tempdir.path().join("stderr");
+        std::fs::write(&path, "#s score 12\n").unwrap();
+
+        let mut tail = LiveScoreTail::new(path.clone(), Duration::from_secs(3600));
+        assert_eq!(tail.poll().await, Some(12));
+
+        // not read before the interval elapsed
+        std::fs::write(&path, "#s score 12\n#s score 10\n").unwrap();
+        assert_eq!(tail.poll().await, Some(12));
+    }
+
+    #[test]
+    fn score_lines() {
+        assert_eq!(parse_score_line(b"#s score 7"), Some(7));
+        assert_eq!(parse_score_line(b"#s  score   7  "), Some(7));
+        assert_eq!(parse_score_line(b"#s scores 7"), None);
+        assert_eq!(parse_score_line(b"score 7"), None);
+        assert_eq!(parse_score_line(b"#s score x"), None);
+    }
+}
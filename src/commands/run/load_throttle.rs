@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const LOADAVG_PATH: &str = "/proc/loadavg";
+
+/// The load average is smoothed by the kernel anyway, so there is no point in reading it more often
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pauses the dispatch of new solvers while the 1-minute load average of the machine
+/// exceeds a threshold (see `--max-loadavg`); running solvers are not affected
+pub struct LoadThrottle {
+    max_loadavg: f64,
+    path: PathBuf,
+    last_poll: Option<Instant>,
+    paused: bool,
+}
+
+impl LoadThrottle {
+    pub fn new(max_loadavg: f64) -> Self {
+        Self::with_path(max_loadavg, PathBuf::from(LOADAVG_PATH))
+    }
+
+    fn with_path(max_loadavg: f64, path: PathBuf) -> Self {
+        Self {
+            max_loadavg,
+            path,
+            last_poll: None,
+            paused: false,
+        }
+    }
+
+    /// Returns true if no new solver should be started at the moment
+    pub fn is_paused(&mut self) -> bool {
+        if self
+            .last_poll
+            .is_some_and(|last| last.elapsed() < POLL_INTERVAL)
+        {
+            return self.paused;
+        }
+
+        let is_first_poll = self.last_poll.is_none();
+        self.last_poll = Some(Instant::now());
+
+        let loadavg = match std::fs::read_to_string(&self.path).map(|s| parse_loadavg(&s)) {
+            Ok(Some(loadavg)) => loadavg,
+            Ok(None) | Err(_) => {
+                // never block the run because the load cannot be determined
+                if is_first_poll {
+                    warn!(
+                        "Cannot read load average from {:?}; ignore --max-loadavg",
+                        self.path
+                    );
+                }
+                self.paused = false;
+                return false;
+            }
+        };
+
+        let paused = loadavg > self.max_loadavg;
+        if paused != self.paused {
+            if paused {
+                info!(
+                    "Load average {loadavg:.2} exceeds {:.2}; pause starting new solvers",
+                    self.max_loadavg
+                );
+            } else {
+                info!("Load average dropped to {loadavg:.2}; resume starting new solvers");
+            }
+        }
+
+        self.paused = paused;
+        paused
+    }
+}
+
+/// Extracts the 1-minute load average from the contents of `/proc/loadavg`
+fn parse_loadavg(contents: &str) -> Option<f64> {
+    contents.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loadavg() {
+        assert_eq!(parse_loadavg("0.52 0.58 0.59 1/467 12345\n"), Some(0.52));
+        assert_eq!(parse_loadavg(""), None);
+        assert_eq!(parse_loadavg("x 0.58"), None);
+    }
+
+    #[test]
+    fn pauses_above_threshold() {
+        let tempdir = tempdir::TempDir::new("load_throttle").unwrap();
+        let path = tempdir.path().join("loadavg");
+
+        std::fs::write(&path, "8.50 3.00 1.00 1/467 12345\n").unwrap();
+        let mut throttle = LoadThrottle::with_path(4.0, path.clone());
+        assert!(throttle.is_paused());
+
+        // the load is only re-read after the poll interval
+        std::fs::write(&path, "1.00 3.00 1.00 1/467 12345\n").unwrap();
+        assert!(throttle.is_paused());
+        throttle.last_poll = None;
+        assert!(!throttle.is_paused());
+
+        // an unreadable load average never pauses the run
+        let mut throttle = LoadThrottle::with_path(4.0, tempdir.path().join("missing"));
+        assert!(!throttle.is_paused());
+    }
+}
@@ -1,6 +1,24 @@
+pub mod bug_bundle;
 pub mod command;
+pub mod compare;
+pub mod control;
 pub mod display;
+pub mod estimate;
+pub mod fingerprint;
+pub mod histogram;
 pub mod instances;
+pub mod jitter;
+pub mod live_score;
+pub mod load_throttle;
+pub mod optimal_db;
+pub mod probe;
+pub mod repeat;
+pub mod report;
+pub mod resume;
+pub mod reuse;
+pub mod rotating_log;
+pub mod solver_profiles;
 pub use command::*;
 pub mod summary_writer;
+pub mod tui;
 pub mod upload;
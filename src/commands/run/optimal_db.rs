@@ -0,0 +1,119 @@
+use pace26checker::digest::digest_output::InstanceDigest;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OptimalDbError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid idigest {0:?}")]
+    Idigest(String),
+}
+
+/// An entry of the database; without `optimal`, the score is taken as a proven optimum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Optimum(u64),
+    Detailed {
+        score: u64,
+        #[serde(default = "default_optimal")]
+        optimal: bool,
+    },
+}
+
+fn default_optimal() -> bool {
+    true
+}
+
+/// User-maintained scores per idigest (see `--optimal-db`). Only scores marked as optimal are
+/// treated as authoritative; other entries are merely the best scores seen so far and do not
+/// influence `s_is_optimal` or `--skip-known-optimal`.
+#[derive(Debug, Default)]
+pub struct OptimalDb {
+    optima: HashMap<InstanceDigest, u64>,
+    num_best_seen: usize,
+}
+
+impl OptimalDb {
+    /// Reads a JSON object mapping idigests either to their optimal score or to an object
+    /// `{"score": N, "optimal": false}`
+    pub fn from_file(path: &Path) -> Result<Self, OptimalDbError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    fn parse(content: &str) -> Result<Self, OptimalDbError> {
+        let entries: HashMap<String, Entry> = serde_json::from_str(content)?;
+
+        let mut db = Self::default();
+        for (idigest, entry) in entries {
+            let idigest: InstanceDigest = idigest
+                .as_str()
+                .try_into()
+                .map_err(|_| OptimalDbError::Idigest(idigest.clone()))?;
+
+            match entry {
+                Entry::Optimum(score)
+                | Entry::Detailed {
+                    score,
+                    optimal: true,
+                } => {
+                    db.optima.insert(idigest, score);
+                }
+                Entry::Detailed { optimal: false, .. } => db.num_best_seen += 1,
+            }
+        }
+
+        Ok(db)
+    }
+
+    /// Returns the proven optimal score of the instance, if known
+    pub fn optimum(&self, idigest: &InstanceDigest) -> Option<u64> {
+        self.optima.get(idigest).copied()
+    }
+
+    pub fn num_optima(&self) -> usize {
+        self.optima.len()
+    }
+
+    /// Number of entries that are not marked as optimal
+    pub fn num_best_seen(&self) -> usize {
+        self.num_best_seen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entries() {
+        let db = OptimalDb::parse(
+            r#"{
+                "00000000000000000000000000000001": 5,
+                "00000000000000000000000000000002": {"score": 7},
+                "00000000000000000000000000000003": {"score": 9, "optimal": false}
+            }"#,
+        )
+        .unwrap();
+
+        let optimum = |s: &str| db.optimum(&InstanceDigest::try_from(s).unwrap());
+        assert_eq!(optimum("00000000000000000000000000000001"), Some(5));
+        assert_eq!(optimum("00000000000000000000000000000002"), Some(7));
+        assert_eq!(optimum("00000000000000000000000000000003"), None);
+        assert_eq!(optimum("00000000000000000000000000000004"), None);
+        assert_eq!(db.num_optima(), 2);
+        assert_eq!(db.num_best_seen(), 1);
+
+        assert!(matches!(
+            OptimalDb::parse(r#"{"xyz": 5}"#),
+            Err(OptimalDbError::Idigest(_))
+        ));
+    }
+}
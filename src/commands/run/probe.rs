@@ -0,0 +1,94 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+use tracing::warn;
+
+/// Time budget of the probe; it is independent of the instance timeouts
+const PROBE_SOLVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs the solver once with `flags` (see `--probe-solver`) and returns its trimmed stdout,
+/// e.g. the solver's self-reported version. Failures are logged and yield `None`.
+pub async fn probe_solver(solver: &Path, flags: &str) -> Option<String> {
+    let args = match shell_words::split(flags) {
+        Ok(args) => args,
+        Err(e) => {
+            warn!("Failed to parse probe flags `{flags}`: {e}");
+            return None;
+        }
+    };
+
+    let output = Command::new(solver)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+
+    match timeout(PROBE_SOLVER_TIMEOUT, output).await {
+        Ok(Ok(output)) => {
+            if !output.status.success() {
+                warn!("Solver probe exited with {}", output.status);
+            }
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(Err(e)) => {
+            warn!("Failed to probe solver {solver:?}: {e}");
+            None
+        }
+        Err(_) => {
+            warn!("Solver probe did not complete within {PROBE_SOLVER_TIMEOUT:?}");
+            None
+        }
+    }
+}
+
+/// Checks that `profiler` exists and accepts the hidden `p` subcommand of `stride`, by
+/// running `{profiler} p --help`. Returns a description of the problem otherwise.
+pub async fn verify_profiler(profiler: &Path) -> Result<(), String> {
+    if !profiler.is_file() {
+        return Err("file does not exist".into());
+    }
+
+    let status = Command::new(profiler)
+        .args(["p", "--help"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status();
+
+    match timeout(PROBE_SOLVER_TIMEOUT, status).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("`p --help` exited with {status}")),
+        Ok(Err(e)) => Err(format!("failed to execute: {e}")),
+        Err(_) => Err(format!(
+            "`p --help` did not complete within {PROBE_SOLVER_TIMEOUT:?}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn probe() {
+        let version = probe_solver(Path::new("/bin/echo"), "'solver 1.2' --version").await;
+        assert_eq!(version.as_deref(), Some("solver 1.2 --version"));
+
+        assert!(
+            probe_solver(Path::new("/nonexistent"), "--version")
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn profiler() {
+        assert!(verify_profiler(Path::new("/nonexistent")).await.is_err());
+        assert!(verify_profiler(Path::new("/bin/false")).await.is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use pace26remote::job_description::JobDescription;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::commands::arguments::Objective;
+use crate::job::job_processor::JobResult;
+
+/// Collects the outcomes of all repetitions of an instance (see `--repeat`)
+pub struct RepeatAggregator {
+    num_repeats: usize,
+    objective: Objective,
+    pending: Mutex<HashMap<String, RepeatResults>>,
+}
+
+#[derive(Default)]
+struct RepeatResults {
+    num_completed: usize,
+    num_valid: usize,
+    best_score: Option<usize>,
+    runtimes: Vec<Duration>,
+    upload_desc: Option<(JobResult, JobDescription)>,
+}
+
+/// Aggregated outcome of all repetitions of an instance
+pub struct RepeatAggregate {
+    pub num_repeats: usize,
+    pub num_valid: usize,
+    pub best_score: Option<usize>,
+    pub median_runtime: Option<Duration>,
+    /// descriptor of the best repetition, i.e. the only one uploaded
+    pub upload_desc: Option<JobDescription>,
+}
+
+impl RepeatAggregator {
+    pub fn new(num_repeats: usize, objective: Objective) -> Self {
+        Self {
+            num_repeats,
+            objective,
+            pending: Default::default(),
+        }
+    }
+
+    /// Records the outcome of a single repetition of `instance_name`; returns the aggregate
+    /// once all repetitions of this instance completed
+    pub fn record(
+        &self,
+        instance_name: &str,
+        job_result: JobResult,
+        runtime: Option<Duration>,
+        upload_desc: Option<JobDescription>,
+    ) -> Option<RepeatAggregate> {
+        let mut pending = self.pending.lock().unwrap();
+        let results = pending.entry(instance_name.to_owned()).or_default();
+
+        results.num_completed += 1;
+        if let JobResult::Valid { size } = job_result {
+            results.num_valid += 1;
+            results.best_score = Some(results.best_score.map_or(size, |best| {
+                self.objective.best(best as u64, size as u64) as usize
+            }));
+        }
+        results.runtimes.extend(runtime);
+
+        if let Some(desc) = upload_desc
+            && results
+                .upload_desc
+                .as_ref()
+                .is_none_or(|(best, _)| self.is_better(job_result, *best))
+        {
+            results.upload_desc = Some((job_result, desc));
+        }
+
+        if results.num_completed < self.num_repeats {
+            return None;
+        }
+
+        let mut results = pending.remove(instance_name).unwrap();
+        results.runtimes.sort_unstable();
+
+        Some(RepeatAggregate {
+            num_repeats: self.num_repeats,
+            num_valid: results.num_valid,
+            best_score: results.best_score,
+            median_runtime: results.runtimes.get(results.runtimes.len() / 2).copied(),
+            upload_desc: results.upload_desc.map(|(_, desc)| desc),
+        })
+    }
+
+    /// Valid solutions (ranked by the objective) precede all other results
+    fn is_better(&self, a: JobResult, b: JobResult) -> bool {
+        match (a, b) {
+            (JobResult::Valid { size: a }, JobResult::Valid { size: b }) => {
+                self.objective.is_better(a as u64, b as u64)
+            }
+            (a, b) => a.is_valid() && !b.is_valid(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate() {
+        let aggregator = RepeatAggregator::new(3, Objective::Min);
+        let secs = |s| Some(Duration::from_secs(s));
+
+        assert!(
+            aggregator
+                .record("a", JobResult::Valid { size: 5 }, secs(3), None)
+                .is_none()
+        );
+        assert!(
+            aggregator
+                .record("b", JobResult::Timeout, secs(10), None)
+                .is_none()
+        );
+        assert!(
+            aggregator
+                .record("a", JobResult::Infeasible, secs(1), None)
+                .is_none()
+        );
+
+        let aggregate = aggregator
+            .record("a", JobResult::Valid { size: 4 }, secs(2), None)
+            .unwrap();
+
+        assert_eq!(aggregate.num_repeats, 3);
+        assert_eq!(aggregate.num_valid, 2);
+        assert_eq!(aggregate.best_score, Some(4));
+        assert_eq!(aggregate.median_runtime, secs(2));
+    }
+
+    #[test]
+    fn failed_repetitions_complete_the_aggregate() {
+        let aggregator = RepeatAggregator::new(2, Objective::Min);
+
+        // e.g. a repetition whose work dir could not be created
+        assert!(
+            aggregator
+                .record("a", JobResult::SystemError, None, None)
+                .is_none()
+        );
+        let aggregate = aggregator
+            .record(
+                "a",
+                JobResult::Valid { size: 4 },
+                Some(Duration::from_secs(2)),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(aggregate.num_repeats, 2);
+        assert_eq!(aggregate.num_valid, 1);
+        assert_eq!(aggregate.median_runtime, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn prefers_better_valid() {
+        let valid = |size| JobResult::Valid { size };
+
+        let aggregator = RepeatAggregator::new(3, Objective::Min);
+        assert!(aggregator.is_better(valid(3), valid(4)));
+        assert!(!aggregator.is_better(valid(4), valid(3)));
+        assert!(aggregator.is_better(valid(100), JobResult::Timeout));
+        assert!(!aggregator.is_better(JobResult::Timeout, JobResult::Infeasible));
+
+        let aggregator = RepeatAggregator::new(3, Objective::Max);
+        assert!(aggregator.is_better(valid(4), valid(3)));
+        assert!(aggregator.is_better(valid(1), JobResult::Timeout));
+    }
+}
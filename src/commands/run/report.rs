@@ -0,0 +1,134 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::commands::run::display::StrideCounts;
+use crate::job::job_processor::JobResult;
+
+pub const REPORT_FILENAME: &str = "report.json";
+
+/// Percentiles of the solver runtimes reported in [`RunReport::runtime_secs`]
+const RUNTIME_PERCENTILES: [(&str, f64); 4] =
+    [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("max", 1.0)];
+
+/// Accumulates the per-job data needed for the [`RunReport`]
+#[derive(Debug, Default)]
+pub struct ReportCollector {
+    results: BTreeMap<String, u64>,
+    runtimes: Vec<Duration>,
+}
+
+impl ReportCollector {
+    pub fn record(&mut self, job_result: JobResult, runtime: Option<Duration>) {
+        *self.results.entry(job_result.to_string()).or_default() += 1;
+        self.runtimes.extend(runtime);
+    }
+
+    pub fn into_report(
+        self,
+        started: DateTime<Local>,
+        uploads: Option<StrideCounts>,
+        config: serde_json::Value,
+        run_fingerprint: Option<String>,
+    ) -> RunReport {
+        let finished = Local::now();
+
+        let mut runtimes = self.runtimes;
+        runtimes.sort_unstable();
+        let runtime_secs = RUNTIME_PERCENTILES
+            .iter()
+            .filter_map(|&(name, p)| Some((name, percentile(&runtimes, p)?.as_secs_f64())))
+            .collect();
+
+        RunReport {
+            started: started.to_rfc3339(),
+            finished: finished.to_rfc3339(),
+            duration_secs: (finished - started).num_milliseconds() as f64 / 1000.0,
+            num_jobs: self.results.values().sum(),
+            results: self.results,
+            runtime_secs,
+            uploads,
+            config,
+            run_fingerprint,
+            solver_version: None,
+        }
+    }
+}
+
+/// Run-level overview written into `report.json` of the run directory; it complements the
+/// per-instance rows of `summary.json`
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    pub started: String,
+    pub finished: String,
+    pub duration_secs: f64,
+    pub num_jobs: u64,
+    /// number of jobs per `s_result`
+    pub results: BTreeMap<String, u64>,
+    /// percentiles of the solver runtimes
+    pub runtime_secs: BTreeMap<&'static str, f64>,
+    /// outcomes of the uploads to the STRIDE server; omitted if the run was offline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uploads: Option<StrideCounts>,
+    pub config: serde_json::Value,
+    /// hash over the solver binary, its arguments, timeouts, instances, and server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_fingerprint: Option<String>,
+    /// stdout of the solver when run with the flags of `--probe-solver`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solver_version: Option<String>,
+}
+
+impl RunReport {
+    pub fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+}
+
+/// Nearest-rank percentile of the ascendingly sorted durations
+fn percentile(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len()) - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let mut collector = ReportCollector::default();
+        for secs in 1..=10 {
+            collector.record(
+                JobResult::Valid { size: 3 },
+                Some(Duration::from_secs(secs)),
+            );
+        }
+        collector.record(JobResult::Timeout, None);
+
+        let report = collector.into_report(Local::now(), None, serde_json::json!({}), None);
+        assert_eq!(report.num_jobs, 11);
+        assert_eq!(report.results["Valid"], 10);
+        assert_eq!(report.results["Timeout"], 1);
+        assert_eq!(report.runtime_secs["p50"], 5.0);
+        assert_eq!(report.runtime_secs["p90"], 9.0);
+        assert_eq!(report.runtime_secs["max"], 10.0);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json.get("uploads").is_none());
+    }
+
+    #[test]
+    fn empty_report() {
+        let report =
+            ReportCollector::default().into_report(Local::now(), None, Default::default(), None);
+        assert_eq!(report.num_jobs, 0);
+        assert!(report.runtime_secs.is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::commands::run::instances::absolute_path;
+use crate::commands::run::summary_writer::{JSON_KEY_INSTANCE_PATH, JSON_KEY_JOB_RESULT};
+
+/// Reads the (normalized) paths of all instances recorded in a summary file (see `--resume`);
+/// with `valid_only`, only instances with a `Valid` row are returned. A missing file yields no
+/// paths, and malformed rows (e.g. a truncated last line of an interrupted run) are skipped.
+pub fn read_completed_paths(
+    summary_path: &Path,
+    valid_only: bool,
+) -> Result<HashSet<PathBuf>, std::io::Error> {
+    let file = match std::fs::File::open(summary_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            warn!("Summary {summary_path:?} to resume from does not exist; run all instances");
+            return Ok(HashSet::new());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut paths = HashSet::new();
+    let mut num_malformed = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&line) else {
+            num_malformed += 1;
+            continue;
+        };
+
+        if valid_only && row.get(JSON_KEY_JOB_RESULT).and_then(|v| v.as_str()) != Some("Valid") {
+            continue;
+        }
+
+        if let Some(path) = row
+            .get(JSON_KEY_INSTANCE_PATH)
+            .and_then(|v| v.as_str())
+            .and_then(|path| absolute_path(Path::new(path)))
+        {
+            paths.insert(path);
+        }
+    }
+
+    if num_malformed > 0 {
+        warn!("Skipped {num_malformed} malformed rows of {summary_path:?}");
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_completed_paths() {
+        let tempdir = tempdir::TempDir::new("resume").unwrap();
+        let path = tempdir.path().join("summary.json");
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"s_name\": \"a\", \"s_path\": \"/data/a.in\", \"s_result\": \"Valid\"}\n",
+                "{\"s_name\": \"b\", \"s_path\": \"/data/./b.in\", \"s_result\": \"Timeout\"}\n",
+                "{\"s_name\": \"c\", \"s_result\": \"Valid\"}\n",
+                "{\"s_name\": \"d\", \"s_path\": \"/data/d.in\", \"s_res",
+            ),
+        )
+        .unwrap();
+
+        let paths = read_completed_paths(&path, false).unwrap();
+        assert_eq!(
+            paths,
+            HashSet::from([PathBuf::from("/data/a.in"), PathBuf::from("/data/b.in")])
+        );
+
+        let paths = read_completed_paths(&path, true).unwrap();
+        assert_eq!(paths, HashSet::from([PathBuf::from("/data/a.in")]));
+
+        let missing = read_completed_paths(&tempdir.path().join("missing.json"), false).unwrap();
+        assert!(missing.is_empty());
+    }
+}
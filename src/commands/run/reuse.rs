@@ -0,0 +1,126 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::commands::run::summary_writer::{
+    JSON_KEY_INSTANCE_NAME, JSON_KEY_JOB_RESULT, JSON_KEY_REPEAT_INDEX, JSON_KEY_SOLUTION_SIZE,
+    JSON_KEY_SOLUTION_TREES,
+};
+use crate::job::check_and_extract::JSON_KEY_GAP;
+use crate::job::job_processor::{JSON_KEY_CHECK_KEY, JSON_KEY_STDOUT_SHA, JobResult, PriorVerdict};
+
+/// Reads the verdicts of the checker recorded in a summary file (see `--reuse-unchanged`),
+/// indexed by instance name. Only rows stating the digest of the solver's output and the check
+/// key are considered; repetitions are skipped, as their outputs differ in general. If an
+/// instance occurs multiple times, its last row is used.
+pub fn read_prior_verdicts(
+    summary_path: &Path,
+) -> Result<HashMap<String, PriorVerdict>, std::io::Error> {
+    let reader = BufReader::new(std::fs::File::open(summary_path)?);
+
+    let mut verdicts = HashMap::new();
+    for line in reader.lines() {
+        let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&line?) else {
+            continue;
+        };
+
+        if row.contains_key(JSON_KEY_REPEAT_INDEX) {
+            continue;
+        }
+
+        let Some(name) = row.get(JSON_KEY_INSTANCE_NAME).and_then(|v| v.as_str()) else {
+            continue;
+        };
+
+        if let Some(verdict) = prior_verdict(&row) {
+            verdicts.insert(name.to_string(), verdict);
+        }
+    }
+
+    Ok(verdicts)
+}
+
+fn prior_verdict(row: &Map<String, Value>) -> Option<PriorVerdict> {
+    let stdout_sha = row.get(JSON_KEY_STDOUT_SHA)?.as_str()?;
+    let check_key = row.get(JSON_KEY_CHECK_KEY)?.as_str()?;
+    let result = checker_verdict(
+        row.get(JSON_KEY_JOB_RESULT)?.as_str()?,
+        row.get(JSON_KEY_SOLUTION_SIZE).and_then(|v| v.as_u64()),
+    )?;
+
+    let trees = match row.get(JSON_KEY_SOLUTION_TREES) {
+        Some(Value::Array(trees)) => trees
+            .iter()
+            .map(|tree| tree.as_str().map(String::from))
+            .collect::<Option<Vec<_>>>()?,
+        _ => Vec::new(),
+    };
+
+    // infos derived from the solution: keys reported by the solver, stride lines of the
+    // instance, and the optimality gap; all other `s_*` keys are recomputed by the new run
+    let infos = row
+        .iter()
+        .filter(|(key, _)| !key.starts_with("s_") || *key == JSON_KEY_GAP)
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    Some(PriorVerdict {
+        stdout_sha: stdout_sha.into(),
+        check_key: check_key.into(),
+        result,
+        infos: (trees, infos),
+    })
+}
+
+/// Parses the results that are determined by the checker alone; results depending on the
+/// circumstances of the run (e.g. `SystemError`) are never reused
+fn checker_verdict(result: &str, score: Option<u64>) -> Option<JobResult> {
+    Some(match result {
+        "Valid" => JobResult::Valid {
+            size: score?.try_into().ok()?,
+        },
+        "Infeasible" => JobResult::Infeasible,
+        "InvalidInstance" => JobResult::InvalidInstance,
+        "EmptySolution" => JobResult::EmptySolution,
+        "SyntaxError" => JobResult::SyntaxError,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_reusable_rows() {
+        let tempdir = tempdir::TempDir::new("reuse").unwrap();
+        let path = tempdir.path().join("summary.json");
+        std::fs::write(
+            &path,
+            concat!(
+                "{\"s_name\": \"a\", \"s_result\": \"Valid\", \"s_score\": 3, \"s_solution_trees\": [\"(1,2);\"], \"s_stdout_sha\": \"aa\", \"s_check_key\": \"k\", \"s_gap\": 0.5, \"s_queue_wait_ms\": 3, \"i_optimal\": 2, \"foo\": \"bar\"}\n",
+                "{\"s_name\": \"b\", \"s_result\": \"SystemError\", \"s_stdout_sha\": \"bb\", \"s_check_key\": \"k\"}\n",
+                "{\"s_name\": \"c\", \"s_result\": \"Infeasible\", \"s_stdout_sha\": \"cc\"}\n",
+                "{\"s_name\": \"d\", \"s_result\": \"Infeasible\", \"s_stdout_sha\": \"dd\", \"s_check_key\": \"k\", \"s_repeat_index\": 0}\n",
+                "{\"s_name\": \"e\", \"s_result\": \"Valid\", \"s_score\": 3, \"s_stdout_sha\": \"e1\", \"s_check_key\": \"k\"}\n",
+                "{\"s_name\": \"e\", \"s_result\": \"Infeasible\", \"s_stdout_sha\": \"e2\", \"s_check_key\": \"k\"}\n",
+            ),
+        )
+        .unwrap();
+
+        let verdicts = read_prior_verdicts(&path).unwrap();
+        let mut names: Vec<_> = verdicts.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["a", "e"]);
+
+        let a = &verdicts["a"];
+        assert_eq!(a.result, JobResult::Valid { size: 3 });
+        assert_eq!(a.stdout_sha, "aa");
+        assert_eq!(a.infos.0, ["(1,2);"]);
+        let keys: Vec<_> = a.infos.1.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["s_gap", "i_optimal", "foo"]);
+
+        assert_eq!(verdicts["e"].result, JobResult::Infeasible);
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Log file that is rotated to `{path}.1`, `{path}.2`, ... once it exceeds `max_bytes`
+/// (see `--log-rotate`); only the `keep` most recent rotations are retained.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    pub fn create(path: &Path, max_bytes: u64, keep: usize) -> Result<Self, std::io::Error> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            keep,
+            file: File::create(path)?,
+            written: 0,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> Result<(), std::io::Error> {
+        self.file.flush()?;
+
+        if self.keep > 0 {
+            // the oldest rotation is overwritten by its successor
+            for index in (1..self.keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = File::create(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let bytes = self.file.write(buf)?;
+        self.written += bytes as u64;
+        Ok(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn rotation() {
+        let tempdir = TempDir::new("rotating_log").unwrap();
+        let path = tempdir.path().join("messages.log");
+
+        let mut file = RotatingFile::create(&path, 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        let read = |p: &Path| std::fs::read_to_string(p).unwrap();
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&tempdir.path().join("messages.log.1")), "third\n");
+        assert_eq!(read(&tempdir.path().join("messages.log.2")), "second\n");
+        assert!(!tempdir.path().join("messages.log.3").exists());
+    }
+}
@@ -0,0 +1,162 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::commands::arguments::CommandRunArgs;
+
+/// A named solver configuration (see `--solver-profile`). Each table of the profiles file
+/// is one profile, e.g.
+/// ```toml
+/// [fast]
+/// path = "./solver"
+/// args = ["--fast"]
+/// timeout = 30
+/// grace = 5
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SolverProfile {
+    /// relative paths are resolved from the directory containing the profiles file
+    pub path: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// in seconds
+    pub timeout: Option<u64>,
+    /// in seconds
+    pub grace: Option<u64>,
+}
+
+#[derive(Error, Debug)]
+pub enum SolverProfileError {
+    #[error("Failed to read solver profiles {0:?}: {1}")]
+    Io(PathBuf, std::io::Error),
+
+    #[error("Failed to parse solver profiles {0:?}: {1}")]
+    Toml(PathBuf, toml::de::Error),
+
+    #[error("Unknown solver profile `{name}`; available: {available:?}")]
+    UnknownProfile {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+impl SolverProfile {
+    /// Reads the profile `name` from the TOML file at `path`
+    pub fn load(path: &Path, name: &str) -> Result<Self, SolverProfileError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SolverProfileError::Io(path.to_path_buf(), e))?;
+        let mut profiles: BTreeMap<String, SolverProfile> = toml::from_str(&content)
+            .map_err(|e| SolverProfileError::Toml(path.to_path_buf(), e))?;
+
+        let Some(mut profile) = profiles.remove(name) else {
+            return Err(SolverProfileError::UnknownProfile {
+                name: name.into(),
+                available: profiles.into_keys().collect(),
+            });
+        };
+
+        if profile.path.is_relative() {
+            let base = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            profile.path = base.join(&profile.path);
+        }
+
+        Ok(profile)
+    }
+
+    /// Uses the profile as defaults of `args`; values given explicitly (i.e., those for which
+    /// `is_explicit` returns `true`, or a non-empty solver/solver arguments) are kept
+    pub fn apply_to(self, args: &mut CommandRunArgs, is_explicit: impl Fn(&str) -> bool) {
+        if args.solver.is_none() {
+            args.solver = Some(self.path);
+        }
+
+        if args.solver_args.is_empty() {
+            args.solver_args = self.args;
+        }
+
+        if let Some(timeout) = self.timeout
+            && !is_explicit("soft_timeout")
+        {
+            args.soft_timeout = Duration::from_secs(timeout);
+        }
+
+        if let Some(grace) = self.grace
+            && !is_explicit("grace_period")
+        {
+            args.grace_period = Duration::from_secs(grace);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempdir::TempDir;
+
+    const PROFILES: &str = r#"
+[fast]
+path = "./solver"
+args = ["--fast"]
+timeout = 10
+
+[thorough]
+path = "/opt/solver"
+grace = 60
+"#;
+
+    fn write_profiles() -> (TempDir, PathBuf) {
+        let tempdir = TempDir::new("solver_profiles").unwrap();
+        let path = tempdir.path().join("solvers.toml");
+        std::fs::write(&path, PROFILES).unwrap();
+        (tempdir, path)
+    }
+
+    #[test]
+    fn load() {
+        let (tempdir, path) = write_profiles();
+
+        let fast = SolverProfile::load(&path, "fast").unwrap();
+        assert_eq!(fast.path, tempdir.path().join("./solver"));
+        assert_eq!(fast.args, ["--fast"]);
+        assert_eq!(fast.timeout, Some(10));
+        assert_eq!(fast.grace, None);
+
+        let thorough = SolverProfile::load(&path, "thorough").unwrap();
+        assert_eq!(thorough.path, PathBuf::from("/opt/solver"));
+
+        assert!(matches!(
+            SolverProfile::load(&path, "missing"),
+            Err(SolverProfileError::UnknownProfile { available, .. }) if available.len() == 2
+        ));
+    }
+
+    #[test]
+    fn explicit_values_win() {
+        let (_tempdir, path) = write_profiles();
+        let profile = SolverProfile::load(&path, "fast").unwrap();
+
+        let mut args = CommandRunArgs::parse_from([
+            "run",
+            "--solver-profile",
+            "fast",
+            "-i",
+            "x.in",
+            "-t",
+            "5",
+            "--",
+            "--custom",
+        ]);
+        profile.apply_to(&mut args, |id| id == "soft_timeout");
+
+        assert!(args.solver.is_some());
+        assert_eq!(args.solver_args, ["--custom"]);
+        assert_eq!(args.soft_timeout, Duration::from_secs(5));
+    }
+}
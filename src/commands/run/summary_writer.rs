@@ -1,31 +1,213 @@
 use serde_json::{Map, Value};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncWriteExt, BufWriter};
 use tokio::sync::Mutex;
+use tokio::time::MissedTickBehavior;
 use tracing::warn;
 
+use crate::commands::arguments::ExistingSummary;
+use crate::commands::run::repeat::RepeatAggregate;
+use crate::commands::run::upload::UploadStatus;
 use crate::job::check_and_extract::SolutionInfos;
 use crate::{commands::run::instances::Instance, job::job_processor::JobResult};
 
-const JSON_KEY_INSTANCE_NAME: &str = "s_name";
-const JSON_KEY_INSTANCE_PATH: &str = "s_path";
-const JSON_KEY_INSTANCE_HASH: &str = "s_idigest";
-const JSON_KEY_JOB_RESULT: &str = "s_result";
-const JSON_KEY_SOLUTION_SIZE: &str = "s_score";
+/// Version of the summary format; it is bumped whenever keys written by stride are renamed,
+/// removed, or change their meaning. Consumers can use it to detect incompatible summaries.
+pub const SUMMARY_SCHEMA_VERSION: u32 = 2;
 
-const JSON_KEY_PREV_BEST_KNOWN: &str = "s_prev_best";
+// Keys written by stride into each row; all of them start with `s_`. Keys reported by
+// the solver are included verbatim and keys of the instance's stride lines are prefixed by `i_`.
+pub const JSON_KEY_SCHEMA_VERSION: &str = "s_schema_version";
+pub const JSON_KEY_INSTANCE_NAME: &str = "s_name";
+pub const JSON_KEY_INSTANCE_PATH: &str = "s_path";
+pub const JSON_KEY_INSTANCE_HASH: &str = "s_idigest";
+pub const JSON_KEY_JOB_RESULT: &str = "s_result";
+pub const JSON_KEY_SOLUTION_SIZE: &str = "s_score";
+pub const JSON_KEY_SOLUTION_TREES: &str = "s_solution_trees";
+pub const JSON_KEY_PREV_BEST_KNOWN: &str = "s_prev_best";
+pub const JSON_KEY_QUEUE_WAIT: &str = "s_queue_wait_ms";
+pub const JSON_KEY_REPEAT_INDEX: &str = "s_repeat_index";
+pub const JSON_KEY_IS_OPTIMAL: &str = "s_is_optimal";
+pub const JSON_KEY_BEATS_OPTIMUM: &str = "s_beats_optimum";
+pub const JSON_KEY_TIMEOUT: &str = "s_timeout";
+pub const JSON_KEY_RUNTIME: &str = "s_runtime";
+pub const JSON_KEY_NUMA_NODE: &str = "s_numa_node";
+pub const JSON_KEY_CPU: &str = "s_cpu";
+pub const JSON_KEY_UPLOADED: &str = "s_uploaded";
+pub const JSON_KEY_UPLOAD_ERROR: &str = "s_upload_error";
+/// reported by the profiler (see `stride profile`) along with further `s_*` metrics
+pub const JSON_KEY_WALL_TIME: &str = "s_wtime";
+
+// Keys of the aggregated row of an instance run with `--repeat`
+pub const JSON_KEY_REPEATS: &str = "s_repeats";
+pub const JSON_KEY_NUM_VALID: &str = "s_num_valid";
+pub const JSON_KEY_MEDIAN_TIME: &str = "s_median_time";
+pub const RESULT_AGGREGATE: &str = "Aggregate";
 
 /// Maintains a machine-readable log file where each line corresponds to an completed task in JSON format
 pub struct SummaryWriter {
-    file: Mutex<File>,
+    /// shared with the flush timer (see [`SummaryWriter::spawn_flush_timer`])
+    file: Arc<Mutex<SummaryFile>>,
+    /// rows are flushed once this many are buffered; `None` leaves flushing to the caller
+    flush_every: Option<usize>,
+    /// rows are also flushed if the last flush is older than this (checked when a row is added
+    /// and by the flush timer)
+    flush_interval: Option<Duration>,
+    /// rows are additionally printed to stdout (see `--stdout-ndjson`)
+    mirror_stdout: bool,
+    /// the trees of valid solutions are stored (see `--store-solutions`)
+    store_solutions: bool,
+}
+
+struct SummaryFile {
+    writer: BufWriter<File>,
+    num_buffered: usize,
+    last_flush: Instant,
+}
+
+impl SummaryFile {
+    async fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush().await?;
+        self.num_buffered = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
 }
 
 impl SummaryWriter {
     pub async fn new(path: &Path) -> Result<Self, std::io::Error> {
-        let file = Mutex::new(File::create_new(path).await?);
-        Ok(Self { file })
+        Ok(Self::from_file(File::create_new(path).await?))
+    }
+
+    /// Creates the summary file at `path`; if it already exists, it is handled as specified
+    /// by `existing`. Returns the writer and the path of the file actually written.
+    pub async fn new_or_recover(
+        path: &Path,
+        existing: ExistingSummary,
+    ) -> Result<(Self, PathBuf), std::io::Error> {
+        match File::create_new(path).await {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            result => return Ok((Self::from_file(result?), path.to_path_buf())),
+        }
+
+        match existing {
+            ExistingSummary::Backup => {
+                let mut backup = path.as_os_str().to_owned();
+                backup.push(".bak");
+                warn!("Summary {path:?} already exists; move it to {backup:?}");
+                tokio::fs::rename(path, &backup).await?;
+                Ok((Self::new(path).await?, path.to_path_buf()))
+            }
+
+            ExistingSummary::Append => {
+                warn!("Summary {path:?} already exists; append to it");
+                let file = tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(path)
+                    .await?;
+                Ok((Self::from_file(file), path.to_path_buf()))
+            }
+
+            ExistingSummary::Unique => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let ext = path.extension().unwrap_or_default().to_string_lossy();
+                for i in 1.. {
+                    let unique = path.with_file_name(format!("{stem}_{i}.{ext}"));
+                    match File::create_new(&unique).await {
+                        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                        result => {
+                            warn!("Summary {path:?} already exists; write to {unique:?} instead");
+                            return Ok((Self::from_file(result?), unique));
+                        }
+                    }
+                }
+                unreachable!()
+            }
+
+            ExistingSummary::Fail => Err(std::io::ErrorKind::AlreadyExists.into()),
+        }
+    }
+
+    fn from_file(file: File) -> Self {
+        let file = Arc::new(Mutex::new(SummaryFile {
+            writer: BufWriter::new(file),
+            num_buffered: 0,
+            last_flush: Instant::now(),
+        }));
+        Self {
+            file,
+            flush_every: Some(1),
+            flush_interval: None,
+            mirror_stdout: false,
+            store_solutions: false,
+        }
+    }
+
+    /// If set, rows are not flushed individually; the caller has to invoke [`SummaryWriter::flush`]
+    pub fn set_batched(&mut self, batched: bool) {
+        if batched {
+            self.flush_every = None;
+            self.flush_interval = None;
+        } else {
+            self.set_buffered(1, None);
+        }
+    }
+
+    /// Flushes after every `rows` rows and, if `interval` is given, whenever a row is added more
+    /// than `interval` after the last flush; `rows = 1` flushes each row individually.
+    /// The caller still has to invoke [`SummaryWriter::flush`] after the last row.
+    pub fn set_buffered(&mut self, rows: usize, interval: Option<Duration>) {
+        self.flush_every = Some(rows.max(1));
+        self.flush_interval = interval;
+    }
+
+    /// Spawns a task that flushes the buffered rows every `interval` (see
+    /// [`SummaryWriter::set_buffered`]); otherwise, rows linger in the buffer as long as no
+    /// further rows are added. The task ends along with the writer.
+    pub fn spawn_flush_timer(&self) {
+        let Some(interval) = self.flush_interval else {
+            return;
+        };
+
+        let file = Arc::downgrade(&self.file);
+        tokio::spawn(async move {
+            let mut ticks =
+                tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let Some(file) = file.upgrade() else {
+                    break;
+                };
+
+                let mut lock = file.lock().await;
+                if lock.num_buffered > 0
+                    && let Err(e) = lock.flush().await
+                {
+                    warn!("Failed to flush summary: {e}");
+                }
+            }
+        });
+    }
+
+    /// If set, each row is also written to stdout immediately, independently of the buffering
+    pub fn set_mirror_stdout(&mut self, mirror: bool) {
+        self.mirror_stdout = mirror;
+    }
+
+    /// If set, the trees of valid solutions are stored in the rows, e.g., to upload them later
+    pub fn set_store_solutions(&mut self, store: bool) {
+        self.store_solutions = store;
+    }
+
+    /// Writes all buffered rows to disk
+    pub async fn flush(&self) -> Result<(), SummaryWriterError> {
+        self.file.lock().await.flush().await?;
+        Ok(())
     }
 
     pub async fn add_entry(
@@ -34,9 +216,79 @@ impl SummaryWriter {
         job_result: JobResult,
         opt_infos: Option<SolutionInfos>,
         prev_best_known: Option<u32>,
+        upload_status: Option<&UploadStatus>,
     ) -> Result<(), SummaryWriterError> {
+        let mut row = Self::instance_columns(instance, prev_best_known, upload_status);
+
+        row.insert(
+            JSON_KEY_JOB_RESULT.into(),
+            Value::String(job_result.to_string()),
+        );
+
+        if let JobResult::Valid { size } | JobResult::Unchecked { size } = job_result {
+            row.insert(JSON_KEY_SOLUTION_SIZE.into(), Value::Number(size.into()));
+        }
+
+        if let Some((trees, extra)) = opt_infos {
+            if self.store_solutions && job_result.is_valid() && !trees.is_empty() {
+                row.insert(
+                    JSON_KEY_SOLUTION_TREES.into(),
+                    Value::Array(trees.into_iter().map(Value::String).collect()),
+                );
+            }
+
+            for (key, value) in extra {
+                let old = row.insert(key.clone(), value);
+                if old.is_some() {
+                    warn!(
+                        "Multiple definitions of key {} in instance {:?}. Use latest",
+                        &key,
+                        instance.path()
+                    );
+                }
+            }
+        }
+
+        self.write_row(row).await
+    }
+
+    /// Adds a row summarizing all repetitions of an instance (see `--repeat`); its `s_result` is `Aggregate`
+    pub async fn add_aggregate_entry(
+        &self,
+        instance: &Instance,
+        aggregate: &RepeatAggregate,
+        prev_best_known: Option<u32>,
+        upload_status: Option<&UploadStatus>,
+    ) -> Result<(), SummaryWriterError> {
+        let mut row = Self::instance_columns(instance, prev_best_known, upload_status);
+
+        row.insert(
+            JSON_KEY_JOB_RESULT.into(),
+            Value::String(RESULT_AGGREGATE.into()),
+        );
+        row.insert(JSON_KEY_REPEATS.into(), aggregate.num_repeats.into());
+        row.insert(JSON_KEY_NUM_VALID.into(), aggregate.num_valid.into());
+        if let Some(score) = aggregate.best_score {
+            row.insert(JSON_KEY_SOLUTION_SIZE.into(), score.into());
+        }
+        if let Some(runtime) = aggregate.median_runtime {
+            row.insert(JSON_KEY_MEDIAN_TIME.into(), runtime.as_secs_f64().into());
+        }
+
+        self.write_row(row).await
+    }
+
+    fn instance_columns(
+        instance: &Instance,
+        prev_best_known: Option<u32>,
+        upload_status: Option<&UploadStatus>,
+    ) -> Map<String, Value> {
         let mut row = Map::with_capacity(10);
 
+        row.insert(
+            JSON_KEY_SCHEMA_VERSION.into(),
+            SUMMARY_SCHEMA_VERSION.into(),
+        );
         row.insert(
             JSON_KEY_INSTANCE_NAME.into(),
             Value::String(instance.name().into()),
@@ -56,36 +308,42 @@ impl SummaryWriter {
                 Value::String(prev_best.to_string()),
             );
         }
-
-        row.insert(
-            JSON_KEY_JOB_RESULT.into(),
-            Value::String(job_result.to_string()),
-        );
-
-        if let JobResult::Valid { size } = job_result {
-            row.insert(JSON_KEY_SOLUTION_SIZE.into(), Value::Number(size.into()));
-        }
-
-        if let Some((_trees, extra)) = opt_infos {
-            for (key, value) in extra {
-                let old = row.insert(key.clone(), value);
-                if old.is_some() {
-                    warn!(
-                        "Multiple definitions of key {} in instance {:?}. Use latest",
-                        &key,
-                        instance.path()
-                    );
-                }
+        if let Some(status) = upload_status {
+            row.insert(JSON_KEY_UPLOADED.into(), status.is_ok().into());
+            if let Err(reason) = status {
+                row.insert(JSON_KEY_UPLOAD_ERROR.into(), reason.as_str().into());
             }
         }
 
+        row
+    }
+
+    async fn write_row(&self, row: Map<String, Value>) -> Result<(), SummaryWriterError> {
         let json = serde_json::to_string(&Value::Object(row))?;
 
         {
             let mut lock = self.file.lock().await;
-            lock.write_all(json.as_bytes()).await?;
-            lock.write_all("\n".as_bytes()).await?;
-            lock.flush().await?;
+            lock.writer.write_all(json.as_bytes()).await?;
+            lock.writer.write_all("\n".as_bytes()).await?;
+            lock.num_buffered += 1;
+
+            // mirrored under the lock, so consumers see the rows in the order of the file
+            if self.mirror_stdout {
+                let mut stdout = tokio::io::stdout();
+                stdout.write_all(json.as_bytes()).await?;
+                stdout.write_all("\n".as_bytes()).await?;
+                stdout.flush().await?;
+            }
+
+            if self
+                .flush_every
+                .is_some_and(|rows| lock.num_buffered >= rows)
+                || self
+                    .flush_interval
+                    .is_some_and(|interval| lock.last_flush.elapsed() >= interval)
+            {
+                lock.flush().await?;
+            }
         }
 
         Ok(())
@@ -100,3 +358,94 @@ pub enum SummaryWriterError {
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::run::instances::Instances;
+    use crate::test_helpers::test_cases_glob;
+    use tempdir::TempDir;
+
+    #[tokio::test]
+    async fn buffered_rows_are_not_lost() {
+        let tempdir = TempDir::new("summary_writer").unwrap();
+        let path = tempdir.path().join("summary.json");
+        let num_lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let mut instances = Instances::default();
+        instances.insert_instace_by_path(test_cases_glob("instance_only").next().unwrap());
+        let instance = instances.iter().next().unwrap().clone();
+
+        let mut writer = SummaryWriter::new(&path).await.unwrap();
+        writer.set_buffered(3, None);
+
+        for i in 1..=7 {
+            writer
+                .add_entry(&instance, JobResult::Timeout, None, None, None)
+                .await
+                .unwrap();
+            assert_eq!(num_lines(), i / 3 * 3);
+        }
+
+        writer.flush().await.unwrap();
+        assert_eq!(num_lines(), 7);
+    }
+
+    #[tokio::test]
+    async fn flush_timer() {
+        let tempdir = TempDir::new("summary_writer").unwrap();
+        let path = tempdir.path().join("summary.json");
+        let num_lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let mut instances = Instances::default();
+        instances.insert_instace_by_path(test_cases_glob("instance_only").next().unwrap());
+        let instance = instances.iter().next().unwrap().clone();
+
+        let mut writer = SummaryWriter::new(&path).await.unwrap();
+        writer.set_buffered(100, Some(Duration::from_millis(200)));
+        writer.spawn_flush_timer();
+
+        writer
+            .add_entry(&instance, JobResult::Timeout, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(num_lines(), 0);
+
+        // no further row is added, but the timer flushes the buffered one
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(num_lines(), 1);
+    }
+
+    #[tokio::test]
+    async fn existing_summary() {
+        let tempdir = TempDir::new("summary_writer").unwrap();
+        let path = tempdir.path().join("summary.json");
+        std::fs::write(&path, "old\n").unwrap();
+
+        let (_, written) = SummaryWriter::new_or_recover(&path, ExistingSummary::Unique)
+            .await
+            .unwrap();
+        assert_eq!(written, tempdir.path().join("summary_1.json"));
+
+        let (_, written) = SummaryWriter::new_or_recover(&path, ExistingSummary::Append)
+            .await
+            .unwrap();
+        assert_eq!(written, path);
+
+        assert!(
+            SummaryWriter::new_or_recover(&path, ExistingSummary::Fail)
+                .await
+                .is_err()
+        );
+
+        let (_, written) = SummaryWriter::new_or_recover(&path, ExistingSummary::Backup)
+            .await
+            .unwrap();
+        assert_eq!(written, path);
+        assert_eq!(
+            std::fs::read_to_string(tempdir.path().join("summary.json.bak")).unwrap(),
+            "old\n"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+}
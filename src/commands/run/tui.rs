@@ -0,0 +1,442 @@
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table, TableState},
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+    mpsc::{Receiver, Sender, channel},
+};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::commands::run::display::{ProgressDisplay, ResultCounts};
+use crate::job::job_processor::{JobProgress, JobResult};
+
+const TUI_TICK: Duration = Duration::from_millis(100);
+
+/// Per-instance events emitted by the tasks of `stride run` and consumed by the [`Tui`]
+#[derive(Debug, Clone)]
+pub enum InstanceEvent {
+    Started { name: String, path: PathBuf },
+    Progress { name: String, progress: JobProgress },
+    Finished { name: String, result: JobResult },
+}
+
+/// Interactive terminal UI that replaces the progress bars if `--tui` is passed.
+/// It is rendered in an own thread and shut down using [`Tui::finish`].
+pub struct Tui {
+    sender: Sender<InstanceEvent>,
+    shutdown: Arc<AtomicBool>,
+    join_handle: JoinHandle<()>,
+}
+
+impl Tui {
+    pub fn start(display: Arc<ProgressDisplay>, num_instances: usize) -> Self {
+        let (sender, receiver) = channel();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        display.hide();
+
+        let join_handle = {
+            let shutdown = shutdown.clone();
+            std::thread::spawn(move || {
+                let terminal = ratatui::init();
+                let abort = render_loop(terminal, receiver, &shutdown, &display, num_instances);
+                ratatui::restore();
+
+                if abort {
+                    // raw mode swallows Ctrl+C, so we send it to the runner itself; its interrupt
+                    // handler then terminates the solvers (which run in their own process groups)
+                    unsafe {
+                        libc::kill(libc::getpid(), libc::SIGINT);
+                    }
+                }
+            })
+        };
+
+        Self {
+            sender,
+            shutdown,
+            join_handle,
+        }
+    }
+
+    pub fn sender(&self) -> Sender<InstanceEvent> {
+        self.sender.clone()
+    }
+
+    /// Stops rendering and restores the terminal
+    pub fn finish(self) {
+        self.shutdown.store(true, Ordering::Release);
+        if self.join_handle.join().is_err() {
+            error!("TUI thread panicked");
+        }
+    }
+}
+
+/// Returns `true` iff the user requested to abort the run
+fn render_loop(
+    mut terminal: DefaultTerminal,
+    receiver: Receiver<InstanceEvent>,
+    shutdown: &AtomicBool,
+    display: &ProgressDisplay,
+    num_instances: usize,
+) -> bool {
+    let mut app = App::new(num_instances);
+
+    while !shutdown.load(Ordering::Acquire) {
+        while let Ok(event) = receiver.try_recv() {
+            app.apply(event);
+        }
+
+        let counts = display.result_counts();
+        if let Err(e) = terminal.draw(|frame| app.draw(frame, &counts)) {
+            error!("Failed to draw TUI: {e}");
+            return false;
+        }
+
+        match event::poll(TUI_TICK) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                error!("Failed to poll terminal events: {e}");
+                return false;
+            }
+        }
+
+        let Ok(Event::Key(key)) = event::read() else {
+            continue;
+        };
+
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            KeyCode::Up | KeyCode::Char('k') => app.select_relative(-1),
+            KeyCode::Down | KeyCode::Char('j') => app.select_relative(1),
+            KeyCode::PageUp => app.select_relative(-20),
+            KeyCode::PageDown => app.select_relative(20),
+            KeyCode::Char('f') | KeyCode::Tab => app.next_filter(),
+            KeyCode::Enter => app.show_details = !app.show_details,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Filter {
+    All,
+    Running,
+    Valid,
+    Infeasible,
+    Timeout,
+    Errors,
+}
+
+impl Filter {
+    fn next(self) -> Self {
+        match self {
+            Filter::All => Filter::Running,
+            Filter::Running => Filter::Valid,
+            Filter::Valid => Filter::Infeasible,
+            Filter::Infeasible => Filter::Timeout,
+            Filter::Timeout => Filter::Errors,
+            Filter::Errors => Filter::All,
+        }
+    }
+
+    fn matches(self, state: RowState) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Running => matches!(state, RowState::Running(_)),
+            Filter::Valid => matches!(state, RowState::Done(JobResult::Valid { .. })),
+            Filter::Infeasible => matches!(state, RowState::Done(JobResult::Infeasible)),
+            Filter::Timeout => matches!(state, RowState::Done(JobResult::Timeout)),
+            Filter::Errors => matches!(
+                state,
+                RowState::Done(result) if !matches!(
+                    result,
+                    JobResult::Valid { .. }
+                        | JobResult::Infeasible
+                        | JobResult::Timeout
+                        | JobResult::Cancelled
+                        | JobResult::Unchecked { .. }
+                )
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RowState {
+    Running(JobProgress),
+    Done(JobResult),
+}
+
+struct InstanceRow {
+    name: String,
+    path: PathBuf,
+    state: RowState,
+    started: Instant,
+    finished: Option<Instant>,
+}
+
+impl InstanceRow {
+    fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(Instant::now) - self.started
+    }
+
+    fn state_text(&self) -> String {
+        match self.state {
+            RowState::Running(progress) => format!("{progress:?}"),
+            RowState::Done(JobResult::Valid { size }) => format!("Valid ({size})"),
+            RowState::Done(JobResult::Unchecked { size }) => format!("Unchecked ({size})"),
+            RowState::Done(result) => result.to_string(),
+        }
+    }
+
+    fn style(&self) -> Style {
+        let color = match self.state {
+            RowState::Running(_) => Color::Cyan,
+            RowState::Done(JobResult::Valid { .. }) => Color::Green,
+            RowState::Done(
+                JobResult::Infeasible
+                | JobResult::Timeout
+                | JobResult::Cancelled
+                | JobResult::Unchecked { .. },
+            ) => Color::Yellow,
+            RowState::Done(_) => Color::Red,
+        };
+        Style::new().fg(color)
+    }
+}
+
+struct App {
+    num_instances: usize,
+    rows: Vec<InstanceRow>,
+    index: HashMap<String, usize>,
+    filter: Filter,
+    table_state: TableState,
+    show_details: bool,
+}
+
+impl App {
+    fn new(num_instances: usize) -> Self {
+        Self {
+            num_instances,
+            rows: Vec::with_capacity(num_instances),
+            index: HashMap::with_capacity(num_instances),
+            filter: Filter::All,
+            table_state: TableState::default().with_selected(Some(0)),
+            show_details: false,
+        }
+    }
+
+    fn apply(&mut self, event: InstanceEvent) {
+        match event {
+            InstanceEvent::Started { name, path } => {
+                self.index.insert(name.clone(), self.rows.len());
+                self.rows.push(InstanceRow {
+                    name,
+                    path,
+                    state: RowState::Running(JobProgress::Starting),
+                    started: Instant::now(),
+                    finished: None,
+                });
+            }
+            InstanceEvent::Progress { name, progress } => {
+                if let Some(&idx) = self.index.get(&name) {
+                    self.rows[idx].state = RowState::Running(progress);
+                }
+            }
+            InstanceEvent::Finished { name, result } => {
+                if let Some(&idx) = self.index.get(&name) {
+                    self.rows[idx].state = RowState::Done(result);
+                    self.rows[idx].finished = Some(Instant::now());
+                }
+            }
+        }
+    }
+
+    fn visible_rows(&self) -> impl Iterator<Item = &InstanceRow> {
+        self.rows.iter().filter(|r| self.filter.matches(r.state))
+    }
+
+    fn select_relative(&mut self, delta: isize) {
+        let num_visible = self.visible_rows().count();
+        if num_visible == 0 {
+            self.table_state.select(None);
+            return;
+        }
+
+        let current = self.table_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, num_visible as isize - 1);
+        self.table_state.select(Some(next as usize));
+    }
+
+    fn next_filter(&mut self) {
+        self.filter = self.filter.next();
+        self.table_state.select(Some(0));
+    }
+
+    fn draw(&mut self, frame: &mut Frame, counts: &ResultCounts) {
+        let details_height = if self.show_details { 6 } else { 0 };
+        let [header, body, details, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(details_height),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_header(frame, header, counts);
+        self.draw_table(frame, body);
+        if self.show_details {
+            self.draw_details(frame, details);
+        }
+
+        frame.render_widget(
+            Paragraph::new(
+                "q: abort run | ↑/↓: select | f/Tab: filter by result | Enter: toggle details",
+            )
+            .style(Style::new().fg(Color::DarkGray)),
+            footer,
+        );
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect, counts: &ResultCounts) {
+        let completed = self
+            .rows
+            .iter()
+            .filter(|r| matches!(r.state, RowState::Done(_)))
+            .count();
+
+        let text = format!(
+            "Completed: {completed}/{} | Running: {} | Valid: {} | Infeas: {} | Timeout: {} | Errors: {} | Subopt: {}",
+            self.num_instances,
+            self.rows.len() - completed,
+            counts.valid,
+            counts.infeasible,
+            counts.timeout,
+            counts.errors,
+            counts.suboptimal,
+        );
+
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(" stride run ")),
+            area,
+        );
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let rows: Vec<Row> = self
+            .visible_rows()
+            .map(|r| {
+                Row::new([
+                    r.name.clone(),
+                    r.state_text(),
+                    format!("{:.1}s", r.elapsed().as_secs_f64()),
+                ])
+                .style(r.style())
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(20),
+                Constraint::Length(18),
+                Constraint::Length(10),
+            ],
+        )
+        .header(
+            Row::new(["Instance", "State", "Time"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(format!(" Instances [filter: {:?}] ", self.filter)))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, area, &mut self.table_state);
+    }
+
+    fn draw_details(&self, frame: &mut Frame, area: Rect) {
+        let selected = self
+            .table_state
+            .selected()
+            .and_then(|idx| self.visible_rows().nth(idx));
+
+        let lines = if let Some(row) = selected {
+            vec![
+                Line::from(format!("Name:    {}", row.name)),
+                Line::from(format!("Path:    {}", row.path.display())),
+                Line::from(format!("State:   {}", row.state_text())),
+                Line::from(format!("Elapsed: {:.3}s", row.elapsed().as_secs_f64())),
+            ]
+        } else {
+            vec![Line::from("No instance selected")]
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Details ")),
+            area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_rows() {
+        let mut app = App::new(3);
+        for name in ["a", "b", "c"] {
+            app.apply(InstanceEvent::Started {
+                name: name.into(),
+                path: PathBuf::from(name),
+            });
+        }
+
+        app.apply(InstanceEvent::Finished {
+            name: "a".into(),
+            result: JobResult::Valid { size: 3 },
+        });
+        app.apply(InstanceEvent::Finished {
+            name: "b".into(),
+            result: JobResult::SyntaxError,
+        });
+        app.apply(InstanceEvent::Progress {
+            name: "c".into(),
+            progress: JobProgress::Checking,
+        });
+
+        let visible = |app: &App| {
+            app.visible_rows()
+                .map(|r| r.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(visible(&app), ["a", "b", "c"]);
+        app.next_filter();
+        assert_eq!(app.filter, Filter::Running);
+        assert_eq!(visible(&app), ["c"]);
+        app.next_filter();
+        assert_eq!(visible(&app), ["a"]);
+
+        app.filter = Filter::Errors;
+        assert_eq!(visible(&app), ["b"]);
+    }
+}
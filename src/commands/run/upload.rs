@@ -1,9 +1,10 @@
 use pace26checker::digest::digest_output::InstanceDigest;
 use pace26remote::job_description::{JobDescription, JobResult};
-use pace26remote::job_transfer::{TransferFromServer, TransferToServer};
+use pace26remote::job_transfer::TransferFromServer;
 use pace26remote::upload::UploadError;
 use reqwest::{ClientBuilder, IntoUrl};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
@@ -14,63 +15,146 @@ use url::Url;
 
 const UPLOAD_AGGREGATION_TIMEOUT: Duration = Duration::from_millis(500);
 const UPLOAD_MAX_BUFFER_SIZE: usize = 200;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Response to an awaited upload: the best known score reported by the server (if any) or the
+/// reason why the upload failed
+pub type UploadResponse = Result<Option<u32>, String>;
+
+/// Whether an awaited upload succeeded, or the reason why it failed
+pub type UploadStatus = Result<(), String>;
+
+type ReturnChannel = oneshot::Sender<UploadResponse>;
+type MessageToUploader = (Option<ReturnChannel>, UploadJob);
+
+/// Optional metadata attached to non-valid results (see `--rich-upload`)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_trees: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_leaves: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solver_tag: Option<String>,
+}
+
+/// A job as transmitted to the server; without context, it serializes exactly as `JobDescription`
+#[derive(Clone, Debug, Serialize)]
+pub struct UploadJob {
+    #[serde(flatten)]
+    pub job: JobDescription,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub context: Option<UploadContext>,
+}
+
+impl From<JobDescription> for UploadJob {
+    fn from(job: JobDescription) -> Self {
+        Self { job, context: None }
+    }
+}
 
-type ReturnChannel = oneshot::Sender<Option<u32>>;
-type MessageToUploader = (Option<ReturnChannel>, JobDescription);
+/// Mirrors `pace26remote::job_transfer::TransferToServer`, but carries the optional context
+#[derive(Serialize)]
+struct UploadPayload<'a> {
+    jobs: &'a [UploadJob],
+}
 
 pub trait Uploader: Send + Sync {
     fn upload(
         &self,
-        jobs: &[JobDescription],
+        jobs: &[UploadJob],
     ) -> impl Future<Output = Result<HashMap<InstanceDigest, u32>, UploadError>> + Send;
+
+    /// Returns the idigests of the instances that still accept improvements (i.e. whose best
+    /// known solution is not known to be optimal), or `None` if the uploader cannot query them
+    fn open_instances(
+        &self,
+    ) -> impl Future<Output = Result<Option<HashSet<InstanceDigest>>, UploadError>> + Send {
+        async { Ok(None) }
+    }
 }
 
 pub struct UploadToStride {
     url: Url,
+    /// only known if constructed from the server's address
+    open_instances_url: Option<Url>,
 }
 
 impl UploadToStride {
     pub fn new_with_server(into_url: impl IntoUrl) -> Result<UploadToStride, UploadError> {
-        let url = into_url.into_url()?.join("/api/solution")?;
-        Self::new_with_endpoint(url)
+        let server = into_url.into_url()?;
+        let mut uploader = Self::new_with_endpoint(server.join("/api/solution")?)?;
+        uploader.open_instances_url = Some(server.join("/api/instances/open")?);
+        Ok(uploader)
     }
 
     pub fn new_with_endpoint(into_url: impl IntoUrl) -> Result<Self, UploadError> {
         let url = into_url.into_url()?;
-        Ok(UploadToStride { url })
+        Ok(UploadToStride {
+            url,
+            open_instances_url: None,
+        })
+    }
+}
+
+impl UploadToStride {
+    /// Sends a `HEAD` request to the server's root to check that it is reachable; unlike an
+    /// (empty) upload, this cannot have side effects on the server
+    pub async fn probe(&self) -> Result<(), UploadError> {
+        let client = ClientBuilder::new()
+            .danger_accept_invalid_certs(true)
+            .timeout(PROBE_TIMEOUT)
+            .build()?;
+
+        let response = client.head(self.url.join("/")?).send().await?;
+        trace!("Probe request received: {:?}", response);
+        response.error_for_status()?;
+
+        Ok(())
     }
 }
 
 impl Uploader for UploadToStride {
     async fn upload(
         &self,
-        jobs: &[JobDescription],
+        jobs: &[UploadJob],
     ) -> Result<HashMap<InstanceDigest, u32>, UploadError> {
         let client = ClientBuilder::new()
             .danger_accept_invalid_certs(true)
             .build()?;
 
-        let payload = TransferToServer {
-            jobs: jobs.to_vec(),
-        };
+        let payload = UploadPayload { jobs };
         let response = client.post(self.url.clone()).json(&payload).send().await?;
         trace!("Upload request received: {:?}", response);
 
-        let deserialized: TransferFromServer = if !response.status().is_success() {
+        if let Err(err) = response.error_for_status_ref() {
             error!(
                 "Upload request returned an error: {} / {}",
                 response.status(),
                 response.text().await?
             );
-            TransferFromServer {
-                best_scores: Default::default(),
-            }
-        } else {
-            response.json().await?
-        };
+            return Err(err.into());
+        }
 
+        let deserialized: TransferFromServer = response.json().await?;
         Ok(deserialized.best_scores)
     }
+
+    async fn open_instances(&self) -> Result<Option<HashSet<InstanceDigest>>, UploadError> {
+        let Some(url) = &self.open_instances_url else {
+            return Ok(None);
+        };
+
+        let client = ClientBuilder::new()
+            .danger_accept_invalid_certs(true)
+            .build()?;
+
+        let response = client.get(url.clone()).send().await?;
+        trace!("Open instances request received: {:?}", response);
+
+        let idigests: Vec<InstanceDigest> = response.error_for_status()?.json().await?;
+        Ok(Some(idigests.into_iter().collect()))
+    }
 }
 
 pub struct JobResultUploadAggregation {
@@ -94,7 +178,7 @@ impl JobResultUploadAggregation {
                     Ok(Some((channel, msg))) => {
                         if let Some(channel) = channel {
                             return_channels
-                                .entry(msg.idigest)
+                                .entry(msg.job.idigest)
                                 .or_default()
                                 .push(channel);
                         }
@@ -126,24 +210,27 @@ impl JobResultUploadAggregation {
                 time_since_first = None;
                 trace!("Received best knowns from server: {:?}", best_known);
 
-                match best_known {
+                // all remaining channels belong to the same (failed or succeeded) request
+                let remaining = match best_known {
                     Ok(best_known) => {
                         for (idigest, score) in best_known.into_iter() {
                             if let Some(channels) = return_channels.remove(&idigest) {
                                 for channel in channels {
-                                    let _ = channel.send(Some(score));
+                                    let _ = channel.send(Ok(Some(score)));
                                 }
                             }
                         }
+                        Ok(None)
                     }
                     Err(err) => {
                         error!("Uploader failed: {err:?}");
+                        Err(err.to_string())
                     }
-                }
+                };
 
                 for (_, channels) in return_channels.drain() {
                     for channel in channels {
-                        let _ = channel.send(None);
+                        let _ = channel.send(remaining.clone());
                     }
                 }
             }
@@ -155,39 +242,89 @@ impl JobResultUploadAggregation {
         }
     }
 
-    pub async fn upload_and_fetch_best_known(&self, desc: JobDescription) -> Option<u32> {
-        let channel_to_upload = self.channel_to_upload.clone();
-
-        if matches!(desc.result, JobResult::Valid { .. }) {
+    pub async fn upload_and_fetch_best_known(&self, desc: impl Into<UploadJob>) -> Option<u32> {
+        let desc = desc.into();
+        if matches!(desc.job.result, JobResult::Valid { .. }) {
             // we only wait for an answer if the JobResult is valid
-            let (sender, receiver) = oneshot::channel::<Option<u32>>();
-            if let Err(e) = channel_to_upload.send((Some(sender), desc)) {
-                debug!("Error sending job result upload: {e:?}");
-                return None;
-            }
-
-            receiver.await.unwrap_or_else(|e| {
-                debug!("Error receiving best known score: {e:?}");
-                None
-            })
+            self.upload_and_wait(desc).await.ok().flatten()
         } else {
-            if let Err(e) = channel_to_upload.send((None, desc)) {
-                debug!("Error sending job result upload: {e:?}");
-            }
+            self.upload_only(desc);
             None
         }
     }
 
+    /// Queues `desc` for upload and waits until the request containing it was answered
+    pub async fn upload_and_wait(&self, desc: impl Into<UploadJob>) -> UploadResponse {
+        let (sender, receiver) = oneshot::channel::<UploadResponse>();
+        if let Err(e) = self.channel_to_upload.send((Some(sender), desc.into())) {
+            debug!("Error sending job result upload: {e:?}");
+            return Err("upload queue is closed".into());
+        }
+
+        receiver.await.unwrap_or_else(|e| {
+            debug!("Error receiving upload response: {e:?}");
+            Err("uploader terminated".into())
+        })
+    }
+
+    /// Queues `desc` for upload without waiting for the server's response
+    pub fn upload_only(&self, desc: impl Into<UploadJob>) {
+        if let Err(e) = self.channel_to_upload.send((None, desc.into())) {
+            debug!("Error sending job result upload: {e:?}");
+        }
+    }
+
+    /// Closes the channel to the uploader and waits until all queued results are transmitted
     pub async fn join(self) -> Result<(), JoinError> {
-        self.join_handle.await
+        let Self {
+            channel_to_upload,
+            join_handle,
+        } = self;
+
+        drop(channel_to_upload);
+        join_handle.await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
     use tokio::sync::Mutex;
 
+    /// Answers a single HTTP request with `status`; returns the server's address and the request
+    async fn serve_once(status: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        let request = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0u8; 4096];
+            let len = stream.read(&mut buffer).await.unwrap();
+            let response =
+                format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buffer[..len]).into_owned()
+        });
+
+        (address, request)
+    }
+
+    #[tokio::test]
+    async fn probe_is_side_effect_free() {
+        let (address, request) = serve_once("200 OK").await;
+        let uploader = UploadToStride::new_with_server(address.as_str()).unwrap();
+        uploader.probe().await.unwrap();
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("HEAD / "), "{request}");
+
+        let (address, _) = serve_once("503 Service Unavailable").await;
+        let uploader = UploadToStride::new_with_server(address.as_str()).unwrap();
+        assert!(uploader.probe().await.is_err());
+    }
+
     #[tokio::test]
     async fn no_results_from_server() {
         let dummy_inst: InstanceDigest = "00000000000000000000000000000003".try_into().unwrap();
@@ -283,6 +420,54 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn upload_only_is_flushed_on_join() {
+        let dummy_inst: InstanceDigest = "00000000000000000000000000000003".try_into().unwrap();
+
+        let uploader = Arc::new(MockUploader::default());
+        uploader.put(Ok(HashMap::new())).await;
+
+        let aggr = JobResultUploadAggregation::new(uploader.clone());
+        aggr.upload_only(JobDescription::valid(dummy_inst, Vec::new(), None));
+
+        timeout(5 * UPLOAD_AGGREGATION_TIMEOUT, aggr.join())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // the mock's response is consumed by the upload
+        assert!(uploader.response.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn upload_and_wait_reports_status() {
+        let dummy_inst: InstanceDigest = "00000000000000000000000000000003".try_into().unwrap();
+
+        let uploader = Arc::new(MockUploader::default());
+        let aggr = JobResultUploadAggregation::new(uploader.clone());
+
+        // non-valid results receive a response as well
+        uploader.put(Ok([(dummy_inst, 7)].into())).await;
+        let response = timeout(
+            5 * UPLOAD_AGGREGATION_TIMEOUT,
+            aggr.upload_and_wait(JobDescription::infeasible(dummy_inst, None)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response, Ok(Some(7)));
+
+        uploader
+            .put(Err(Url::parse("not a url").unwrap_err().into()))
+            .await;
+        let response = timeout(
+            5 * UPLOAD_AGGREGATION_TIMEOUT,
+            aggr.upload_and_wait(JobDescription::valid(dummy_inst, Vec::new(), None)),
+        )
+        .await
+        .unwrap();
+        assert!(response.is_err());
+    }
+
     #[derive(Default)]
     struct MockUploader {
         response: Mutex<Option<Result<HashMap<InstanceDigest, u32>, UploadError>>>,
@@ -298,7 +483,7 @@ mod tests {
     impl Uploader for MockUploader {
         async fn upload(
             &self,
-            _jobs: &[JobDescription],
+            _jobs: &[UploadJob],
         ) -> Result<HashMap<InstanceDigest, u32>, UploadError> {
             let mut lock = self.response.lock().await;
             lock.take().unwrap()
@@ -0,0 +1,151 @@
+use crate::commands::arguments::CommandSelftestArgs;
+use crate::commands::run::instances::{Instances, InstancesError};
+use crate::commands::run::summary_writer::{
+    JSON_KEY_JOB_RESULT, SummaryWriter, SummaryWriterError,
+};
+use crate::job::job_processor::{JobProcessorBuilder, JobResult};
+use crate::run_directory::{CreateInstanceDirError, RunDirectory};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{error, info};
+
+const SELFTEST_TIMEOUT: Duration = Duration::from_secs(10);
+const SELFTEST_GRACE: Duration = Duration::from_secs(1);
+const SELFTEST_SUMMARY_FILENAME: &str = "summary.json";
+
+/// Instances (with parameters for the `test_solver` binary) and their expected outcome
+const SELFTEST_CASES: [(&str, &str, JobResult); 3] = [
+    (
+        "valid",
+        include_str!("../../testcases/test_solver_valid/valid.in"),
+        JobResult::Valid { size: 2 },
+    ),
+    (
+        "infeasible",
+        include_str!("../../testcases/test_solver_errors/infeasible.in"),
+        JobResult::Infeasible,
+    ),
+    (
+        "syntaxerror",
+        include_str!("../../testcases/test_solver_errors/syntaxerror.in"),
+        JobResult::SyntaxError,
+    ),
+];
+
+#[derive(Error, Debug)]
+pub enum CommandSelftestError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    Instances(#[from] InstancesError),
+    #[error(transparent)]
+    InstanceDir(#[from] CreateInstanceDirError),
+    #[error(transparent)]
+    SummaryWriter(#[from] SummaryWriterError),
+    #[error("Test solver not found at {0:?}; use --test-solver to specify its path")]
+    TestSolverNotFound(PathBuf),
+    #[error("Selftest failed: {0} check(s) did not pass; logs are kept in {1:?}")]
+    Failed(usize, PathBuf),
+}
+
+pub async fn command_selftest(args: &CommandSelftestArgs) -> Result<(), CommandSelftestError> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::INFO)
+        .without_time()
+        .init();
+
+    let test_solver = match &args.test_solver {
+        Some(path) => path.clone(),
+        None => std::env::current_exe()?.with_file_name("test_solver"),
+    };
+    if !test_solver.is_file() {
+        return Err(CommandSelftestError::TestSolverNotFound(test_solver));
+    }
+
+    let parent = std::env::temp_dir().join(format!("stride-selftest-{}", std::process::id()));
+    let run_dir = RunDirectory::new_within(&parent, None)?;
+    let summary_path = run_dir.path().join(SELFTEST_SUMMARY_FILENAME);
+    let summary_writer = SummaryWriter::new(&summary_path).await?;
+
+    let mut instances = Instances::default();
+    for (name, content, _) in SELFTEST_CASES {
+        let path = run_dir.path().join(format!("{name}.in"));
+        std::fs::write(&path, content)?;
+        instances.insert_instace_by_path(path);
+    }
+
+    let mut num_failed = 0;
+    for instance in instances.iter() {
+        let (_, _, expected) = SELFTEST_CASES
+            .iter()
+            .find(|(name, _, _)| *name == instance.name())
+            .expect("instance was created from SELFTEST_CASES");
+
+        let job = JobProcessorBuilder::default()
+            .work_dir(run_dir.create_task_dir_for(instance.path())?)
+            .instance_path(instance.path().to_path_buf())
+            .solver(test_solver.clone())
+            .solver_args(vec!["-f".into()])
+            .soft_timeout(SELFTEST_TIMEOUT)
+            .grace_period(SELFTEST_GRACE)
+            .profiler(true)
+            .set_stride_envs(true)
+            .build()
+            .unwrap();
+
+        let (job_result, opt_infos) = job.run().await;
+
+        if job_result == *expected {
+            info!("[ok]   {}: {job_result}", instance.name());
+        } else {
+            error!(
+                "[fail] {}: expected {expected}, got {job_result}",
+                instance.name()
+            );
+            num_failed += 1;
+        }
+
+        summary_writer
+            .add_entry(instance, job_result, opt_infos, None, None)
+            .await?;
+    }
+
+    num_failed += check_summary(&summary_path)?;
+
+    if num_failed > 0 {
+        return Err(CommandSelftestError::Failed(num_failed, parent));
+    }
+
+    std::fs::remove_dir_all(&parent)?;
+    info!("Selftest passed");
+
+    Ok(())
+}
+
+/// Verifies that the summary contains exactly one entry per test case with the expected result;
+/// returns the number of mismatches
+fn check_summary(summary_path: &Path) -> Result<usize, std::io::Error> {
+    let mut entries: Vec<_> = std::fs::read_to_string(summary_path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|row| Some(row.get(JSON_KEY_JOB_RESULT)?.as_str()?.to_string()))
+        .collect();
+    entries.sort_unstable();
+
+    let mut expected: Vec<_> = SELFTEST_CASES
+        .iter()
+        .map(|(_, _, result)| result.to_string())
+        .collect();
+    expected.sort_unstable();
+
+    if entries == expected {
+        info!("[ok]   summary");
+        Ok(0)
+    } else {
+        error!("[fail] summary: expected results {expected:?}, got {entries:?}");
+        Ok(1)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::commands::arguments::CommandUploadDaemonArgs;
+use crate::commands::run::upload::{
+    JobResultUploadAggregation, UploadContext, UploadJob, UploadToStride, Uploader,
+};
+use pace26checker::digest::digest_output::InstanceDigest;
+use pace26remote::job_description::{JobDescription, JobResult};
+use pace26remote::upload::UploadError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, warn};
+
+#[derive(Error, Debug)]
+pub enum CommandUploadDaemonError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    Upload(#[from] UploadError),
+}
+
+/// A job as sent from `stride run` to the daemon
+#[derive(Serialize, Deserialize)]
+struct DaemonJob {
+    job: JobDescription,
+    context: Option<UploadContext>,
+}
+
+/// Each request is a single line; it is answered by a single [`DaemonResponse`] line
+#[derive(Serialize, Deserialize)]
+struct DaemonRequest {
+    jobs: Vec<DaemonJob>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct DaemonResponse {
+    best_scores: Vec<(InstanceDigest, u32)>,
+}
+
+/// Runs a daemon that accepts uploads of several concurrent `stride run` invocations (see
+/// `--upload-daemon`) on a Unix socket and batches them into shared requests to the server
+pub async fn command_upload_daemon(
+    args: &CommandUploadDaemonArgs,
+) -> Result<(), CommandUploadDaemonError> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::INFO)
+        .without_time()
+        .init();
+
+    let uploader = Arc::new(UploadToStride::new_with_server(
+        args.solution_server.clone(),
+    )?);
+    let aggregation = Arc::new(JobResultUploadAggregation::new(uploader));
+
+    let listener = UnixListener::bind(&args.socket)?;
+    info!(
+        "Forwarding uploads received on {:?} to {}",
+        args.socket, args.solution_server
+    );
+
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    connections.spawn(handle_connection(stream, aggregation.clone()));
+                }
+                Err(e) => {
+                    warn!("Upload daemon failed to accept connection: {e}");
+                    break;
+                }
+            },
+            _ = tokio::signal::ctrl_c() => {
+                info!("Shutting down upload daemon");
+                break;
+            }
+        }
+
+        // reap completed connections
+        while connections.try_join_next().is_some() {}
+    }
+
+    drop(listener);
+    let _ = std::fs::remove_file(&args.socket);
+
+    // queued uploads are still transmitted when the aggregation is joined
+    connections.shutdown().await;
+    match Arc::try_unwrap(aggregation) {
+        Ok(aggregation) => {
+            if let Err(e) = aggregation.join().await {
+                error!("Uploader failed to complete: {e}");
+            }
+        }
+        Err(aggregation) => error!(
+            "Uploader is still referenced {} times; queued uploads are dropped",
+            Arc::strong_count(&aggregation) - 1
+        ),
+    }
+
+    Ok(())
+}
+
+async fn handle_connection(stream: UnixStream, aggregation: Arc<JobResultUploadAggregation>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let request: DaemonRequest = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Upload daemon received malformed request: {e}");
+                break;
+            }
+        };
+
+        let response = forward_request(request, &aggregation).await;
+        let Ok(mut response) = serde_json::to_string(&response) else {
+            break;
+        };
+        response.push('\n');
+
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Passes the jobs to the shared aggregation and waits for the best known scores of valid ones
+async fn forward_request(
+    request: DaemonRequest,
+    aggregation: &Arc<JobResultUploadAggregation>,
+) -> DaemonResponse {
+    debug!("Upload daemon received {} jobs", request.jobs.len());
+
+    let mut pending = JoinSet::new();
+    for DaemonJob { job, context } in request.jobs {
+        let idigest = job.idigest;
+        let job = UploadJob { job, context };
+
+        if matches!(job.job.result, JobResult::Valid { .. }) {
+            let aggregation = aggregation.clone();
+            pending.spawn(async move {
+                let best_known = aggregation.upload_and_fetch_best_known(job).await;
+                best_known.map(|score| (idigest, score))
+            });
+        } else {
+            aggregation.upload_only(job);
+        }
+    }
+
+    let mut response = DaemonResponse::default();
+    while let Some(result) = pending.join_next().await {
+        if let Ok(Some(best_score)) = result {
+            response.best_scores.push(best_score);
+        }
+    }
+
+    response
+}
+
+/// Sends uploads to an upload daemon instead of the server (see `stride upload-daemon`)
+pub struct UploadViaDaemon {
+    socket: PathBuf,
+}
+
+impl UploadViaDaemon {
+    pub fn new(socket: &Path) -> Self {
+        Self {
+            socket: socket.to_path_buf(),
+        }
+    }
+
+    async fn request(&self, jobs: &[UploadJob]) -> Result<DaemonResponse, std::io::Error> {
+        let request = DaemonRequest {
+            jobs: jobs
+                .iter()
+                .map(|job| DaemonJob {
+                    job: job.job.clone(),
+                    context: job.context.clone(),
+                })
+                .collect(),
+        };
+        let mut request = serde_json::to_string(&request)?;
+        request.push('\n');
+
+        let stream = UnixStream::connect(&self.socket).await?;
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(request.as_bytes()).await?;
+
+        let mut response = String::new();
+        BufReader::new(reader).read_line(&mut response).await?;
+        Ok(serde_json::from_str(&response)?)
+    }
+}
+
+impl Uploader for UploadViaDaemon {
+    async fn upload(
+        &self,
+        jobs: &[UploadJob],
+    ) -> Result<HashMap<InstanceDigest, u32>, UploadError> {
+        match self.request(jobs).await {
+            Ok(response) => Ok(response.best_scores.into_iter().collect()),
+            Err(e) => {
+                // as for failed requests to the server, the results are reported without response
+                error!("Upload via daemon {:?} failed: {e}", self.socket);
+                Ok(HashMap::new())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    struct FixedUploader(HashMap<InstanceDigest, u32>);
+
+    impl Uploader for FixedUploader {
+        async fn upload(
+            &self,
+            _jobs: &[UploadJob],
+        ) -> Result<HashMap<InstanceDigest, u32>, UploadError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn upload_via_daemon() {
+        let idigest: InstanceDigest = "00000000000000000000000000000003".try_into().unwrap();
+
+        let tempdir = TempDir::new("upload_daemon").unwrap();
+        let socket = tempdir.path().join("daemon.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let uploader = Arc::new(FixedUploader([(idigest, 7)].into()));
+        let aggregation = Arc::new(JobResultUploadAggregation::new(uploader));
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(stream, aggregation.clone()));
+            }
+        });
+
+        let jobs = [
+            JobDescription::valid(idigest, Vec::new(), None).into(),
+            JobDescription::infeasible(idigest, None).into(),
+        ];
+        let best_scores = UploadViaDaemon::new(&socket).upload(&jobs).await.unwrap();
+        assert_eq!(best_scores.get(&idigest), Some(&7));
+
+        // an unreachable daemon is treated like a server without response
+        let missing = UploadViaDaemon::new(&tempdir.path().join("missing.sock"));
+        assert!(missing.upload(&jobs).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::commands::arguments::CommandUploadSummaryArgs;
+use crate::commands::run::summary_writer::{
+    JSON_KEY_INSTANCE_HASH, JSON_KEY_JOB_RESULT, JSON_KEY_RUNTIME, JSON_KEY_SOLUTION_TREES,
+    JSON_KEY_WALL_TIME,
+};
+use crate::commands::run::upload::{UploadJob, UploadToStride, Uploader};
+use pace26checker::digest::digest_output::InstanceDigest;
+use pace26remote::job_description::JobDescription;
+use pace26remote::upload::UploadError;
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+/// Maximum number of job descriptions sent in a single request
+const UPLOAD_BATCH_SIZE: usize = 200;
+
+#[derive(Error, Debug)]
+pub enum CommandUploadSummaryError {
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    Upload(#[from] UploadError),
+}
+
+pub async fn command_upload_summary(
+    args: &CommandUploadSummaryArgs,
+) -> Result<(), CommandUploadSummaryError> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::INFO)
+        .without_time()
+        .init();
+
+    let reader = BufReader::new(File::open(&args.summary)?);
+
+    let mut jobs = Vec::new();
+    let mut num_skipped = 0;
+    for (lineno, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Ok(Value::Object(row)) = serde_json::from_str::<Value>(&line) else {
+            warn!(
+                "Line {} of {:?} is not a JSON object",
+                lineno + 1,
+                args.summary
+            );
+            num_skipped += 1;
+            continue;
+        };
+
+        match job_description_from_row(&row) {
+            Some(desc) => jobs.push(UploadJob::from(desc)),
+            None => num_skipped += 1,
+        }
+    }
+
+    info!(
+        "Found {} uploadable results; skipped {num_skipped} entries",
+        jobs.len()
+    );
+
+    let uploader = UploadToStride::new_with_server(args.solution_server.clone())?;
+    for batch in jobs.chunks(UPLOAD_BATCH_SIZE) {
+        let best_scores = uploader.upload(batch).await?;
+        debug!("Server reported {} best known scores", best_scores.len());
+    }
+
+    info!("Uploaded {} results", jobs.len());
+
+    Ok(())
+}
+
+/// Reconstructs the upload descriptor of a summary entry. Returns `None` if the entry has
+/// no idigest, or its result is not uploadable (e.g., `SolverError`, `Valid` without trees, or
+/// `Timeout` without runtime)
+fn job_description_from_row(row: &Map<String, Value>) -> Option<JobDescription> {
+    let idigest: InstanceDigest =
+        serde_json::from_value(row.get(JSON_KEY_INSTANCE_HASH)?.clone()).ok()?;
+
+    // summaries of older versions only contain the profiler's measurement
+    let runtime = row
+        .get(JSON_KEY_RUNTIME)
+        .or_else(|| row.get(JSON_KEY_WALL_TIME))
+        .and_then(Value::as_f64)
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+
+    match row.get(JSON_KEY_JOB_RESULT)?.as_str()? {
+        "Valid" => {
+            let Some(trees) = row.get(JSON_KEY_SOLUTION_TREES).and_then(Value::as_array) else {
+                debug!("Valid entry of {idigest} has no solution trees; skip");
+                return None;
+            };
+
+            let mut trees = trees
+                .iter()
+                .map(|t| t.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(JobDescription::valid_from_strings(
+                idigest, &mut trees, runtime,
+            ))
+        }
+        "Infeasible" => Some(JobDescription::infeasible(idigest, runtime)),
+        "Timeout" => Some(JobDescription::timeout(idigest, runtime?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDIGEST: &str = "00000000000000000000000000000001";
+
+    fn description_of(row: Value) -> Option<Value> {
+        job_description_from_row(row.as_object().unwrap())
+            .map(|desc| serde_json::to_value(desc).unwrap())
+    }
+
+    fn idigest() -> InstanceDigest {
+        IDIGEST.try_into().unwrap()
+    }
+
+    #[test]
+    fn entries_without_idigest_are_skipped() {
+        let row: Value = serde_json::json!({
+            "s_name": "test",
+            "s_result": "Valid",
+            "s_score": 1,
+            "s_solution_trees": ["(1,2);"],
+        });
+
+        assert!(job_description_from_row(row.as_object().unwrap()).is_none());
+    }
+
+    #[test]
+    fn valid_entries() {
+        let mut trees = vec!["(1,2);".to_string()];
+        let expected = JobDescription::valid_from_strings(
+            idigest(),
+            &mut trees,
+            Some(Duration::from_secs_f64(1.5)),
+        );
+
+        let row = serde_json::json!({
+            "s_idigest": IDIGEST,
+            "s_result": "Valid",
+            "s_score": 1,
+            "s_solution_trees": ["(1,2);"],
+            "s_runtime": 1.5,
+        });
+        assert_eq!(
+            description_of(row),
+            Some(serde_json::to_value(&expected).unwrap())
+        );
+
+        // without stored trees, there is nothing to upload
+        let row = serde_json::json!({
+            "s_idigest": IDIGEST,
+            "s_result": "Valid",
+            "s_score": 1,
+            "s_runtime": 1.5,
+        });
+        assert_eq!(description_of(row), None);
+    }
+
+    #[test]
+    fn timeout_entries() {
+        let expected = JobDescription::timeout(idigest(), Duration::from_secs(10));
+
+        let row = serde_json::json!({
+            "s_idigest": IDIGEST,
+            "s_result": "Timeout",
+            "s_runtime": 10.0,
+        });
+        assert_eq!(
+            description_of(row),
+            Some(serde_json::to_value(&expected).unwrap())
+        );
+
+        // profiled runs of older versions
+        let row = serde_json::json!({
+            "s_idigest": IDIGEST,
+            "s_result": "Timeout",
+            "s_wtime": 10.0,
+        });
+        assert_eq!(
+            description_of(row),
+            Some(serde_json::to_value(&expected).unwrap())
+        );
+
+        let row = serde_json::json!({
+            "s_idigest": IDIGEST,
+            "s_result": "Timeout",
+        });
+        assert_eq!(description_of(row), None);
+    }
+}
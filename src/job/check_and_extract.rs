@@ -1,14 +1,16 @@
+use flate2::read::GzDecoder;
 use std::{
     collections::HashMap,
     fs::File,
-    io::BufReader,
+    io::{BufRead, BufReader},
     path::{Path, PathBuf},
 };
 use thiserror::Error;
 
-use crate::run_directory::CreateInstanceDirError;
+use crate::{job::explain::explain_mismatch, run_directory::CreateInstanceDirError};
 use pace26checker::{
     checks::bin_forest::{BinForest, TreeInsertionError},
+    digest::{algo::digest_instance, digest_output::InstanceDigest},
     io::{
         instance_reader::{self, *},
         solution_reader::*,
@@ -19,6 +21,61 @@ use tracing::{error, warn};
 
 pub type SolutionInfos = (Vec<String>, Vec<(String, serde_json::Value)>);
 
+/// Returns `true` iff the instance at `path` is gzip-compressed (i.e. ends in `.gz`)
+pub fn is_gzipped(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Opens the instance at `path`; gzip-compressed instances are transparently decompressed
+pub fn open_instance(path: &Path) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    Ok(if is_gzipped(path) {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    })
+}
+
+/// Parses a stride line `#s key value` into its key and value; values that are no valid JSON
+/// are kept as strings. Returns `None` for all other lines.
+pub fn parse_stride_line(line: &str) -> Option<(&str, serde_json::Value)> {
+    let (key, value) = line
+        .strip_prefix("#s ")?
+        .trim()
+        .split_once(char::is_whitespace)?;
+
+    let value = value.trim();
+    Some((
+        key,
+        serde_json::from_str(value).unwrap_or_else(|_| value.into()),
+    ))
+}
+
+/// Prefix of keys of stride lines found in the instance (to avoid collisions with solution keys)
+pub const INSTANCE_INFO_PREFIX: &str = "i_";
+
+/// Key of the stride line of an instance stating a lower bound on the solution size
+pub const INSTANCE_KEY_LOWER_BOUND: &str = "lower_bound";
+
+/// Key of the stride line of an instance stating the optimal solution size
+pub const INSTANCE_KEY_OPTIMAL: &str = "optimal";
+
+/// Key of the optimality gap `(score - lower_bound) / lower_bound` of valid solutions
+pub const JSON_KEY_GAP: &str = "s_gap";
+
+/// Key of the per-instance-tree matching statistics (see [`CheckAndExtract::set_match_stats`])
+pub const JSON_KEY_MATCH_STATS: &str = "s_match_stats";
+
+/// Rough upper bound on the memory of a single tree node kept by the checker
+pub const ESTIMATED_BYTES_PER_NODE: u64 = 64;
+
+/// Estimates the peak memory in bytes of [`CheckAndExtract::process`] for an instance with
+/// `num_trees` trees on `num_leaves` leaves: all instance trees are kept in memory while the
+/// solution is matched against one `BinForest` at a time
+pub fn estimate_check_memory(num_trees: u32, num_leaves: u32) -> u64 {
+    (num_trees as u64 + 1) * 2 * num_leaves as u64 * ESTIMATED_BYTES_PER_NODE
+}
+
 #[derive(Default)]
 pub struct CheckAndExtract {
     instance_path: PathBuf,
@@ -29,6 +86,12 @@ pub struct CheckAndExtract {
 
     solution_infos: Vec<(String, serde_json::Value)>,
     solution_forest: Vec<(usize, instance_reader::Tree)>,
+
+    strict_solution: bool,
+    match_stats: Option<Vec<serde_json::Value>>,
+    stride_lines_path: Option<PathBuf>,
+    gap: Option<f64>,
+    explanation: Option<Option<String>>,
 }
 
 #[derive(Error, Debug)]
@@ -48,7 +111,7 @@ pub enum CheckerError {
     #[error("Solution input error: {0}")]
     ForestConstructionError(#[from] TreeInsertionError),
 
-    #[error("Failed to match solution tree in line {} against instance tree in line {}", instance_line + 1, solution_lineno + 1)]
+    #[error("Failed to match solution tree in line {} against instance tree in line {}", solution_lineno + 1, instance_line + 1)]
     SolutionTreeMatchingError {
         instance_line: usize,
         solution_lineno: usize,
@@ -56,6 +119,12 @@ pub enum CheckerError {
 
     #[error("Solution contains no trees")]
     EmptySolution,
+
+    #[error("Instance contains no header or no trees (empty file?)")]
+    EmptyInstance,
+
+    #[error("Solution input warning (strict mode): {0}")]
+    StrictSolutionWarning(String),
 }
 
 impl CheckAndExtract {
@@ -63,6 +132,35 @@ impl CheckAndExtract {
         Self::default()
     }
 
+    /// If set, warnings while reading the solution are treated as errors
+    pub fn set_strict_solution(&mut self, strict: bool) {
+        self.strict_solution = strict;
+    }
+
+    /// If set, the number of solution trees matched against each instance tree (and the
+    /// line of the first unmatched solution tree) are reported in [`JSON_KEY_MATCH_STATS`]
+    pub fn set_match_stats(&mut self, enabled: bool) {
+        self.match_stats = enabled.then(Vec::new);
+    }
+
+    /// If set, `#s` stride lines are additionally read from this file (e.g., the solver's stderr)
+    /// and appended to the solution's stride lines; all other lines of the file are ignored
+    pub fn set_stride_lines_path(&mut self, path: Option<PathBuf>) {
+        self.stride_lines_path = path;
+    }
+
+    /// If set, a human-readable description of the first solution tree that could not be
+    /// matched is kept (see [`CheckAndExtract::explanation`])
+    pub fn set_explain(&mut self, enabled: bool) {
+        self.explanation = enabled.then_some(None);
+    }
+
+    /// Returns the description of the matching failure, if explaining was enabled and the
+    /// solution failed with [`CheckerError::SolutionTreeMatchingError`]
+    pub fn explanation(&self) -> Option<&str> {
+        self.explanation.as_ref()?.as_deref()
+    }
+
     pub fn process(
         &mut self,
         instance_path: &Path,
@@ -70,24 +168,69 @@ impl CheckAndExtract {
     ) -> Result<usize, CheckerError> {
         self.read_instance(instance_path)?;
         self.read_solution(solution_path)?;
+        if let Some(path) = self.stride_lines_path.clone() {
+            self.read_stride_lines(&path)?;
+        }
+
+        let size = self.check_solution()?;
+        self.gap = self
+            .lower_bound()
+            .filter(|&lb| lb > 0)
+            .map(|lb| (size as f64 - lb as f64) / lb as f64);
 
-        self.check_solution()
+        Ok(size)
+    }
+
+    /// Reads the instance at `path` (transparently decompressing it) and returns its digest
+    pub fn instance_digest(mut self, path: &Path) -> Result<InstanceDigest, CheckerError> {
+        self.read_instance(path)?;
+        let trees = self
+            .instance_trees
+            .into_iter()
+            .map(|(_, tree)| tree)
+            .collect::<Vec<_>>();
+        Ok(digest_instance(trees, self.instance_num_leaves))
     }
 
+    /// Returns the lower bound stated by the instance's `#s lower_bound` line, if any
+    pub fn lower_bound(&self) -> Option<u64> {
+        self.instance_infos
+            .get(INSTANCE_KEY_LOWER_BOUND)
+            .and_then(serde_json::Value::as_u64)
+    }
+
+    /// Returns the solution trees and stride lines. The latter also include the instance's
+    /// stride lines whose keys are prefixed by [`INSTANCE_INFO_PREFIX`]
     pub fn into_solution_infos(self) -> SolutionInfos {
         let tree = self
             .solution_forest
             .into_iter()
             .map(|(_, t)| t.top_down().to_newick_string())
             .collect();
-        (tree, self.solution_infos)
+
+        let mut infos: Vec<_> = self
+            .instance_infos
+            .into_iter()
+            .map(|(key, value)| (format!("{INSTANCE_INFO_PREFIX}{key}"), value))
+            .collect();
+        infos.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        infos.extend(self.solution_infos);
+
+        if let Some(gap) = self.gap {
+            infos.push((JSON_KEY_GAP.into(), gap.into()));
+        }
+
+        if let Some(stats) = self.match_stats {
+            infos.push((JSON_KEY_MATCH_STATS.into(), serde_json::Value::Array(stats)));
+        }
+
+        (tree, infos)
     }
 
     fn read_instance(&mut self, path: &Path) -> Result<(), CheckerError> {
         self.instance_path = path.to_path_buf();
 
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
+        let mut reader = open_instance(path)?;
         let mut visitor = InstanceInputVisitor::process(&mut reader);
 
         for e in &visitor.errors {
@@ -101,7 +244,18 @@ impl CheckAndExtract {
             return Err(CheckerError::InstanceInputError(visitor.errors.remove(0)));
         }
 
-        self.instance_num_leaves = visitor.header.unwrap().1; // safe since the reader would raise an InstanceInputError::NoHeader error if there is no header
+        // the reader should report a missing header as error, but we rather not rely on it
+        // (e.g., for empty files)
+        let Some((_, num_leaves)) = visitor.header else {
+            error!("[{:?}] {}", self.instance_path, CheckerError::EmptyInstance);
+            return Err(CheckerError::EmptyInstance);
+        };
+        self.instance_num_leaves = num_leaves;
+
+        if visitor.trees.is_empty() {
+            error!("[{:?}] {}", self.instance_path, CheckerError::EmptyInstance);
+            return Err(CheckerError::EmptyInstance);
+        }
 
         self.instance_trees = std::mem::take(&mut visitor.trees);
         for (key, value) in visitor.stride_lines {
@@ -120,7 +274,11 @@ impl CheckAndExtract {
             error!("[{:?}] {e:?}", self.instance_path);
         }
         for w in &visitor.warnings {
-            warn!("[{:?}] {w:?}", self.instance_path);
+            if self.strict_solution {
+                error!("[{:?}] {w:?}", self.instance_path);
+            } else {
+                warn!("[{:?}] {w:?}", self.instance_path);
+            }
         }
 
         if !visitor.found_tree_line {
@@ -136,12 +294,30 @@ impl CheckAndExtract {
             return Err(CheckerError::SolutionInputError(visitor.errors.remove(0)));
         }
 
+        if self.strict_solution
+            && let Some(w) = visitor.warnings.first()
+        {
+            return Err(CheckerError::StrictSolutionWarning(format!("{w:?}")));
+        }
+
         self.solution_infos = std::mem::take(&mut visitor.stride_lines);
         self.solution_forest = std::mem::take(&mut visitor.trees);
 
         Ok(())
     }
 
+    fn read_stride_lines(&mut self, path: &Path) -> Result<(), CheckerError> {
+        let content = std::fs::read_to_string(path)?;
+        self.solution_infos.extend(
+            content
+                .lines()
+                .filter_map(parse_stride_line)
+                .map(|(key, value)| (key.to_string(), value)),
+        );
+
+        Ok(())
+    }
+
     fn check_solution(&mut self) -> Result<usize, CheckerError> {
         assert!(!self.instance_trees.is_empty()); // should be handled by reader tests
         let solution_size = self.solution_forest.len();
@@ -151,16 +327,41 @@ impl CheckAndExtract {
             let mut forest = BinForest::new(self.instance_num_leaves);
             forest = forest.add_tree(instance_tree.clone())?;
 
-            for (sol_line, subtree) in &self.solution_forest {
+            for (matched, (sol_line, subtree)) in self.solution_forest.iter().enumerate() {
                 if let Some(f) = forest.isolate_tree(subtree) {
                     forest = f;
                 } else {
+                    if let Some(stats) = &mut self.match_stats {
+                        stats.push(serde_json::json!({
+                            "instance_line": instance_lineno + 1,
+                            "solution_line": sol_line + 1,
+                            "matched": matched,
+                        }));
+                    }
+
+                    if let Some(explanation) = &mut self.explanation {
+                        *explanation = Some(explain_mismatch(
+                            instance_lineno + 1,
+                            &instance_tree.top_down().to_newick_string(),
+                            sol_line + 1,
+                            &subtree.top_down().to_newick_string(),
+                            matched,
+                        ));
+                    }
+
                     return Err(CheckerError::SolutionTreeMatchingError {
                         instance_line: instance_lineno,
                         solution_lineno: *sol_line,
                     });
                 }
             }
+
+            if let Some(stats) = &mut self.match_stats {
+                stats.push(serde_json::json!({
+                    "instance_line": instance_lineno + 1,
+                    "matched": solution_size,
+                }));
+            }
         }
 
         Ok(solution_size)
@@ -172,6 +373,21 @@ mod tests {
     use super::*;
     use crate::test_helpers::*;
 
+    #[test]
+    fn stride_lines() {
+        assert_eq!(
+            parse_stride_line("#s iterations 42"),
+            Some(("iterations", serde_json::json!(42)))
+        );
+        assert_eq!(
+            parse_stride_line("#s heuristic  greedy fast "),
+            Some(("heuristic", serde_json::json!("greedy fast")))
+        );
+        assert_eq!(parse_stride_line("#s nokey"), None);
+        assert_eq!(parse_stride_line("#x key 1"), None);
+        assert_eq!(parse_stride_line("(1,2);"), None);
+    }
+
     #[test]
     fn test_valid_solutions() {
         let instances = test_cases_glob("valid_solutions");
@@ -225,4 +441,212 @@ mod tests {
             "No valid instance-solution pairs found for testing"
         );
     }
+
+    #[test]
+    fn test_empty_instance() {
+        let instance_path = test_testcases_dir().join("invalid_instances/empty.in");
+        assert_eq!(std::fs::metadata(&instance_path).unwrap().len(), 0);
+
+        let mut checker = CheckAndExtract::new();
+        let result = checker.process(&instance_path, &instance_path.with_extension("out"));
+
+        // whether the reader or our guard rejects it, it must not be a panic or a system error
+        assert!(matches!(
+            result,
+            Err(CheckerError::InstanceInputError(_) | CheckerError::EmptyInstance)
+        ));
+    }
+
+    #[test]
+    fn test_match_stats() {
+        let stats_of = |instance_path: &Path| {
+            let mut checker = CheckAndExtract::new();
+            checker.set_match_stats(true);
+            let result = checker.process(instance_path, &instance_path.with_extension("out"));
+
+            let (_, infos) = checker.into_solution_infos();
+            let stats = infos
+                .into_iter()
+                .find(|(key, _)| key == JSON_KEY_MATCH_STATS)
+                .unwrap()
+                .1;
+            (result, stats)
+        };
+
+        let (_, valid) = stats_of(&test_cases_glob("valid_solutions").next().unwrap());
+        let valid = valid.as_array().unwrap();
+        assert!(!valid.is_empty());
+        assert!(valid.iter().all(|s| s.get("solution_line").is_none()));
+
+        // solutions rejected by the reader never reach the matching stage, so only consider
+        // those failing to match
+        let mut num_mismatches = 0;
+        for instance_path in test_cases_glob("invalid_solutions") {
+            let (result, stats) = stats_of(&instance_path);
+            if !matches!(result, Err(CheckerError::SolutionTreeMatchingError { .. })) {
+                continue;
+            }
+
+            let last = stats.as_array().unwrap().last().unwrap();
+            assert!(last.get("solution_line").is_some(), "{instance_path:?}");
+            num_mismatches += 1;
+        }
+        assert!(num_mismatches > 0);
+    }
+
+    #[test]
+    fn test_explanation() {
+        for instance_path in test_cases_glob("invalid_solutions") {
+            let mut checker = CheckAndExtract::new();
+            checker.set_explain(true);
+            let result = checker.process(&instance_path, &instance_path.with_extension("out"));
+
+            let is_matching_error = matches!(
+                result,
+                Err(CheckerError::SolutionTreeMatchingError { .. })
+            );
+            assert_eq!(is_matching_error, checker.explanation().is_some());
+        }
+
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let mut checker = CheckAndExtract::new();
+        checker.set_explain(true);
+        checker
+            .process(&instance_path, &instance_path.with_extension("out"))
+            .unwrap();
+        assert!(checker.explanation().is_none());
+    }
+
+    #[test]
+    fn test_stride_lines_from_file() {
+        let tempdir = tempdir::TempDir::new("stride_lines").unwrap();
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let stderr_path = tempdir.path().join("stderr");
+        std::fs::write(
+            &stderr_path,
+            "some diagnostics\n#s iterations 42\n#s heuristic \"greedy\"\n#x nokey\n",
+        )
+        .unwrap();
+
+        let mut checker = CheckAndExtract::new();
+        checker.set_stride_lines_path(Some(stderr_path));
+        checker
+            .process(&instance_path, &instance_path.with_extension("out"))
+            .unwrap();
+
+        let (_, infos) = checker.into_solution_infos();
+        let value_of = |key: &str| infos.iter().find(|(k, _)| k == key).unwrap().1.clone();
+        assert_eq!(value_of("iterations"), serde_json::json!(42));
+        assert_eq!(value_of("heuristic"), serde_json::json!("greedy"));
+        assert_eq!(infos.iter().filter(|(k, _)| k == "iterations").count(), 1);
+    }
+
+    #[test]
+    fn test_strict_solution() {
+        let tempdir = tempdir::TempDir::new("strict_solution").unwrap();
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let solution_path = instance_path.with_extension("out");
+
+        // superfluous whitespace is only a warning of the solution reader
+        let sloppy_path = tempdir.path().join("sloppy.out");
+        let sloppy: String = std::fs::read_to_string(&solution_path)
+            .unwrap()
+            .lines()
+            .map(|line| format!("{line}  \n"))
+            .collect();
+        std::fs::write(&sloppy_path, sloppy).unwrap();
+
+        let process = |solution_path: &Path, strict| {
+            let mut checker = CheckAndExtract::new();
+            checker.set_strict_solution(strict);
+            checker.process(&instance_path, solution_path)
+        };
+
+        assert!(process(&solution_path, true).is_ok());
+        assert!(process(&sloppy_path, false).is_ok());
+        assert!(matches!(
+            process(&sloppy_path, true),
+            Err(CheckerError::StrictSolutionWarning(_))
+        ));
+    }
+
+    #[test]
+    fn test_instance_stride_lines() {
+        let tempdir = tempdir::TempDir::new("instance_infos").unwrap();
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let content = std::fs::read_to_string(&instance_path).unwrap();
+        let (header, trees) = content.split_once('\n').unwrap();
+
+        let annotated_path = tempdir.path().join("annotated.in");
+        std::fs::write(
+            &annotated_path,
+            format!("{header}\n#s source \"generator\"\n#s {INSTANCE_KEY_OPTIMAL} 5\n{trees}"),
+        )
+        .unwrap();
+
+        let mut checker = CheckAndExtract::new();
+        checker
+            .process(&annotated_path, &instance_path.with_extension("out"))
+            .unwrap();
+
+        // the instance's stride lines come first, prefixed and sorted by key
+        let (_, infos) = checker.into_solution_infos();
+        let num_instance_infos = infos
+            .iter()
+            .take_while(|(key, _)| key.starts_with(INSTANCE_INFO_PREFIX))
+            .count();
+        let instance_infos = &infos[..num_instance_infos];
+        assert!(instance_infos.is_sorted_by(|(a, _), (b, _)| a <= b));
+        assert!(instance_infos.contains(&("i_optimal".into(), serde_json::json!(5))));
+        assert!(instance_infos.contains(&("i_source".into(), serde_json::json!("generator"))));
+        assert!(infos.iter().all(|(key, _)| key != "source"));
+    }
+
+    #[test]
+    fn test_estimate_check_memory() {
+        assert_eq!(estimate_check_memory(0, 0), 0);
+        assert_eq!(
+            estimate_check_memory(1, 10),
+            2 * 2 * 10 * ESTIMATED_BYTES_PER_NODE
+        );
+        // must not overflow for the largest possible headers
+        assert!(estimate_check_memory(u32::MAX, u32::MAX) > 0);
+    }
+
+    #[test]
+    fn test_gap() {
+        let tempdir = tempdir::TempDir::new("gap").unwrap();
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let solution_path = instance_path.with_extension("out");
+
+        let mut checker = CheckAndExtract::new();
+        let size = checker.process(&instance_path, &solution_path).unwrap();
+        assert!(checker.lower_bound().is_none());
+        let (_, infos) = checker.into_solution_infos();
+        assert!(infos.iter().all(|(key, _)| key != JSON_KEY_GAP));
+
+        // state a lower bound of half the solution size (after the header line)
+        let lower_bound = (size / 2).max(1);
+        let content = std::fs::read_to_string(&instance_path).unwrap();
+        let (header, trees) = content.split_once('\n').unwrap();
+        let bounded_path = tempdir.path().join("bounded.in");
+        std::fs::write(
+            &bounded_path,
+            format!("{header}\n#s {INSTANCE_KEY_LOWER_BOUND} {lower_bound}\n{trees}"),
+        )
+        .unwrap();
+
+        let mut checker = CheckAndExtract::new();
+        checker.process(&bounded_path, &solution_path).unwrap();
+        assert_eq!(checker.lower_bound(), Some(lower_bound as u64));
+        let (_, infos) = checker.into_solution_infos();
+        let gap = infos
+            .iter()
+            .find(|(key, _)| key == JSON_KEY_GAP)
+            .unwrap()
+            .1
+            .as_f64()
+            .unwrap();
+        assert_eq!(gap, (size - lower_bound) as f64 / lower_bound as f64);
+    }
 }
@@ -0,0 +1,138 @@
+//! Pinning of solvers to single CPUs (see `stride run --pin-cpus`)
+
+use std::sync::{Arc, Mutex};
+
+/// Returns the ids of the CPUs the runner may run on (e.g. restricted by `taskset`)
+#[cfg(target_os = "linux")]
+pub fn allowed_cpus() -> Vec<usize> {
+    // SAFETY: cpu_set_t is a plain bit set; all zeros is the empty set
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, std::mem::size_of_val(&cpu_set), &mut cpu_set) } != 0 {
+        return (0..num_cpus::get()).collect();
+    }
+
+    (0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &cpu_set) })
+        .collect()
+}
+
+/// Without an affinity mask, we assume the CPUs to be numbered consecutively
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cpus() -> Vec<usize> {
+    (0..num_cpus::get()).collect()
+}
+
+/// Assigns each job the CPU currently running the fewest jobs; ties are broken round-robin, so
+/// that jobs get distinct CPUs as long as there are at least as many CPUs as parallel jobs
+pub struct CpuScheduler {
+    cpus: Vec<usize>,
+    state: Mutex<SchedulerState>,
+}
+
+struct SchedulerState {
+    running: Vec<usize>,
+    /// index of the CPU at which the search for the next assignment starts
+    next: usize,
+}
+
+impl CpuScheduler {
+    pub fn new(cpus: Vec<usize>) -> Self {
+        assert!(!cpus.is_empty());
+        let state = Mutex::new(SchedulerState {
+            running: vec![0; cpus.len()],
+            next: 0,
+        });
+        Self { cpus, state }
+    }
+
+    pub fn num_cpus(&self) -> usize {
+        self.cpus.len()
+    }
+
+    /// Returns the least loaded CPU; it counts as running a job until the lease is dropped
+    pub fn acquire(self: &Arc<Self>) -> CpuLease {
+        let mut state = self.state.lock().unwrap();
+        let num_cpus = self.cpus.len();
+        let index = (0..num_cpus)
+            .map(|i| (state.next + i) % num_cpus)
+            .min_by_key(|&i| state.running[i])
+            .expect("at least one CPU");
+        state.running[index] += 1;
+        state.next = (index + 1) % num_cpus;
+
+        CpuLease {
+            scheduler: self.clone(),
+            index,
+        }
+    }
+}
+
+pub struct CpuLease {
+    scheduler: Arc<CpuScheduler>,
+    index: usize,
+}
+
+impl CpuLease {
+    pub fn cpu(&self) -> usize {
+        self.scheduler.cpus[self.index]
+    }
+}
+
+impl Drop for CpuLease {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().running[self.index] -= 1;
+    }
+}
+
+/// Returns a closure (to be executed between `fork` and `exec`) restricting the calling process
+/// to `cpu`. The CPU set is prepared up front, so that the closure does not allocate.
+#[cfg(target_os = "linux")]
+pub fn pin_to_cpu(cpu: usize) -> impl FnMut() -> std::io::Result<()> + Send + Sync + 'static {
+    // SAFETY: cpu_set_t is a plain bit set; all zeros is the empty set
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+
+    move || {
+        if unsafe { libc::sched_setaffinity(0, std::mem::size_of_val(&cpu_set), &cpu_set) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// CPU pinning is only supported on Linux
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_cpu(_cpu: usize) -> impl FnMut() -> std::io::Result<()> + Send + Sync + 'static {
+    || Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_robin_over_free_cpus() {
+        let scheduler = Arc::new(CpuScheduler::new(vec![2, 5, 7]));
+
+        let first = scheduler.acquire();
+        let second = scheduler.acquire();
+        assert_eq!((first.cpu(), second.cpu()), (2, 5));
+
+        // a released CPU is reused only once it is the least loaded one
+        drop(first);
+        let third = scheduler.acquire();
+        assert_eq!(third.cpu(), 7);
+        let fourth = scheduler.acquire();
+        assert_eq!(fourth.cpu(), 2);
+
+        // with more jobs than CPUs, they are shared
+        let fifth = scheduler.acquire();
+        assert_eq!(fifth.cpu(), 5);
+    }
+
+    #[test]
+    fn allowed_cpus_are_nonempty() {
+        assert!(!allowed_cpus().is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+//! Human-readable diagnostics for solution trees that cannot be matched against an instance
+//! tree (see `stride check --explain`)
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Minimal representation of a Newick tree whose leaves are labeled by numbers
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    Leaf(u32),
+    Inner(Vec<Node>),
+}
+
+impl Node {
+    /// Parses a tree as written by `NewickWriter`, e.g. `((1,2),3);`
+    fn parse(newick: &str) -> Option<Self> {
+        let mut chars = newick.trim().trim_end_matches(';').chars().peekable();
+        let node = Self::parse_node(&mut chars)?;
+        chars.next().is_none().then_some(node)
+    }
+
+    fn parse_node(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<Self> {
+        if chars.peek() == Some(&'(') {
+            chars.next();
+            let mut children = vec![Self::parse_node(chars)?];
+            loop {
+                match chars.next()? {
+                    ',' => children.push(Self::parse_node(chars)?),
+                    ')' => return Some(Node::Inner(children)),
+                    _ => return None,
+                }
+            }
+        }
+
+        let mut label = String::new();
+        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            label.push(c);
+            chars.next();
+        }
+        label.parse().ok().map(Node::Leaf)
+    }
+
+    fn leaves(&self, leaves: &mut BTreeSet<u32>) {
+        match self {
+            Node::Leaf(x) => {
+                leaves.insert(*x);
+            }
+            Node::Inner(children) => children.iter().for_each(|c| c.leaves(leaves)),
+        }
+    }
+
+    /// Returns the tree induced by `leaves`, with inner nodes of degree one suppressed
+    fn restrict(&self, leaves: &BTreeSet<u32>) -> Option<Self> {
+        match self {
+            Node::Leaf(x) => leaves.contains(x).then_some(Node::Leaf(*x)),
+            Node::Inner(children) => {
+                let mut children: Vec<_> =
+                    children.iter().filter_map(|c| c.restrict(leaves)).collect();
+                match children.len() {
+                    0 => None,
+                    1 => children.pop(),
+                    _ => Some(Node::Inner(children)),
+                }
+            }
+        }
+    }
+
+    /// Orders children by their smallest leaf, so equal topologies yield equal trees
+    fn canonicalize(&mut self) -> u32 {
+        match self {
+            Node::Leaf(x) => *x,
+            Node::Inner(children) => {
+                let mut keyed: Vec<_> = std::mem::take(children)
+                    .into_iter()
+                    .map(|mut c| (c.canonicalize(), c))
+                    .collect();
+                keyed.sort_unstable_by_key(|(min, _)| *min);
+                let min = keyed[0].0;
+                *children = keyed.into_iter().map(|(_, c)| c).collect();
+                min
+            }
+        }
+    }
+
+    fn to_newick(&self, out: &mut String) {
+        match self {
+            Node::Leaf(x) => write!(out, "{x}").unwrap(),
+            Node::Inner(children) => {
+                out.push('(');
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    child.to_newick(out);
+                }
+                out.push(')');
+            }
+        }
+    }
+}
+
+fn canonical_newick(mut node: Node) -> String {
+    node.canonicalize();
+    let mut out = String::new();
+    node.to_newick(&mut out);
+    out.push(';');
+    out
+}
+
+/// Describes why the solution tree `solution_tree` (in line `solution_line`) could not be
+/// isolated in the instance tree `instance_tree` (in line `instance_line`), after
+/// `num_matched` solution trees were isolated successfully. Both trees are given in Newick.
+pub fn explain_mismatch(
+    instance_line: usize,
+    instance_tree: &str,
+    solution_line: usize,
+    solution_tree: &str,
+    num_matched: usize,
+) -> String {
+    let mut out = String::new();
+    writeln!(out, "Instance tree (line {instance_line}): {instance_tree}").unwrap();
+    writeln!(out, "Solution tree (line {solution_line}): {solution_tree}").unwrap();
+
+    let (Some(instance), Some(solution)) = (Node::parse(instance_tree), Node::parse(solution_tree))
+    else {
+        return out;
+    };
+
+    let mut leaves = BTreeSet::new();
+    solution.leaves(&mut leaves);
+    let leaves_str: Vec<_> = leaves.iter().map(u32::to_string).collect();
+    writeln!(
+        out,
+        "Leaves of the solution tree: {{{}}}",
+        leaves_str.join(", ")
+    )
+    .unwrap();
+
+    let Some(induced) = instance.restrict(&leaves) else {
+        return out;
+    };
+    let induced = canonical_newick(induced);
+    let solution = canonical_newick(solution);
+
+    if induced != solution {
+        writeln!(
+            out,
+            "The instance tree restricted to these leaves is {induced},"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "but the solution tree is {solution} (both with sorted children);"
+        )
+        .unwrap();
+        write!(
+            out,
+            "so the solution tree does not agree with the instance tree."
+        )
+        .unwrap();
+    } else {
+        writeln!(
+            out,
+            "The topology agrees with the instance tree, but its subtree in the instance tree"
+        )
+        .unwrap();
+        write!(
+            out,
+            "overlaps with those of the {num_matched} solution tree(s) isolated before."
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_restrict() {
+        let tree = Node::parse("(((4,5),6),(1,(2,3)));").unwrap();
+        let leaves = BTreeSet::from([2, 4, 6, 3]);
+        let induced = canonical_newick(tree.restrict(&leaves).unwrap());
+        assert_eq!(induced, "((2,3),(4,6));");
+
+        assert!(Node::parse("((1,2),3").is_none());
+        assert!(Node::parse("(1,x);").is_none());
+    }
+
+    #[test]
+    fn explain_topology() {
+        let explanation =
+            explain_mismatch(2, "(((4,5),6),(1,(2,3)));", 1, "(((4,6),5),(1,(2,3)));", 0);
+        assert!(explanation.contains("{1, 2, 3, 4, 5, 6}"), "{explanation}");
+        assert!(
+            explanation.contains("((1,(2,3)),((4,5),6));"),
+            "{explanation}"
+        );
+        assert!(explanation.contains("does not agree"), "{explanation}");
+    }
+
+    #[test]
+    fn explain_overlap() {
+        let explanation = explain_mismatch(1, "((1,2),(3,4));", 2, "(2,3);", 1);
+        assert!(explanation.contains("overlaps"), "{explanation}");
+    }
+}
@@ -1,17 +1,31 @@
 use derive_builder::Builder;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::any::Any;
+use std::borrow::Cow;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::process::ExitStatusExt;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::Path;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::sync::{Notify, Semaphore};
 use tokio::task::JoinError;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
-use crate::job::check_and_extract::SolutionInfos;
+use crate::job::check_and_extract::{SolutionInfos, parse_stride_line};
 use crate::{
-    commands::arguments,
     job::{
         check_and_extract::{CheckAndExtract, CheckerError},
-        solver_executor::{self, ChildExitStatus, ExecutorError, SolverExecutorBuilder},
+        numa::NumaNode,
+        result_classifier::{DefaultResultClassifier, ResultClassifier},
+        solver_executor::{
+            self, ChildExitStatus, ChildUsage, ExecutorError, HEADER_CMD, HEADER_INSTANCE,
+            SolverExecutorBuilder,
+        },
     },
     run_directory::CreateInstanceDirError,
 };
@@ -36,6 +50,24 @@ pub enum JobError {
     JoinError(#[from] JoinError),
 }
 
+impl JobError {
+    /// Returns `true` if the error was caused by a full filesystem (`ENOSPC`)
+    pub fn is_disk_full(&self) -> bool {
+        match self {
+            JobError::Io(e)
+            | JobError::Executor(ExecutorError::Io(e))
+            | JobError::Checker(CheckerError::Io(e))
+            | JobError::CreateInstanceDirError(CreateInstanceDirError::Io(e)) => is_disk_full(e),
+            _ => false,
+        }
+    }
+}
+
+/// Returns `true` if `e` indicates a full filesystem (`ENOSPC`)
+pub fn is_disk_full(e: &std::io::Error) -> bool {
+    e.kind() == std::io::ErrorKind::StorageFull
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub enum JobProgress {
     #[default]
@@ -93,6 +125,11 @@ pub enum JobResult {
     SystemError,
     SolverError,
     Timeout,
+    CheckerPanic,
+    Cancelled,
+    OutputOverflow,      // terminated early as the output exceeded `max_output_rate`
+    MemoryLimitExceeded, // crashed while the address space was limited by `memory_limit`
+    Unchecked { size: usize }, // size of the solver's `#s s_score` line (see `trust_solver`)
 }
 
 impl JobResult {
@@ -113,11 +150,63 @@ impl Display for JobResult {
             JobResult::SystemError => "SystemError",
             JobResult::SolverError => "SolverError",
             JobResult::Timeout => "Timeout",
+            JobResult::CheckerPanic => "CheckerPanic",
+            JobResult::Cancelled => "Cancelled",
+            JobResult::OutputOverflow => "OutputOverflow",
+            JobResult::MemoryLimitExceeded => "MemoryLimitExceeded",
+            JobResult::Unchecked { .. } => "Unchecked",
         });
         write!(f, "{}", str)
     }
 }
 
+// summary keys of stride-reported infos; see also `commands::run::summary_writer`
+const JSON_KEY_CHECKER_PANIC: &str = "s_checker_panic";
+const JSON_KEY_CPU_LIMITED: &str = "s_cpu_limited";
+/// `cpu` if a `Timeout` stems from `cpu_timeout`, `wall` if it stems from `soft_timeout`
+const JSON_KEY_TIMEOUT_KIND: &str = "s_timeout_kind";
+const JSON_KEY_RESOURCE_LIMITS: &str = "s_resource_limits";
+const JSON_KEY_CORE_PATH: &str = "s_core_path";
+const JSON_KEY_EARLY_OUTPUT: &str = "s_early_output";
+const JSON_KEY_STDERR_TAIL: &str = "s_stderr_tail";
+pub const JSON_KEY_STDOUT_SHA: &str = "s_stdout_sha";
+pub const JSON_KEY_CHECK_KEY: &str = "s_check_key";
+pub const JSON_KEY_REUSED: &str = "s_reused";
+const JSON_KEY_TRANSFORM_ERROR: &str = "s_transform_error";
+
+/// Files in the work dir receiving the output of `solution_transform`; the raw output of the
+/// solver remains in `stdout`
+pub const PATH_TRANSFORMED_STDOUT: &str = "stdout.transformed";
+const PATH_TRANSFORM_STDERR: &str = "stderr.transform";
+const TRANSFORM_SHELL: &str = "/bin/sh";
+
+/// Key of the stride line by which solvers may report their score (used with `trust_solver`)
+const SOLVER_KEY_SCORE: &str = "s_score";
+
+pub const DEFAULT_ENV_PREFIX: &str = "STRIDE_";
+const ENV_SUFFIX_INSTANCE_PATH: &str = "INSTANCE_PATH";
+const ENV_SUFFIX_TIMEOUT: &str = "TIMEOUT";
+const ENV_SUFFIX_GRACE: &str = "GRACE";
+
+const CHECKER_IO_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Either the solution infos and the checker's result, or the payload of a panic of the checker
+type CheckerOutcome =
+    Result<(SolutionInfos, Result<usize, CheckerError>), Box<dyn Any + Send + 'static>>;
+
+/// Verdict of the checker recorded by a previous run (see `--reuse-unchanged`)
+#[derive(Debug, Clone)]
+pub struct PriorVerdict {
+    pub stdout_sha: String,
+    /// see [`check_key`]
+    pub check_key: String,
+    pub result: JobResult,
+    pub infos: SolutionInfos,
+}
+
+/// Variables set with `deterministic_env` to avoid locale- and timezone-dependent behavior
+const DETERMINISTIC_ENVS: [(&str, &str); 2] = [("LC_ALL", "C"), ("TZ", "UTC")];
+
 #[derive(Builder)]
 pub struct JobProcessor {
     work_dir: PathBuf,
@@ -127,6 +216,10 @@ pub struct JobProcessor {
     soft_timeout: Duration,
     grace_period: Duration,
 
+    #[builder(default = "libc::SIGTERM")]
+    /// signal sent to the solver at the soft timeout; `SIGKILL` follows after the grace period
+    term_signal: libc::c_int,
+
     #[builder(default)]
     solver_args: Vec<String>,
 
@@ -140,9 +233,130 @@ pub struct JobProcessor {
     /// use own binary if omitted
     profiler_executable: Option<PathBuf>,
 
+    #[builder(default)]
+    /// limit of the solver's CPU time (enforced by the kernel); independent of `soft_timeout`
+    cpu_timeout: Option<Duration>,
+
+    #[builder(default)]
+    /// limit of the solver's open file descriptors (`RLIMIT_NOFILE`)
+    max_fds: Option<u64>,
+
+    #[builder(default)]
+    /// limit of the processes of the solver's user (`RLIMIT_NPROC`)
+    max_procs: Option<u64>,
+
+    #[builder(default)]
+    /// limit of the solver's address space in bytes (`RLIMIT_AS`)
+    memory_limit: Option<u64>,
+
+    #[builder(default)]
+    /// run the solver in `work_dir` with core dumps enabled and report found dumps in `s_core_path`
+    capture_cores: bool,
+
+    #[builder(default)]
+    /// bind the solver to the CPUs and the memory of this NUMA node
+    numa_node: Option<NumaNode>,
+
+    #[builder(default)]
+    /// pin the solver to this CPU
+    cpu: Option<usize>,
+
+    #[builder(default)]
+    /// pipe the instance to the solver and report in `s_early_output` if it wrote to stdout
+    /// before the instance was completely written
+    detect_early_output: bool,
+
+    #[builder(default)]
+    /// terminate the solver if it writes more than this many bytes per second to stdout
+    max_output_rate: Option<u64>,
+
+    #[builder(default)]
+    /// if notified, the solver is terminated early and the job reported as `Cancelled`
+    cancel: Option<Arc<Notify>>,
+
+    #[builder(default)]
+    /// treat warnings while reading the solution as errors (i.e. `SyntaxError`)
+    strict_solution: bool,
+
+    #[builder(default)]
+    /// skip the checker and report `Unchecked` with the score stated by the solver (unsafe!)
+    trust_solver: bool,
+
+    #[builder(default)]
+    /// report per-instance-tree matching statistics of the checker in the solution infos
+    verbose_check: bool,
+
+    #[builder(default)]
+    /// additionally scan the solver's stderr for `#s` stride lines (the solution is still read from stdout)
+    stride_lines_from_stderr: bool,
+
+    #[builder(default)]
+    /// for results other than `Valid`, report up to this many trailing bytes of the solver's
+    /// stderr in `s_stderr_tail`; disabled if 0
+    stderr_tail_bytes: usize,
+
+    #[builder(default)]
+    /// shared budget of concurrent checks (in MB) and the estimated memory of this job's check;
+    /// the check waits until the budget allows it
+    check_memory: Option<(Arc<Semaphore>, u32)>,
+
+    #[builder(default)]
+    /// number of times the checker is retried on IO errors before reporting a `SystemError`
+    checker_io_retries: usize,
+
+    #[builder(default)]
+    /// shell command that reads the solver's stdout and writes the solution passed to the checker
+    solution_transform: Option<String>,
+
+    #[builder(default)]
+    /// reported instead of running the checker if the solver's stdout and the check key match
+    prior_verdict: Option<PriorVerdict>,
+
+    #[builder(default)]
+    /// report `prior_verdict` without running the solver if the check key matches
+    reuse_without_solver: bool,
+
+    #[builder(default)]
+    /// state `s_check_key` even without a `prior_verdict`, so that a later run can reuse the verdict
+    record_check_key: bool,
+
+    #[builder(default, setter(skip))]
+    reused: AtomicBool,
+
+    #[builder(default = "Arc::new(DefaultResultClassifier)")]
+    /// maps the solver's exit status and the checker's outcome to the job's result
+    classifier: Arc<dyn ResultClassifier>,
+
     #[builder(default)]
     set_stride_envs: bool,
 
+    #[builder(default = "String::from(DEFAULT_ENV_PREFIX)")]
+    /// prefix of the environment variables set if `set_stride_envs` is enabled
+    env_prefix: String,
+
+    #[builder(default)]
+    /// set `LC_ALL=C` and `TZ=UTC`, and remove the variables in `cleared_envs`
+    deterministic_env: bool,
+
+    #[builder(default)]
+    /// variables removed from the solver's environment if `deterministic_env` is enabled
+    cleared_envs: Vec<String>,
+
+    #[builder(default)]
+    /// do not inherit the runner's environment, except for variables matching `kept_envs`
+    clean_env: bool,
+
+    #[builder(default)]
+    /// patterns of inherited variables retained if `clean_env` is enabled
+    kept_envs: Vec<glob::Pattern>,
+
+    #[builder(default)]
+    /// additional environment variables; values may contain placeholders, see [`JobProcessor::expand_placeholders`]
+    extra_envs: Vec<(String, String)>,
+
+    #[builder(default, setter(skip))]
+    disk_full: AtomicBool,
+
     // somewhat crude hack to avoid using mutexes: we will never measure a runtime <1ms (otherwise
     // it's set to 1). So 0 indicates no measurement
     #[builder(default, setter(skip))]
@@ -166,6 +380,16 @@ impl JobProcessor {
         self.progress.load()
     }
 
+    /// Returns `true` if the job failed as the filesystem of the work dir is full
+    pub fn disk_full(&self) -> bool {
+        self.disk_full.load(Ordering::Acquire)
+    }
+
+    /// Returns `true` if the verdict was taken from `prior_verdict`
+    pub fn reused(&self) -> bool {
+        self.reused.load(Ordering::Acquire)
+    }
+
     pub fn runtime(&self) -> Option<Duration> {
         let ms = self.solver_runtime_millis.load(Ordering::Acquire);
         (ms > 0).then(|| Duration::from_millis(ms))
@@ -175,16 +399,54 @@ impl JobProcessor {
         let result = self.run_internal().await;
         self.progress.store(JobProgress::Finished);
 
-        result.unwrap_or_else(|e| {
+        let (job_result, mut infos) = result.unwrap_or_else(|e| {
             error!("{e}");
+            if e.is_disk_full() {
+                self.disk_full.store(true, Ordering::Release);
+            }
             (JobResult::SystemError, None)
-        })
+        });
+
+        // spare digging through the work dir for the reason of a failure
+        if !job_result.is_valid()
+            && self.stderr_tail_bytes > 0
+            && let Some(tail) = read_tail(&self.stderr_path(), self.stderr_tail_bytes)
+        {
+            infos
+                .get_or_insert_default()
+                .1
+                .push((JSON_KEY_STDERR_TAIL.into(), Value::String(tail)));
+        }
+
+        (job_result, infos)
     }
 
     pub async fn run_internal(&self) -> Result<(JobResult, Option<SolutionInfos>), JobError> {
         let solution_path = self.work_dir.join(solver_executor::PATH_STDOUT);
 
         debug!("JobProcessor {:?} started", self.instance_path);
+
+        // only verdicts of the checker can be reused; reading the instance is only worth it if the
+        // key is compared or recorded
+        let check_key =
+            if self.trust_solver || (self.prior_verdict.is_none() && !self.record_check_key) {
+                None
+            } else {
+                let instance = tokio::fs::read(&self.instance_path).await?;
+                Some(check_key(
+                    &instance,
+                    self.strict_solution,
+                    self.solution_transform.as_deref(),
+                ))
+            };
+
+        if self.reuse_without_solver
+            && let Some(prior) = &self.prior_verdict
+            && check_key.as_ref() == Some(&prior.check_key)
+        {
+            debug!("JobProcessor {:?} reuses prior verdict", self.instance_path);
+            return Ok(self.reuse_verdict(prior));
+        }
         // TODO: we might want to avoid the clone of path and arguments ...
         let mut executor_builder = SolverExecutorBuilder::default();
 
@@ -192,8 +454,32 @@ impl JobProcessor {
             .instance_path(self.instance_path.clone())
             .working_dir(self.work_dir.clone())
             .env(self.env_vars())
+            .env_remove(if self.deterministic_env {
+                self.cleared_envs.clone()
+            } else {
+                Vec::new()
+            })
+            .clean_env(self.clean_env)
+            .keep_env(self.kept_envs.clone())
             .timeout(self.soft_timeout)
-            .grace(self.grace_period);
+            .grace(self.grace_period)
+            .term_signal(self.term_signal)
+            .cpu_timeout(self.cpu_timeout)
+            .capture_cores(self.capture_cores)
+            .numa_node(self.numa_node.clone())
+            .cpu(self.cpu)
+            .detect_early_output(self.detect_early_output)
+            .max_output_rate(self.max_output_rate)
+            .cancel(self.cancel.clone());
+
+        // with `capture_cores`, the solver runs in the work dir; so relative paths would break
+        let solver = if self.capture_cores {
+            self.solver
+                .canonicalize()
+                .unwrap_or_else(|_| self.solver.clone())
+        } else {
+            self.solver.clone()
+        };
 
         if self.profiler {
             // add indirection
@@ -203,21 +489,35 @@ impl JobProcessor {
                 std::env::current_exe().expect("Failed to get current executable path")
             };
 
-            let solver_path = self
-                .solver
+            let solver_path = solver
                 .as_os_str()
                 .to_str()
                 .expect("Convert solver path into String")
                 .into();
 
-            let mut args: Vec<String> = vec!["p".into(), solver_path, "--".into()];
+            // the profiler applies the resource limits to the solver only; they would also count
+            // its own threads (`RLIMIT_NPROC`) and thread stacks and arenas (`RLIMIT_AS`)
+            let mut args: Vec<String> = vec!["p".into()];
+            for (flag, limit) in [
+                ("--max-fds", self.max_fds),
+                ("--max-procs", self.max_procs),
+                ("--memory-limit", self.memory_limit),
+            ] {
+                if let Some(limit) = limit {
+                    args.extend([flag.into(), limit.to_string()]);
+                }
+            }
+            args.extend([solver_path, "--".into()]);
             args.extend_from_slice(&self.solver_args);
 
             executor_builder.solver_path(profiler_path).args(args);
         } else {
             executor_builder
-                .solver_path(self.solver.clone())
-                .args(self.solver_args.clone());
+                .solver_path(solver)
+                .args(self.solver_args.clone())
+                .max_fds(self.max_fds)
+                .max_procs(self.max_procs)
+                .memory_limit(self.memory_limit);
         }
 
         let mut executor = executor_builder.build().expect("Executor Builder failed"); // if this fails it is a programming error and will always fail 
@@ -235,83 +535,653 @@ impl JobProcessor {
         self.solver_runtime_millis
             .store(start.elapsed().as_millis().max(1) as u64, Ordering::Release);
 
+        let early_output = executor.early_output().then(|| {
+            debug!(
+                "JobProcessor {:?} solver wrote output before reading the whole instance",
+                self.instance_path
+            );
+            (JSON_KEY_EARLY_OUTPUT.to_string(), Value::Bool(true))
+        });
+
         if !exit_status.is_success() {
-            return Ok((
-                match exit_status {
-                    ChildExitStatus::BeforeTimeout(_) | ChildExitStatus::WithinGrace(_) => {
-                        JobResult::SolverError
-                    }
-                    ChildExitStatus::Timeout => JobResult::Timeout,
-                },
-                None,
-            ));
+            let (job_result, mut infos) = match exit_status {
+                ChildExitStatus::BeforeTimeout(status) | ChildExitStatus::WithinGrace(status)
+                    if self.cpu_timeout.is_some_and(|cpu_timeout| {
+                        terminated_by_cpu_limit(status, executor.usage(), cpu_timeout)
+                    }) =>
+                {
+                    debug!("JobProcessor {:?} hit CPU limit", self.instance_path);
+                    let infos = vec![
+                        (JSON_KEY_CPU_LIMITED.into(), Value::Bool(true)),
+                        (JSON_KEY_TIMEOUT_KIND.into(), "cpu".into()),
+                    ];
+                    (JobResult::Timeout, Some((Vec::new(), infos)))
+                }
+                ChildExitStatus::BeforeTimeout(status)
+                    if self.memory_limit.is_some_and(|memory_limit| {
+                        terminated_by_memory_limit(status, executor.usage(), memory_limit)
+                    }) =>
+                {
+                    debug!("JobProcessor {:?} hit memory limit", self.instance_path);
+                    (JobResult::MemoryLimitExceeded, None)
+                }
+                _ => {
+                    let job_result = self.classifier.classify(exit_status, None);
+                    let infos = (job_result == JobResult::Timeout).then(|| {
+                        let infos = vec![(JSON_KEY_TIMEOUT_KIND.into(), "wall".into())];
+                        (Vec::new(), infos)
+                    });
+                    (job_result, infos)
+                }
+            };
+
+            // the solver's failing syscalls may stem from the limits; so report them as context
+            if job_result == JobResult::SolverError
+                && (self.max_fds.is_some()
+                    || self.max_procs.is_some()
+                    || self.memory_limit.is_some())
+            {
+                let mut limits = serde_json::Map::new();
+                if let Some(max_fds) = self.max_fds {
+                    limits.insert("max_fds".into(), max_fds.into());
+                }
+                if let Some(max_procs) = self.max_procs {
+                    limits.insert("max_procs".into(), max_procs.into());
+                }
+                if let Some(memory_limit) = self.memory_limit {
+                    limits.insert("memory_limit".into(), memory_limit.into());
+                }
+                infos
+                    .get_or_insert_default()
+                    .1
+                    .push((JSON_KEY_RESOURCE_LIMITS.into(), Value::Object(limits)));
+            }
+
+            if self.capture_cores
+                && let Some(core_path) = find_core_dump(&self.work_dir)
+            {
+                debug!(
+                    "JobProcessor {:?} found core dump {core_path:?}",
+                    self.instance_path
+                );
+                infos.get_or_insert_default().1.push((
+                    JSON_KEY_CORE_PATH.into(),
+                    // the work dir is moved after the job; so we only report the path within it
+                    Value::String(core_path.file_name().unwrap().to_string_lossy().into()),
+                ));
+            }
+
+            if let Some(info) = early_output {
+                infos.get_or_insert_default().1.push(info);
+            }
+
+            return Ok((job_result, infos));
+        }
+
+        let stdout_sha = stdout_sha256(&tokio::fs::read(&solution_path).await?);
+
+        if let Some(prior) = &self.prior_verdict
+            && prior.stdout_sha == stdout_sha
+            && check_key.as_ref() == Some(&prior.check_key)
+        {
+            debug!(
+                "JobProcessor {:?} output unchanged; reuse prior verdict",
+                self.instance_path
+            );
+            let (job_result, mut infos) = self.reuse_verdict(prior);
+            if let Some(info) = early_output {
+                infos.get_or_insert_default().1.push(info);
+            }
+            return Ok((job_result, infos));
+        }
+
+        let solution_path = match &self.solution_transform {
+            Some(command) => match self.transform_solution(command, &solution_path).await? {
+                Ok(path) => path,
+                Err(reason) => {
+                    error!(
+                        "{:?} Solution transform failed: {reason}",
+                        self.instance_path
+                    );
+                    let infos = vec![
+                        (JSON_KEY_TRANSFORM_ERROR.into(), Value::String(reason)),
+                        (JSON_KEY_STDOUT_SHA.into(), Value::String(stdout_sha)),
+                    ];
+                    return Ok((JobResult::SystemError, Some((Vec::new(), infos))));
+                }
+            },
+            None => solution_path,
+        };
+
+        let (job_result, mut infos) = if self.trust_solver {
+            self.trust_solution(&solution_path).await?
+        } else {
+            self.check_solution(exit_status, solution_path).await?
+        };
+        let extra_infos = &mut infos.get_or_insert_default().1;
+        extra_infos.push((JSON_KEY_STDOUT_SHA.into(), Value::String(stdout_sha)));
+        if let Some(check_key) = check_key {
+            extra_infos.push((JSON_KEY_CHECK_KEY.into(), Value::String(check_key)));
+        }
+        if let Some(info) = early_output {
+            extra_infos.push(info);
         }
 
-        self.check_solution(solution_path).await
+        Ok((job_result, infos))
+    }
+
+    /// Pipes the solver's output through `command` (run by `sh -c`); returns the path of the
+    /// transformed solution or, if the command failed, the reason
+    async fn transform_solution(
+        &self,
+        command: &str,
+        solution_path: &Path,
+    ) -> Result<Result<PathBuf, String>, JobError> {
+        self.progress.store(JobProgress::Checking);
+        let transformed_path = self.work_dir.join(PATH_TRANSFORMED_STDOUT);
+
+        let mut child = match tokio::process::Command::new(TRANSFORM_SHELL)
+            .arg("-c")
+            .arg(command)
+            .stdin(std::fs::File::open(solution_path)?)
+            .stdout(std::fs::File::create(&transformed_path)?)
+            .stderr(std::fs::File::create(
+                self.work_dir.join(PATH_TRANSFORM_STDERR),
+            )?)
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => return Ok(Err(format!("failed to start: {e}"))),
+        };
+
+        // the transform is expected to be fast; a hanging one must not stall the run
+        let status = match tokio::time::timeout(self.soft_timeout, child.wait()).await {
+            Ok(status) => status?,
+            Err(_) => {
+                return Ok(Err(format!(
+                    "no result within {}s",
+                    self.soft_timeout.as_secs()
+                )));
+            }
+        };
+
+        if !status.success() {
+            return Ok(Err(format!("exited with {status}")));
+        }
+
+        trace!(
+            "JobProcessor {:?} transformed solution into {transformed_path:?}",
+            self.instance_path
+        );
+        Ok(Ok(transformed_path))
+    }
+
+    /// Reports `prior` as the result of this job; its digests are carried over, so that the
+    /// verdict can be reused again by later runs
+    fn reuse_verdict(&self, prior: &PriorVerdict) -> (JobResult, Option<SolutionInfos>) {
+        self.reused.store(true, Ordering::Release);
+
+        let (trees, mut infos) = prior.infos.clone();
+        infos.push((
+            JSON_KEY_STDOUT_SHA.into(),
+            Value::String(prior.stdout_sha.clone()),
+        ));
+        infos.push((
+            JSON_KEY_CHECK_KEY.into(),
+            Value::String(prior.check_key.clone()),
+        ));
+        infos.push((JSON_KEY_REUSED.into(), Value::Bool(true)));
+
+        (prior.result, Some((trees, infos)))
     }
 
     async fn check_solution(
         &self,
+        exit_status: ChildExitStatus,
         solution_path: PathBuf,
     ) -> Result<(JobResult, Option<SolutionInfos>), JobError> {
         self.progress.store(JobProgress::Checking);
-        let instance_path = self.instance_path.clone();
 
-        // pace26checker is implemented in a blocking fashion and may also be CPU-bound; so let's move it into an own thread
-        let (solution_infos, result) = tokio::task::spawn_blocking(move || {
-            let mut checker = CheckAndExtract::new();
-            let result = checker.process(&instance_path, &solution_path);
-            trace!("[{:?}] CheckAndExtract returned: {result:?}", instance_path);
+        let _memory_permit = match &self.check_memory {
+            Some((budget, weight)) => budget.acquire_many(*weight).await.ok(),
+            None => None,
+        };
 
-            let infos = checker.into_solution_infos();
+        // the solution is already on disk, so retrying IO errors (e.g. of a flaky network
+        // filesystem) is cheap and does not require to re-run the solver
+        let mut attempt = 0;
+        let (solution_infos, result) = loop {
+            let (solution_infos, result) = match self.run_checker(solution_path.clone()).await? {
+                Ok(x) => x,
+                Err(payload) => {
+                    let message = panic_message(payload.as_ref());
+                    error!("{:?} Checker panicked: {message}", self.instance_path);
+                    let infos = vec![(JSON_KEY_CHECKER_PANIC.into(), Value::String(message))];
+                    return Ok((JobResult::CheckerPanic, Some((Vec::new(), infos))));
+                }
+            };
 
-            (infos, result)
-        })
-        .await?;
+            if let Err(CheckerError::Io(e)) = &result
+                && attempt < self.checker_io_retries
+            {
+                attempt += 1;
+                warn!(
+                    "{:?} Checker IO error: {e}; retry ({attempt}/{})",
+                    self.instance_path, self.checker_io_retries
+                );
+                tokio::time::sleep(CHECKER_IO_RETRY_DELAY * attempt as u32).await;
+                continue;
+            }
+
+            break (solution_infos, result);
+        };
+
+        if let Err(e) = &result {
+            error!("{:?} {:?}", self.instance_path, e);
+            if let CheckerError::Io(e) = e
+                && is_disk_full(e)
+            {
+                self.disk_full.store(true, Ordering::Release);
+            }
+        }
 
         // update solution and map possible error source to job results
         Ok((
-            match result {
-                Ok(size) => JobResult::Valid { size },
-                Err(e) => {
-                    error!("{:?} {:?}", self.instance_path, e);
-                    map_checker_error_to_job_result(e)
-                }
-            },
+            self.classifier.classify(exit_status, Some(&result)),
             Some(solution_infos),
         ))
     }
 
+    /// Takes the score from the solver's `#s s_score` line instead of running the checker;
+    /// all other stride lines are reported as solution infos
+    async fn trust_solution(
+        &self,
+        solution_path: &Path,
+    ) -> Result<(JobResult, Option<SolutionInfos>), JobError> {
+        let mut content = tokio::fs::read_to_string(solution_path).await?;
+        if self.stride_lines_from_stderr {
+            content.push('\n');
+            content.push_str(&tokio::fs::read_to_string(self.stderr_path()).await?);
+        }
+
+        let mut score = None;
+        let mut infos = Vec::new();
+        for (key, value) in content.lines().filter_map(parse_stride_line) {
+            if key == SOLVER_KEY_SCORE {
+                score = value.as_u64().and_then(|score| usize::try_from(score).ok());
+            } else {
+                infos.push((key.to_string(), value));
+            }
+        }
+
+        let Some(size) = score else {
+            error!(
+                "{:?} Solver did not report a valid `#s {SOLVER_KEY_SCORE}` line",
+                self.instance_path
+            );
+            return Ok((JobResult::SyntaxError, Some((Vec::new(), infos))));
+        };
+
+        Ok((JobResult::Unchecked { size }, Some((Vec::new(), infos))))
+    }
+
+    /// Runs the checker in a blocking thread; the outer error indicates a panic of the checker
+    async fn run_checker(&self, solution_path: PathBuf) -> Result<CheckerOutcome, JoinError> {
+        let instance_path = self.instance_path.clone();
+        let strict_solution = self.strict_solution;
+        let verbose_check = self.verbose_check;
+        let stderr_path = self.stride_lines_from_stderr.then(|| self.stderr_path());
+
+        // pace26checker is implemented in a blocking fashion and may also be CPU-bound; so let's move it into an own thread
+        tokio::task::spawn_blocking(move || {
+            let mut checker = CheckAndExtract::new();
+            checker.set_strict_solution(strict_solution);
+            checker.set_match_stats(verbose_check);
+            checker.set_stride_lines_path(stderr_path);
+
+            // a panic indicates a bug in the checker; we catch it to report the instance as a reproducer
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                checker.process(&instance_path, &solution_path)
+            }));
+            trace!("[{:?}] CheckAndExtract returned: {result:?}", instance_path);
+
+            result.map(|result| (checker.into_solution_infos(), result))
+        })
+        .await
+    }
+
+    fn stderr_path(&self) -> PathBuf {
+        self.work_dir.join(solver_executor::PATH_STDERR)
+    }
+
     fn env_vars(&self) -> Vec<(String, String)> {
-        if !self.set_stride_envs {
-            return Vec::new();
+        let mut envs = Vec::with_capacity(5 + self.extra_envs.len());
+
+        if self.deterministic_env {
+            envs.extend(
+                DETERMINISTIC_ENVS
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string())),
+            );
+        }
+
+        if self.set_stride_envs {
+            let prefix = &self.env_prefix;
+            envs.extend([
+                (
+                    format!("{prefix}{ENV_SUFFIX_INSTANCE_PATH}"),
+                    self.solver_instance_path().to_string_lossy().to_string(),
+                ),
+                (
+                    format!("{prefix}{ENV_SUFFIX_TIMEOUT}"),
+                    format!("{}", self.soft_timeout.as_secs_f64()),
+                ),
+                (
+                    format!("{prefix}{ENV_SUFFIX_GRACE}"),
+                    format!("{}", self.grace_period.as_secs_f64()),
+                ),
+            ]);
+        }
+
+        envs.extend(
+            self.extra_envs
+                .iter()
+                .map(|(name, value)| (name.clone(), self.expand_placeholders(value))),
+        );
+
+        envs
+    }
+
+    /// Returns the instance path as passed to the solver; with `capture_cores`, the solver runs
+    /// in the work dir, so a relative path has to be made absolute
+    fn solver_instance_path(&self) -> Cow<'_, Path> {
+        if self.capture_cores
+            && let Ok(path) = std::path::absolute(&self.instance_path)
+        {
+            Cow::Owned(path)
+        } else {
+            Cow::Borrowed(&self.instance_path)
+        }
+    }
+
+    /// Replaces the placeholders `{instance}`, `{timeout}`, and `{grace}` in `value`
+    fn expand_placeholders(&self, value: &str) -> String {
+        value
+            .replace("{instance}", &self.solver_instance_path().to_string_lossy())
+            .replace("{timeout}", &self.soft_timeout.as_secs_f64().to_string())
+            .replace("{grace}", &self.grace_period.as_secs_f64().to_string())
+    }
+}
+
+/// Returns `true` if the solver was killed by `SIGXCPU` (soft limit) or `SIGKILL` (hard limit)
+/// after it consumed the CPU time of the soft limit; thus, e.g., the OOM killer's `SIGKILL` is
+/// not mistaken for a timeout. Without a measurement (`usage`), the signal has to suffice.
+/// The profiler forwards signals as exit code `128 + signal`.
+fn terminated_by_cpu_limit(
+    status: ExitStatus,
+    usage: Option<ChildUsage>,
+    cpu_timeout: Duration,
+) -> bool {
+    let signal = status
+        .signal()
+        .or_else(|| status.code().map(|code| code - 128));
+
+    // the executor rounds the soft limit to whole seconds; the kernel enforces it at tick
+    // granularity, so we allow for some slack
+    let soft_limit = Duration::from_secs(cpu_timeout.as_secs().max(1));
+    matches!(signal, Some(libc::SIGXCPU | libc::SIGKILL))
+        && usage.is_none_or(|usage| usage.cpu_time >= soft_limit.mul_f64(0.9))
+}
+
+/// Returns `true` if the solver died in a way typical for exceeding `RLIMIT_AS`: the kernel does
+/// not kill the process, but failing allocations usually end in `abort` (Rust, C++'s
+/// `std::bad_alloc`) or a segfault (unchecked `malloc`, stack growth). To not mistake ordinary
+/// crashes for it, the solver's peak memory usage has to be close to `memory_limit` (in bytes);
+/// without a measurement (`usage`), the signal has to suffice.
+fn terminated_by_memory_limit(
+    status: ExitStatus,
+    usage: Option<ChildUsage>,
+    memory_limit: u64,
+) -> bool {
+    let signal = status
+        .signal()
+        .or_else(|| status.code().map(|code| code - 128));
+
+    // the limit bounds the address space, which exceeds the resident memory by reserved but
+    // untouched pages, the mapped executable, and the allocation that failed
+    matches!(signal, Some(libc::SIGABRT | libc::SIGSEGV | libc::SIGBUS))
+        && usage.is_none_or(|usage| usage.max_rss >= memory_limit / 2)
+}
+
+/// Returns the last (at most) `max_bytes` bytes of the file, starting at a UTF-8 boundary and
+/// prefixed by `…` if the file is longer; returns `None` if the file is empty or unreadable
+fn read_tail(path: &Path, max_bytes: usize) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(max_bytes as u64);
+    file.seek(SeekFrom::Start(start)).ok()?;
+
+    let mut bytes = Vec::with_capacity(max_bytes.min(len as usize));
+    file.read_to_end(&mut bytes).ok()?;
+    if bytes.is_empty() {
+        return None;
+    }
+
+    if start == 0 {
+        return Some(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    // skip the continuation bytes of a character cut in half
+    let first = bytes
+        .iter()
+        .position(|&b| b & 0b1100_0000 != 0b1000_0000)
+        .unwrap_or(bytes.len());
+    Some(format!("…{}", String::from_utf8_lossy(&bytes[first..])))
+}
+
+/// Returns the path of a core dump (named `core` or `core.*`) in `dir`, if any
+fn find_core_dump(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name == "core" || name.starts_with("core."))
+        })
+}
+
+/// Returns the hex-encoded SHA-256 of the solver's raw stdout; the header lines written by
+/// the runner (`# cmd:` and `# instance:`) are skipped, as they depend on the paths used, and so
+/// are the measurements appended by the profiler, as they differ between runs
+fn stdout_sha256(stdout: &[u8]) -> String {
+    let mut content = stdout;
+    for prefix in [HEADER_CMD, HEADER_INSTANCE] {
+        if content.starts_with(prefix.as_bytes()) {
+            let end = content
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(content.len(), |pos| pos + 1);
+            content = &content[end..];
+        }
+    }
+
+    // the solver's output need not end with a newline; so the trailer may start mid-line
+    let trailer = solver_executor::PROFILER_TRAILER.as_bytes();
+    if let Some(start) = content.windows(trailer.len()).rposition(|w| w == trailer) {
+        content = &content[..start];
+    }
+
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Identifies the running build of stride by the SHA-256 of its executable; unlike the package
+/// version, it changes with every modification of the checker. Falls back to the package version
+/// if the executable cannot be read.
+pub fn build_id() -> &'static str {
+    static BUILD_ID: OnceLock<String> = OnceLock::new();
+    BUILD_ID.get_or_init(|| match std::env::current_exe().and_then(std::fs::read) {
+        Ok(executable) => Sha256::digest(executable)
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect(),
+        Err(e) => {
+            warn!("Cannot read own executable to identify the build: {e}");
+            env!("CARGO_PKG_VERSION").into()
         }
+    })
+}
+
+/// Identifies everything besides the solver's output that determines the checker's verdict:
+/// the build of stride (see [`build_id`]; it includes the checker), the strictness of the
+/// solution parser, the content of the instance, and the solution transform (if any). Verdicts
+/// are only reused if their check keys match.
+pub fn check_key(instance: &[u8], strict_solution: bool, transform: Option<&str>) -> String {
+    let short_sha = |data: &[u8]| -> String {
+        Sha256::digest(data)
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    };
 
-        vec![
-            (
-                "STRIDE_INSTANCE_PATH".to_string(),
-                self.instance_path.to_string_lossy().to_string(),
-            ),
-            (
-                arguments::ENV_SOFT_TIMEOUT.to_string(),
-                format!("{}", self.soft_timeout.as_secs_f64()),
-            ),
-            (
-                arguments::ENV_GRACE_PERIOD.to_string(),
-                format!("{}", self.grace_period.as_secs_f64()),
-            ),
-        ]
+    let mut key = format!(
+        "{}/{}/{}",
+        build_id(),
+        if strict_solution { "strict" } else { "lenient" },
+        short_sha(instance)
+    );
+    if let Some(transform) = transform {
+        key.push('/');
+        key.push_str(&short_sha(transform.as_bytes()));
     }
+    key
 }
 
-fn map_checker_error_to_job_result(e: CheckerError) -> JobResult {
-    match e {
-        CheckerError::Io(..) => JobResult::SystemError,
-        CheckerError::CreateInstanceDirError(..) => JobResult::SystemError,
-        CheckerError::InstanceInputError(..) => JobResult::InvalidInstance,
-        CheckerError::SolutionInputError(..) => JobResult::SyntaxError,
-        CheckerError::ForestConstructionError(..) => JobResult::InvalidInstance,
-        CheckerError::SolutionTreeMatchingError { .. } => JobResult::Infeasible,
-        CheckerError::EmptySolution => JobResult::EmptySolution,
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        String::from("unknown panic payload")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_full_errors() {
+        let enospc = || std::io::Error::from_raw_os_error(libc::ENOSPC);
+        assert!(JobError::Io(enospc()).is_disk_full());
+        assert!(JobError::Executor(ExecutorError::Io(enospc())).is_disk_full());
+        assert!(JobError::Checker(CheckerError::Io(enospc())).is_disk_full());
+
+        let eacces = std::io::Error::from_raw_os_error(libc::EACCES);
+        assert!(!JobError::Io(eacces).is_disk_full());
+        assert!(!JobError::Checker(CheckerError::EmptySolution).is_disk_full());
+    }
+
+    #[test]
+    fn stderr_tail() {
+        let tempdir = tempdir::TempDir::new("stderr_tail").unwrap();
+        let path = tempdir.path().join("stderr");
+        assert_eq!(read_tail(&path, 8), None);
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(read_tail(&path, 8), None);
+
+        std::fs::write(&path, "short").unwrap();
+        assert_eq!(read_tail(&path, 8).unwrap(), "short");
+
+        // the cut falls into the two-byte `ä`
+        std::fs::write(&path, "abcä12345").unwrap();
+        assert_eq!(read_tail(&path, 6).unwrap(), "…12345");
+        assert_eq!(read_tail(&path, 7).unwrap(), "…ä12345");
+    }
+
+    #[test]
+    fn check_key_depends_on_instance_and_strictness() {
+        let instance = b"#p 2 2\n(1,2);\n(1,2);\n";
+        let key = check_key(instance, false, None);
+        assert!(key.starts_with(build_id()), "{key}");
+        assert_ne!(build_id(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(check_key(instance, false, None), key);
+        assert_ne!(check_key(b"#p 2 2\n(1,2);\n(2,1);\n", false, None), key);
+        assert_ne!(check_key(instance, true, None), key);
+        assert_ne!(check_key(instance, false, Some("sort")), key);
+        assert_ne!(
+            check_key(instance, false, Some("sort")),
+            check_key(instance, false, Some("sort -r"))
+        );
+    }
+
+    #[test]
+    fn stdout_sha_ignores_runner_header() {
+        let solution = "#s key 1\n(1,2);\n";
+        let with_header =
+            format!("# cmd:  ./solver --seed 1\n# instance: /some/path.nw\n{solution}");
+        let other_header = format!("# cmd:  /other/solver --seed 1\n# instance: a.nw\n{solution}");
+
+        let sha = stdout_sha256(solution.as_bytes());
+        assert_eq!(sha.len(), 64);
+        assert_eq!(stdout_sha256(with_header.as_bytes()), sha);
+        assert_eq!(stdout_sha256(other_header.as_bytes()), sha);
+
+        // a comment written by the solver changes the digest
+        assert_ne!(stdout_sha256(format!("# c\n{solution}").as_bytes()), sha);
+        assert_eq!(
+            stdout_sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn stdout_sha_ignores_profiler_trailer() {
+        let solution = "#s key 1\n(1,2);\n";
+        let profiled = |wtime: f64, utime: f64| {
+            format!(
+                "{solution}{}{wtime}\n#s s_utime {utime}\n#s s_maxrss 1234\n",
+                solver_executor::PROFILER_TRAILER
+            )
+        };
+
+        let sha = stdout_sha256(solution.as_bytes());
+        assert_eq!(stdout_sha256(profiled(0.5, 0.25).as_bytes()), sha);
+        assert_eq!(stdout_sha256(profiled(1.5, 1.0).as_bytes()), sha);
+
+        // without a trailing newline, the profiler continues the solver's last line
+        let unterminated = format!("(1,2);{}0.5\n", solver_executor::PROFILER_TRAILER);
+        assert_eq!(
+            stdout_sha256(unterminated.as_bytes()),
+            stdout_sha256(b"(1,2);")
+        );
+    }
+
+    #[test]
+    fn core_dumps() {
+        let tempdir = tempdir::TempDir::new("core_dump").unwrap();
+        let dir = tempdir.path();
+
+        std::fs::write(dir.join("stdout"), "").unwrap();
+        std::fs::write(dir.join("score.txt"), "").unwrap();
+        assert_eq!(find_core_dump(dir), None);
+
+        std::fs::write(dir.join("core.1234"), "").unwrap();
+        assert_eq!(find_core_dump(dir), Some(dir.join("core.1234")));
+        std::fs::remove_file(dir.join("core.1234")).unwrap();
+
+        std::fs::write(dir.join("core"), "").unwrap();
+        assert_eq!(find_core_dump(dir), Some(dir.join("core")));
+
+        assert_eq!(find_core_dump(&dir.join("missing")), None);
     }
 }
@@ -1,3 +1,7 @@
 pub mod check_and_extract;
+pub mod cpu_affinity;
+pub mod explain;
 pub mod job_processor;
+pub mod numa;
+pub mod result_classifier;
 pub mod solver_executor;
@@ -0,0 +1,227 @@
+//! NUMA nodes of the machine and the binding of solvers to them (see `stride run --numa-bind`)
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const SYSFS_NODE_DIR: &str = "/sys/devices/system/node";
+
+/// Highest number of NUMA nodes supported by [`bind_to_node`]
+const MAX_NODES: usize = 1024;
+
+/// Highest number of CPUs supported by [`bind_to_node`] (i.e. `CPU_SETSIZE`); `CPU_SET` panics
+/// for larger CPU ids, so they are dropped while parsing the CPU lists
+const MAX_CPUS: usize = 1024;
+#[cfg(target_os = "linux")]
+const NODEMASK_WORDS: usize = MAX_NODES / libc::c_ulong::BITS as usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumaNode {
+    pub id: u32,
+    pub cpus: Vec<usize>,
+}
+
+/// Reads the NUMA nodes with at least one CPU from sysfs, ordered by id
+pub fn detect_nodes() -> std::io::Result<Vec<NumaNode>> {
+    detect_nodes_in(Path::new(SYSFS_NODE_DIR))
+}
+
+fn detect_nodes_in(dir: &Path) -> std::io::Result<Vec<NumaNode>> {
+    let mut nodes = Vec::new();
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+        let Some(id) = path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix("node")?.parse().ok())
+        else {
+            continue;
+        };
+
+        let cpu_list = std::fs::read_to_string(path.join("cpulist"))?;
+        let cpus = parse_cpu_list(&cpu_list).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid cpulist of NUMA node {id}: {cpu_list:?}"),
+            )
+        })?;
+
+        if !cpus.is_empty() && (id as usize) < MAX_NODES {
+            nodes.push(NumaNode { id, cpus });
+        }
+    }
+
+    nodes.sort_unstable_by_key(|node| node.id);
+    Ok(nodes)
+}
+
+/// Parses a kernel CPU list, e.g. `0-3,8,10-11`; CPUs beyond [`MAX_CPUS`] are skipped
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        let (from, to) = range.split_once('-').unwrap_or((range, range));
+        let (from, to) = (from.parse::<usize>().ok()?, to.parse::<usize>().ok()?);
+        cpus.extend(from..=to.min(MAX_CPUS - 1));
+    }
+    Some(cpus)
+}
+
+/// Assigns each job the NUMA node currently running the fewest jobs
+pub struct NumaScheduler {
+    nodes: Vec<NumaNode>,
+    running: Mutex<Vec<usize>>,
+}
+
+impl NumaScheduler {
+    pub fn new(nodes: Vec<NumaNode>) -> Self {
+        let running = Mutex::new(vec![0; nodes.len()]);
+        Self { nodes, running }
+    }
+
+    /// Returns the least loaded node; it counts as running a job until the lease is dropped
+    pub fn acquire(self: &Arc<Self>) -> NumaLease {
+        let mut running = self.running.lock().unwrap();
+        let index = (0..running.len())
+            .min_by_key(|&i| running[i])
+            .expect("at least one node");
+        running[index] += 1;
+
+        NumaLease {
+            scheduler: self.clone(),
+            index,
+        }
+    }
+}
+
+pub struct NumaLease {
+    scheduler: Arc<NumaScheduler>,
+    index: usize,
+}
+
+impl NumaLease {
+    pub fn node(&self) -> &NumaNode {
+        &self.scheduler.nodes[self.index]
+    }
+}
+
+impl Drop for NumaLease {
+    fn drop(&mut self) {
+        self.scheduler.running.lock().unwrap()[self.index] -= 1;
+    }
+}
+
+/// Returns a closure (to be executed between `fork` and `exec`) restricting the calling process
+/// to the CPUs of `node` and its memory allocations to `node`. All buffers are prepared up front,
+/// so that the closure does not allocate.
+#[cfg(target_os = "linux")]
+pub fn bind_to_node(
+    node: &NumaNode,
+) -> impl FnMut() -> std::io::Result<()> + Send + Sync + 'static {
+    // SAFETY: cpu_set_t is a plain bit set; all zeros is the empty set
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in &node.cpus {
+        unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+    }
+
+    let mut nodemask = [0 as libc::c_ulong; NODEMASK_WORDS];
+    let bits = libc::c_ulong::BITS as usize;
+    nodemask[node.id as usize / bits] |= 1 << (node.id as usize % bits);
+
+    move || {
+        if unsafe { libc::sched_setaffinity(0, std::mem::size_of_val(&cpu_set), &cpu_set) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_set_mempolicy,
+                libc::MPOL_BIND,
+                nodemask.as_ptr(),
+                MAX_NODES as libc::c_ulong,
+            )
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// NUMA binding is only supported on Linux
+#[cfg(not(target_os = "linux"))]
+pub fn bind_to_node(
+    _node: &NumaNode,
+) -> impl FnMut() -> std::io::Result<()> + Send + Sync + 'static {
+    || Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n"),
+            Some(vec![0, 1, 2, 3, 8, 10, 11])
+        );
+        assert_eq!(parse_cpu_list("\n"), Some(vec![]));
+        assert_eq!(parse_cpu_list("0-x"), None);
+
+        // CPUs beyond CPU_SETSIZE cannot be bound to
+        assert_eq!(parse_cpu_list("1022-1025"), Some(vec![1022, 1023]));
+        assert_eq!(parse_cpu_list("2000,3"), Some(vec![3]));
+    }
+
+    #[test]
+    fn detect_from_sysfs() {
+        let tempdir = tempdir::TempDir::new("numa").unwrap();
+        for (name, cpus) in [
+            ("node1", "4-5"),
+            ("node0", "0-1"),
+            ("node2", ""),
+            ("possible", ""),
+        ] {
+            let dir = tempdir.path().join(name);
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("cpulist"), cpus).unwrap();
+        }
+
+        let nodes = detect_nodes_in(tempdir.path()).unwrap();
+        assert_eq!(
+            nodes,
+            [
+                NumaNode {
+                    id: 0,
+                    cpus: vec![0, 1]
+                },
+                NumaNode {
+                    id: 1,
+                    cpus: vec![4, 5]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn least_loaded_node() {
+        let nodes = vec![
+            NumaNode {
+                id: 0,
+                cpus: vec![0],
+            },
+            NumaNode {
+                id: 1,
+                cpus: vec![1],
+            },
+        ];
+        let scheduler = Arc::new(NumaScheduler::new(nodes));
+
+        let first = scheduler.acquire();
+        let second = scheduler.acquire();
+        assert_ne!(first.node().id, second.node().id);
+
+        drop(first);
+        let third = scheduler.acquire();
+        assert_ne!(third.node().id, second.node().id);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::job::{
+    check_and_extract::CheckerError, job_processor::JobResult, solver_executor::ChildExitStatus,
+};
+
+/// Maps the outcome of a solver run to a [`JobResult`]. Implement this trait to customize how
+/// exit codes and checker outcomes are bucketed (e.g., for related problems with different
+/// success criteria) and pass it to `JobProcessorBuilder::classifier`.
+pub trait ResultClassifier: Send + Sync {
+    /// `checked` is `None` if the solver did not exit successfully and hence the checker was not
+    /// run; otherwise, it contains the solution size or the checker's error
+    fn classify(
+        &self,
+        exit_status: ChildExitStatus,
+        checked: Option<&Result<usize, CheckerError>>,
+    ) -> JobResult;
+}
+
+/// The classification used by `stride run`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultResultClassifier;
+
+impl ResultClassifier for DefaultResultClassifier {
+    fn classify(
+        &self,
+        exit_status: ChildExitStatus,
+        checked: Option<&Result<usize, CheckerError>>,
+    ) -> JobResult {
+        match checked {
+            Some(Ok(size)) => JobResult::Valid { size: *size },
+            Some(Err(e)) => map_checker_error_to_job_result(e),
+            None => match exit_status {
+                ChildExitStatus::Timeout => JobResult::Timeout,
+                ChildExitStatus::Cancelled => JobResult::Cancelled,
+                ChildExitStatus::OutputOverflow => JobResult::OutputOverflow,
+                ChildExitStatus::BeforeTimeout(_) | ChildExitStatus::WithinGrace(_) => {
+                    JobResult::SolverError
+                }
+            },
+        }
+    }
+}
+
+fn map_checker_error_to_job_result(e: &CheckerError) -> JobResult {
+    match e {
+        CheckerError::Io(..) => JobResult::SystemError,
+        CheckerError::CreateInstanceDirError(..) => JobResult::SystemError,
+        CheckerError::InstanceInputError(..) => JobResult::InvalidInstance,
+        CheckerError::SolutionInputError(..) => JobResult::SyntaxError,
+        CheckerError::ForestConstructionError(..) => JobResult::InvalidInstance,
+        CheckerError::SolutionTreeMatchingError { .. } => JobResult::Infeasible,
+        CheckerError::EmptySolution => JobResult::EmptySolution,
+        CheckerError::EmptyInstance => JobResult::InvalidInstance,
+        CheckerError::StrictSolutionWarning(..) => JobResult::SyntaxError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    #[test]
+    fn default_classification() {
+        let success = ChildExitStatus::BeforeTimeout(ExitStatus::from_raw(0));
+        let classifier = DefaultResultClassifier;
+
+        assert_eq!(
+            classifier.classify(success, Some(&Ok(3))),
+            JobResult::Valid { size: 3 }
+        );
+        assert_eq!(
+            classifier.classify(success, Some(&Err(CheckerError::EmptySolution))),
+            JobResult::EmptySolution
+        );
+        assert_eq!(
+            classifier.classify(ChildExitStatus::Timeout, None),
+            JobResult::Timeout
+        );
+        assert_eq!(
+            classifier.classify(
+                ChildExitStatus::WithinGrace(ExitStatus::from_raw(256)),
+                None
+            ),
+            JobResult::SolverError
+        );
+    }
+}
@@ -1,18 +1,47 @@
-use std::{fs::File, io::Write, path::PathBuf, process::ExitStatus, time::Duration};
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use derive_builder::Builder;
 use thiserror::Error;
 use tokio::{
-    process::{Child, Command},
+    io::{AsyncReadExt, AsyncWriteExt},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::Notify,
+    task::JoinHandle,
     time::{Instant, timeout},
 };
 use tracing::{debug, trace};
 
+use crate::job::check_and_extract::{is_gzipped, open_instance};
+use crate::job::cpu_affinity::pin_to_cpu;
+use crate::job::numa::{NumaNode, bind_to_node};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ChildExitStatus {
     BeforeTimeout(ExitStatus),
     WithinGrace(ExitStatus),
     Timeout,
+    Cancelled,
+    /// the solver was terminated as its output exceeded `max_output_rate`
+    OutputOverflow,
+}
+
+/// Resources consumed by the solver and its (reaped) descendants, measured as it exited
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChildUsage {
+    /// user plus system time
+    pub cpu_time: Duration,
+    /// peak resident set size in bytes
+    pub max_rss: u64,
 }
 
 impl ChildExitStatus {
@@ -20,7 +49,9 @@ impl ChildExitStatus {
         match self {
             ChildExitStatus::BeforeTimeout(exit_status) => exit_status.success(),
             ChildExitStatus::WithinGrace(exit_status) => exit_status.success(),
-            ChildExitStatus::Timeout => false,
+            ChildExitStatus::Timeout
+            | ChildExitStatus::Cancelled
+            | ChildExitStatus::OutputOverflow => false,
         }
     }
 }
@@ -42,38 +73,165 @@ pub struct SolverExecutor {
     args: Vec<String>,
     env: Vec<(String, String)>,
 
+    #[builder(default)]
+    /// environment variables inherited from the runner that are not passed to the solver
+    env_remove: Vec<String>,
+
+    #[builder(default)]
+    /// if set, the solver does not inherit the runner's environment, except for the variables
+    /// whose names match one of `keep_env`
+    clean_env: bool,
+
+    #[builder(default)]
+    /// patterns of inherited variables that are retained with `clean_env`
+    keep_env: Vec<glob::Pattern>,
+
     timeout: Duration,
     grace: Duration,
 
+    #[builder(default = "libc::SIGTERM")]
+    /// signal sent at the timeout (and on cancellation); `SIGKILL` follows after `grace`
+    term_signal: libc::c_int,
+
+    #[builder(default)]
+    /// if set, limit the CPU time of the solver using `RLIMIT_CPU`
+    cpu_timeout: Option<Duration>,
+
+    #[builder(default)]
+    /// if set, limit the number of open file descriptors of the solver using `RLIMIT_NOFILE`
+    max_fds: Option<u64>,
+
+    #[builder(default)]
+    /// if set, limit the number of processes of the solver's user using `RLIMIT_NPROC`
+    max_procs: Option<u64>,
+
+    #[builder(default)]
+    /// if set, limit the address space of the solver (in bytes) using `RLIMIT_AS`
+    memory_limit: Option<u64>,
+
+    #[builder(default)]
+    /// if set, the solver runs in `working_dir` with `RLIMIT_CORE` lifted, so that core dumps
+    /// (with a relative `core_pattern`) end up in the working directory
+    capture_cores: bool,
+
+    #[builder(default)]
+    /// if set, the solver is restricted to the CPUs and the memory of this NUMA node (Linux only)
+    numa_node: Option<NumaNode>,
+
+    #[builder(default)]
+    /// if set, the solver is pinned to this CPU (Linux only)
+    cpu: Option<usize>,
+
+    #[builder(default)]
+    /// if notified, the solver is terminated early (SIGTERM, then SIGKILL after the grace period)
+    cancel: Option<Arc<Notify>>,
+
+    #[builder(default)]
+    /// if set, the instance and the solution are piped through the runner to detect whether the
+    /// solver writes to stdout before the instance was completely written to its stdin
+    detect_early_output: bool,
+
+    #[builder(default)]
+    /// if set, the solver's stdout is piped through the runner and the solver is terminated early
+    /// if it writes more than this many bytes per second on average over [`OUTPUT_RATE_WINDOW`]
+    max_output_rate: Option<u64>,
+
+    #[builder(default, setter(skip))]
+    early_output: bool,
+
+    #[builder(default, setter(skip))]
+    usage: Option<ChildUsage>,
+
     #[builder(default)]
     runtime: Option<Duration>,
 }
 
 pub const PATH_STDOUT: &str = "stdout";
 pub const PATH_STDERR: &str = "stderr";
+/// File in the work dir holding the decompressed instance if the instance is gzip-compressed
+pub const PATH_DECOMPRESSED_INSTANCE: &str = "instance";
+
+/// Prefixes of the header lines written into the stdout file before the solver starts
+pub const HEADER_CMD: &str = "# cmd: ";
+pub const HEADER_INSTANCE: &str = "# instance: ";
+/// Prefix of the first line the profiler (see `stride p`) appends to the stdout file after the
+/// solver terminated; it and all following lines are measurements of the profiler
+pub const PROFILER_TRAILER: &str = "#s s_wtime ";
+
+/// How long we wait for the solver's stdout to be drained after it exited; the pipe may be held
+/// open by processes the solver spawned
+const DRAIN_STDOUT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Window over which the output rate is averaged (see `max_output_rate`); short bursts, e.g.
+/// writing the solution at once, should not trigger the limit
+const OUTPUT_RATE_WINDOW: Duration = Duration::from_secs(2);
 
 impl SolverExecutor {
     pub async fn run(&mut self) -> Result<ChildExitStatus, ExecutorError> {
+        // not accounted to the solver's runtime
+        let input_path = self.prepare_input()?;
+
         // spawn and execute solver as child
         let start_time = Instant::now();
-        let child = self.spawn_child()?;
-        let wait_result = self.timeout_wait_for_child_to_complete(child).await?;
+        let (mut child, stdout) = self.spawn_child(&input_path)?;
+
+        let overflow = Arc::new(Notify::new());
+        let pipes = stdout
+            .map(|stdout| self.start_piping(&mut child, &input_path, stdout, overflow.clone()));
+
+        let (wait_result, usage) = self
+            .timeout_wait_for_child_to_complete(child, &overflow)
+            .await?;
+        self.usage = usage;
         self.runtime = Some(start_time.elapsed());
 
+        if let Some((feeder, collector)) = pipes {
+            feeder.abort();
+            self.early_output = self.finish_piping(collector).await?;
+        }
+
         Ok(wait_result)
     }
 
-    fn spawn_child(&mut self) -> Result<Child, ExecutorError> {
-        let stdin = File::open(&self.instance_path)?;
+    /// Returns `true` if `detect_early_output` is set and the solver wrote to stdout before
+    /// the instance was completely written to its stdin
+    pub fn early_output(&self) -> bool {
+        self.early_output
+    }
+
+    /// Returns the resources consumed by the solver if it exited by itself (before the timeout
+    /// or within the grace period) and they could be measured (Linux only)
+    pub fn usage(&self) -> Option<ChildUsage> {
+        self.usage
+    }
+
+    /// Returns the file fed into the solver's stdin; a gzip-compressed instance is decompressed
+    /// into the working dir, so that it is cleaned up along with the task
+    fn prepare_input(&self) -> Result<PathBuf, ExecutorError> {
+        if !is_gzipped(&self.instance_path) {
+            return Ok(self.instance_path.clone());
+        }
+
+        let path = self.working_dir.join(PATH_DECOMPRESSED_INSTANCE);
+        let mut reader = open_instance(&self.instance_path)?;
+        std::io::copy(&mut reader, &mut File::create(&path)?)?;
+        trace!("Decompressed {:?} into {path:?}", self.instance_path);
+
+        Ok(path)
+    }
+
+    /// Returns the child and, if `detect_early_output` or `max_output_rate` is set, the file its
+    /// stdout is to be copied into
+    fn spawn_child(&mut self, input_path: &Path) -> Result<(Child, Option<File>), ExecutorError> {
         let mut stdout = File::create(self.working_dir.join(PATH_STDOUT))?;
         let stderr = File::create(self.working_dir.join(PATH_STDERR))?;
 
         if let Some(solver) = self.solver_path.as_os_str().to_str() {
-            let _ = writeln!(stdout, "# cmd:  {} {}", solver, self.args.join(" "));
+            let _ = writeln!(stdout, "{HEADER_CMD} {} {}", solver, self.args.join(" "));
         }
 
         if let Some(instance) = self.instance_path.as_os_str().to_str() {
-            let _ = writeln!(stdout, "# instance: {}", instance);
+            let _ = writeln!(stdout, "{HEADER_INSTANCE}{}", instance);
         }
 
         trace!(
@@ -81,50 +239,212 @@ impl SolverExecutor {
             self.solver_path, &self.args
         );
 
-        let child = Command::new(&self.solver_path)
+        let mut command = Command::new(&self.solver_path);
+        if self.clean_env {
+            command
+                .env_clear()
+                .envs(std::env::vars_os().filter(|(name, _)| {
+                    name.to_str()
+                        .is_some_and(|name| self.keep_env.iter().any(|p| p.matches(name)))
+                }));
+        }
+        for name in &self.env_remove {
+            command.env_remove(name);
+        }
+        command
             .args(&self.args)
             .envs(self.env.iter().cloned())
-            .stdin(stdin)
-            .stdout(stdout)
             .stderr(stderr)
-            .kill_on_drop(true)
-            .spawn()?;
+            .kill_on_drop(true);
 
-        Ok(child)
+        let piped_stdout = if self.detect_early_output || self.max_output_rate.is_some() {
+            command.stdin(Stdio::piped()).stdout(Stdio::piped());
+            Some(stdout)
+        } else {
+            command.stdin(File::open(input_path)?).stdout(stdout);
+            None
+        };
+
+        if let Some(cpu_timeout) = self.cpu_timeout {
+            // the kernel sends SIGXCPU at the soft limit and SIGKILL at the hard limit;
+            // we use the grace period in between
+            let soft = cpu_timeout.as_secs().max(1);
+            let hard = soft + self.grace.as_secs().max(1);
+
+            // SAFETY: setrlimit is async-signal-safe and the closure does not allocate
+            unsafe {
+                command.pre_exec(move || set_cpu_limit(soft, hard));
+            }
+        }
+
+        if self.max_fds.is_some() || self.max_procs.is_some() || self.memory_limit.is_some() {
+            let (max_fds, max_procs, memory_limit) =
+                (self.max_fds, self.max_procs, self.memory_limit);
+
+            // SAFETY: setrlimit is async-signal-safe and the closure does not allocate
+            unsafe {
+                command.pre_exec(move || set_resource_limits(max_fds, max_procs, memory_limit));
+            }
+        }
+
+        if self.capture_cores {
+            command.current_dir(&self.working_dir);
+
+            // SAFETY: setrlimit is async-signal-safe and the closure does not allocate
+            unsafe {
+                command.pre_exec(enable_core_dumps);
+            }
+        }
+
+        if let Some(node) = &self.numa_node {
+            // SAFETY: sched_setaffinity and set_mempolicy are plain syscalls and the closure does
+            // not allocate (its buffers are prepared by `bind_to_node`)
+            unsafe {
+                command.pre_exec(bind_to_node(node));
+            }
+        }
+
+        if let Some(cpu) = self.cpu {
+            // SAFETY: sched_setaffinity is a plain syscall and the closure does not allocate
+            unsafe {
+                command.pre_exec(pin_to_cpu(cpu));
+            }
+        }
+
+        // the solver leads its own process group, so that we can signal the helper processes
+        // it may fork along with it (see `signal_group`)
+        // SAFETY: setsid is a plain syscall and the closure does not allocate
+        unsafe {
+            command.pre_exec(new_session);
+        }
+
+        // SAFETY: prctl and getppid are plain syscalls and the closure does not allocate
+        unsafe {
+            command.pre_exec(kill_with_parent());
+        }
+
+        let child = command.spawn()?;
+
+        Ok((child, piped_stdout))
     }
 
-    /// In case of no error, we return
-    ///  - Some(ExitStatus) if the child has exited
-    ///  - None if the child has been killed using SIGKILL
+    /// Spawns tasks feeding the instance into the child's stdin and copying its stdout into
+    /// `stdout_file`; the latter reports whether the first output preceded the end of the input
+    /// and notifies `overflow` if the output exceeds `max_output_rate`
+    fn start_piping(
+        &self,
+        child: &mut Child,
+        input_path: &Path,
+        stdout_file: File,
+        overflow: Arc<Notify>,
+    ) -> (JoinHandle<()>, JoinHandle<std::io::Result<bool>>) {
+        let input_complete = Arc::new(AtomicBool::new(false));
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let feeder = tokio::spawn(feed_stdin(
+            input_path.to_path_buf(),
+            stdin,
+            input_complete.clone(),
+        ));
+        let collector = tokio::spawn(collect_stdout(
+            stdout,
+            tokio::fs::File::from_std(stdout_file),
+            input_complete,
+            self.max_output_rate
+                .map(|max_rate| OutputRateMonitor::new(max_rate, Instant::now())),
+            overflow,
+        ));
+
+        (feeder, collector)
+    }
+
+    async fn finish_piping(
+        &self,
+        collector: JoinHandle<std::io::Result<bool>>,
+    ) -> Result<bool, ExecutorError> {
+        match timeout(DRAIN_STDOUT_TIMEOUT, collector).await {
+            Ok(Ok(result)) => Ok(result?),
+            Ok(Err(e)) => {
+                debug!("[{:?}] Stdout collector failed: {e}", self.instance_path);
+                Ok(false)
+            }
+            Err(_) => {
+                // dropping the handle does not stop the task, but it ends with the pipe
+                debug!(
+                    "[{:?}] Stdout of solver still open after it exited",
+                    self.instance_path
+                );
+                Ok(false)
+            }
+        }
+    }
+
+    /// In case of no error, we return how the child terminated, i.e.
+    ///  - `BeforeTimeout` or `WithinGrace` with its exit status if it exited by itself
+    ///  - `Timeout` if it has been killed using SIGKILL after the grace period
+    ///  - `Cancelled` or `OutputOverflow` if it was stopped early (regardless of its exit status)
+    ///
+    /// along with the child's resource usage if it exited before the final SIGKILL and the usage
+    /// could be measured
     async fn timeout_wait_for_child_to_complete(
         &self,
         mut child: Child,
-    ) -> Result<ChildExitStatus, ExecutorError> {
-        // we get an error if we run into the timeout
-        if let Ok(res) = timeout(self.timeout, child.wait()).await {
-            trace!("Child terminated within time: {res:?}");
-            return Ok(ChildExitStatus::BeforeTimeout(res?));
-        }
+        overflow: &Notify,
+    ) -> Result<(ChildExitStatus, Option<ChildUsage>), ExecutorError> {
+        // the solver's pid is also the id of its process group; we only get None if the child
+        // has already exited
+        let pgid = child.id();
 
-        debug!(
-            "[{:?}] Timeout after {}s reached; send sigterm child",
-            self.instance_path,
-            self.timeout.as_secs()
-        );
+        // we get an error if we run into the timeout; if the solver is stopped for another
+        // reason, `interrupted` is reported regardless of the solver's exit status
+        let interrupted = tokio::select! {
+            res = timeout(self.timeout, wait_measured(&mut child)) => match res {
+                Ok(res) => {
+                    trace!("Child terminated within time: {res:?}");
+                    let (status, usage) = res?;
+                    // helpers that outlive the solver must not keep running (and hog resources)
+                    signal_group(pgid, libc::SIGKILL);
+                    return Ok((ChildExitStatus::BeforeTimeout(status), usage));
+                }
+                Err(_) => None,
+            },
+            _ = wait_for_cancellation(self.cancel.as_deref()) => Some(ChildExitStatus::Cancelled),
+            _ = overflow.notified() => Some(ChildExitStatus::OutputOverflow),
+        };
 
-        // send SIGTERM to the child (we use unsafe here, because I do not want to pull a crate for this one line)
-        if let Some(pid) = child.id() {
-            // we only get None if the child has already exited
-            unsafe {
-                libc::kill(pid as i32, libc::SIGTERM);
-            }
+        if interrupted == Some(ChildExitStatus::Cancelled) {
+            debug!(
+                "[{:?}] Cancellation requested; send signal {} to child",
+                self.instance_path, self.term_signal
+            );
+        } else if interrupted == Some(ChildExitStatus::OutputOverflow) {
+            debug!(
+                "[{:?}] Output exceeds the maximum rate; send signal {} to child",
+                self.instance_path, self.term_signal
+            );
+        } else {
+            debug!(
+                "[{:?}] Timeout after {}s reached; send signal {} to child",
+                self.instance_path,
+                self.timeout.as_secs(),
+                self.term_signal
+            );
         }
 
+        signal_group(pgid, self.term_signal);
+
         // issue a grace period
         if !self.grace.is_zero()
-            && let Ok(res) = timeout(self.grace, child.wait()).await
+            && let Ok(res) = timeout(self.grace, wait_measured(&mut child)).await
         {
-            return Ok(ChildExitStatus::WithinGrace(res?));
+            let (status, usage) = res?;
+            // helpers that outlive the solver had their chance to terminate, too
+            signal_group(pgid, libc::SIGKILL);
+            return Ok((
+                interrupted.unwrap_or(ChildExitStatus::WithinGrace(status)),
+                usage,
+            ));
         }
 
         debug!(
@@ -133,8 +453,472 @@ impl SolverExecutor {
             self.timeout.as_secs()
         );
 
-        child.kill().await?;
+        signal_group(pgid, libc::SIGKILL);
+        child.wait().await?;
+
+        Ok((interrupted.unwrap_or(ChildExitStatus::Timeout), None))
+    }
+}
+
+/// Waits for the child to exit and measures its resource usage before it is reaped
+async fn wait_measured(child: &mut Child) -> std::io::Result<(ExitStatus, Option<ChildUsage>)> {
+    let usage = match child.id() {
+        Some(pid) => tokio::task::spawn_blocking(move || usage_at_exit(pid))
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    Ok((child.wait().await?, usage))
+}
+
+/// Blocks until the process exited and returns its resource usage (including that of the
+/// descendants it waited for); the process is left as a zombie for tokio to reap (`WNOWAIT`)
+#[cfg(target_os = "linux")]
+fn usage_at_exit(pid: u32) -> Option<ChildUsage> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    // unlike its libc wrapper, the syscall reports the resource usage
+    loop {
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_waitid,
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info as *mut libc::siginfo_t,
+                libc::WEXITED | libc::WNOWAIT,
+                &mut usage as *mut libc::rusage,
+            )
+        };
+        if ret == 0 {
+            break;
+        }
+        if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return None;
+        }
+    }
+
+    let time = |t: libc::timeval| Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000);
+    Some(ChildUsage {
+        cpu_time: time(usage.ru_utime) + time(usage.ru_stime),
+        // reported in kilobytes
+        max_rss: usage.ru_maxrss as u64 * 1024,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn usage_at_exit(_pid: u32) -> Option<ChildUsage> {
+    None
+}
+
+/// Sends `signal` to all processes of the solver's process group (see `new_session`), i.e. the
+/// solver and all helpers it spawned (unless they left the group)
+fn signal_group(pgid: Option<u32>, signal: libc::c_int) {
+    if let Some(pgid) = pgid {
+        // we use unsafe here, because I do not want to pull a crate for this one line
+        unsafe {
+            libc::kill(-(pgid as libc::pid_t), signal);
+        }
+    }
+}
+
+async fn feed_stdin(
+    instance_path: PathBuf,
+    mut stdin: ChildStdin,
+    input_complete: Arc<AtomicBool>,
+) {
+    let result = async {
+        let mut instance = tokio::fs::File::open(&instance_path).await?;
+        tokio::io::copy(&mut instance, &mut stdin).await?;
+        stdin.flush().await
+    }
+    .await;
+
+    match result {
+        // set before closing stdin, so solvers waiting for EOF cannot output earlier
+        Ok(()) => input_complete.store(true, Ordering::Release),
+        // e.g. the solver exited (or closed stdin) without reading the whole instance
+        Err(e) => debug!("[{instance_path:?}] Failed to write instance to solver: {e}"),
+    }
+}
+
+async fn collect_stdout(
+    mut stdout: ChildStdout,
+    mut file: tokio::fs::File,
+    input_complete: Arc<AtomicBool>,
+    mut rate_monitor: Option<OutputRateMonitor>,
+    overflow: Arc<Notify>,
+) -> std::io::Result<bool> {
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut early_output = None;
+    let mut overflowed = false;
+
+    loop {
+        let bytes = stdout.read(&mut buffer).await?;
+        if bytes == 0 {
+            break;
+        }
+
+        early_output.get_or_insert_with(|| !input_complete.load(Ordering::Acquire));
+
+        // after an overflow, we keep draining the pipe (so the solver is not killed by SIGPIPE
+        // before we terminate it), but discard the output
+        if overflowed {
+            continue;
+        }
+
+        if let Some(monitor) = &mut rate_monitor
+            && monitor.record(bytes, Instant::now())
+        {
+            overflowed = true;
+            overflow.notify_one();
+            continue;
+        }
+
+        file.write_all(&buffer[..bytes]).await?;
+    }
+
+    file.flush().await?;
+    Ok(early_output.unwrap_or(false))
+}
+
+/// Tracks the number of bytes written within the current window of length [`OUTPUT_RATE_WINDOW`]
+struct OutputRateMonitor {
+    max_bytes_per_window: u64,
+    window_start: Instant,
+    bytes_in_window: u64,
+}
+
+impl OutputRateMonitor {
+    fn new(max_bytes_per_sec: u64, now: Instant) -> Self {
+        Self {
+            max_bytes_per_window: (max_bytes_per_sec as f64 * OUTPUT_RATE_WINDOW.as_secs_f64())
+                as u64,
+            window_start: now,
+            bytes_in_window: 0,
+        }
+    }
+
+    /// Accounts `bytes` written at `now`; returns `true` if the output of the current window
+    /// exceeds the limit
+    fn record(&mut self, bytes: usize, now: Instant) -> bool {
+        if now.duration_since(self.window_start) >= OUTPUT_RATE_WINDOW {
+            self.window_start = now;
+            self.bytes_in_window = 0;
+        }
+
+        self.bytes_in_window += bytes as u64;
+        self.bytes_in_window > self.max_bytes_per_window
+    }
+}
+
+async fn wait_for_cancellation(cancel: Option<&Notify>) {
+    match cancel {
+        Some(cancel) => cancel.notified().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn set_cpu_limit(soft_secs: u64, hard_secs: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft_secs as libc::rlim_t,
+        rlim_max: hard_secs as libc::rlim_t,
+    };
+
+    if unsafe { libc::setrlimit(libc::RLIMIT_CPU, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Sets the (soft and hard) limits `RLIMIT_NOFILE`, `RLIMIT_NPROC`, and `RLIMIT_AS` of the
+/// calling process; to be executed between `fork` and `exec` of the solver
+pub fn set_resource_limits(
+    max_fds: Option<u64>,
+    max_procs: Option<u64>,
+    memory_limit: Option<u64>,
+) -> std::io::Result<()> {
+    let limit = |value: u64| libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+
+    if let Some(max_fds) = max_fds
+        && unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit(max_fds)) } != 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    if let Some(max_procs) = max_procs
+        && unsafe { libc::setrlimit(libc::RLIMIT_NPROC, &limit(max_procs)) } != 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    if let Some(memory_limit) = memory_limit
+        && unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit(memory_limit)) } != 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+fn enable_core_dumps() -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: libc::RLIM_INFINITY,
+        rlim_max: libc::RLIM_INFINITY,
+    };
+
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        // an unprivileged process may not raise its hard limit; use as much as allowed
+        let mut current = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut current) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        current.rlim_cur = current.rlim_max;
+        if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &current) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the calling process into a new session and process group (with the process' id), so
+/// that the solver and its helpers can be signalled as a whole and, e.g., a Ctrl-C at the
+/// terminal only reaches the runner
+fn new_session() -> std::io::Result<()> {
+    if unsafe { libc::setsid() } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Returns a closure (to be executed between `fork` and `exec`) asking the kernel to SIGKILL the
+/// calling process once the spawning process dies. Thus, no solver outlives the runner, even if
+/// the latter is killed without a chance to clean up. Strictly, the signal is sent once the
+/// spawning *thread* exits; hence, solvers must not be spawned from `spawn_blocking` threads.
+#[cfg(target_os = "linux")]
+pub fn kill_with_parent() -> impl FnMut() -> std::io::Result<()> + Send + Sync + 'static {
+    let parent = std::process::id() as libc::pid_t;
+
+    move || {
+        if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // the parent may have died before the death signal was armed
+        if unsafe { libc::getppid() } != parent {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Other platforms lack a parent-death signal; there, we rely on `kill_on_drop`
+#[cfg(not(target_os = "linux"))]
+pub fn kill_with_parent() -> impl FnMut() -> std::io::Result<()> + Send + Sync + 'static {
+    || Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    async fn early_output_of(script: &str) -> bool {
+        let tempdir = TempDir::new("early_output").unwrap();
+        let instance_path = tempdir.path().join("large.in");
+        // exceeds the pipe buffer, so the instance cannot be written at once
+        std::fs::write(&instance_path, "x".repeat(1 << 22)).unwrap();
+
+        let mut executor = SolverExecutorBuilder::default()
+            .instance_path(instance_path)
+            .working_dir(tempdir.path().to_path_buf())
+            .solver_path(PathBuf::from("/bin/sh"))
+            .args(vec!["-c".into(), script.into()])
+            .env(Vec::new())
+            .timeout(Duration::from_secs(10))
+            .grace(Duration::from_secs(1))
+            .detect_early_output(true)
+            .build()
+            .unwrap();
+
+        let status = executor.run().await.unwrap();
+        assert!(status.is_success());
+
+        let stdout = std::fs::read_to_string(tempdir.path().join(PATH_STDOUT)).unwrap();
+        assert!(stdout.ends_with("done\n"));
+
+        executor.early_output()
+    }
+
+    #[tokio::test]
+    async fn gzipped_instance() {
+        use flate2::{Compression, write::GzEncoder};
+
+        let tempdir = TempDir::new("gzipped_instance").unwrap();
+        let instance_path = tempdir.path().join("instance.gr.gz");
+        let mut encoder = GzEncoder::new(
+            File::create(&instance_path).unwrap(),
+            Compression::default(),
+        );
+        encoder.write_all(b"#p 2 3\n").unwrap();
+        encoder.finish().unwrap();
+
+        for detect_early_output in [false, true] {
+            let mut executor = SolverExecutorBuilder::default()
+                .instance_path(instance_path.clone())
+                .working_dir(tempdir.path().to_path_buf())
+                .solver_path(PathBuf::from("/bin/sh"))
+                .args(vec!["-c".into(), "grep -q '^#p 2 3$'".into()])
+                .env(Vec::new())
+                .timeout(Duration::from_secs(10))
+                .grace(Duration::from_secs(1))
+                .detect_early_output(detect_early_output)
+                .build()
+                .unwrap();
+
+            assert!(executor.run().await.unwrap().is_success());
+            assert!(tempdir.path().join(PATH_DECOMPRESSED_INSTANCE).is_file());
+        }
+    }
+
+    #[tokio::test]
+    async fn max_fds() {
+        let tempdir = TempDir::new("max_fds").unwrap();
+        let instance_path = tempdir.path().join("empty.in");
+        std::fs::write(&instance_path, "").unwrap();
+
+        let run = |max_fds| {
+            let mut executor = SolverExecutorBuilder::default()
+                .instance_path(instance_path.clone())
+                .working_dir(tempdir.path().to_path_buf())
+                .solver_path(PathBuf::from("/bin/sh"))
+                .args(vec!["-c".into(), "test \"$(ulimit -n)\" != 5".into()])
+                .env(Vec::new())
+                .timeout(Duration::from_secs(10))
+                .grace(Duration::from_secs(1))
+                .max_fds(max_fds)
+                .build()
+                .unwrap();
+            async move { executor.run().await.unwrap() }
+        };
+
+        assert!(run(None).await.is_success());
+        assert!(!run(Some(5)).await.is_success());
+    }
+
+    #[tokio::test]
+    async fn term_signal() {
+        let tempdir = TempDir::new("term_signal").unwrap();
+        let instance_path = tempdir.path().join("empty.in");
+        std::fs::write(&instance_path, "").unwrap();
+
+        // the shell only exits gracefully on SIGINT
+        let mut executor = SolverExecutorBuilder::default()
+            .instance_path(instance_path)
+            .working_dir(tempdir.path().to_path_buf())
+            .solver_path(PathBuf::from("/bin/sh"))
+            .args(vec![
+                "-c".into(),
+                "trap 'exit 0' INT; trap '' TERM; sleep 60 & wait".into(),
+            ])
+            .env(Vec::new())
+            .timeout(Duration::from_millis(500))
+            .grace(Duration::from_secs(5))
+            .term_signal(libc::SIGINT)
+            .build()
+            .unwrap();
+
+        let status = executor.run().await.unwrap();
+        assert!(matches!(status, ChildExitStatus::WithinGrace(status) if status.success()));
+    }
+
+    #[tokio::test]
+    async fn clean_env() {
+        let tempdir = TempDir::new("clean_env").unwrap();
+        let instance_path = tempdir.path().join("empty.in");
+        std::fs::write(&instance_path, "").unwrap();
+
+        // cargo sets CARGO_MANIFEST_DIR for the test binary, so the runner inherits it
+        let run = |script: &str, keep_env: Vec<glob::Pattern>| {
+            let mut executor = SolverExecutorBuilder::default()
+                .instance_path(instance_path.clone())
+                .working_dir(tempdir.path().to_path_buf())
+                .solver_path(PathBuf::from("/bin/sh"))
+                .args(vec!["-c".into(), script.into()])
+                .env(vec![("STRIDE_TEST".into(), "1".into())])
+                .timeout(Duration::from_secs(10))
+                .grace(Duration::from_secs(1))
+                .clean_env(true)
+                .keep_env(keep_env)
+                .build()
+                .unwrap();
+            async move { executor.run().await.unwrap() }
+        };
+
+        let keep = || vec![glob::Pattern::new("CARGO_MANIFEST_*").unwrap()];
+        assert!(
+            run("test -z \"$CARGO_MANIFEST_DIR\"", vec![])
+                .await
+                .is_success()
+        );
+        assert!(
+            run("test -n \"$CARGO_MANIFEST_DIR\"", keep())
+                .await
+                .is_success()
+        );
+        assert!(run("test \"$STRIDE_TEST\" = 1", vec![]).await.is_success());
+    }
+
+    #[test]
+    fn output_rate_monitor() {
+        let start = Instant::now();
+        let mut monitor = OutputRateMonitor::new(1000, start);
+
+        // the limit applies to the average over the window, not to single bursts
+        assert!(!monitor.record(1500, start));
+        assert!(!monitor.record(500, start + OUTPUT_RATE_WINDOW / 2));
+        assert!(monitor.record(1, start + OUTPUT_RATE_WINDOW / 2));
+
+        // a new window starts from scratch
+        assert!(!monitor.record(2000, start + OUTPUT_RATE_WINDOW));
+    }
+
+    #[tokio::test]
+    async fn output_overflow() {
+        let tempdir = TempDir::new("output_overflow").unwrap();
+        let instance_path = tempdir.path().join("empty.in");
+        std::fs::write(&instance_path, "").unwrap();
+
+        let mut executor = SolverExecutorBuilder::default()
+            .instance_path(instance_path)
+            .working_dir(tempdir.path().to_path_buf())
+            .solver_path(PathBuf::from("/bin/sh"))
+            .args(vec!["-c".into(), "exec yes".into()])
+            .env(Vec::new())
+            .timeout(Duration::from_secs(10))
+            .grace(Duration::from_secs(1))
+            .max_output_rate(Some(1 << 20))
+            .build()
+            .unwrap();
+
+        let status = executor.run().await.unwrap();
+        assert_eq!(status, ChildExitStatus::OutputOverflow);
+    }
 
-        Ok(ChildExitStatus::Timeout)
+    #[tokio::test]
+    async fn early_output() {
+        assert!(!early_output_of("cat > /dev/null; echo done").await);
+        assert!(early_output_of("echo done; cat > /dev/null").await);
     }
 }
@@ -1,8 +1,11 @@
 use chrono::NaiveDateTime;
+use std::borrow::Cow;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+use crate::commands::run::instances::fnv1a_hash;
 
 #[derive(Error, Debug)]
 pub enum CreateInstanceDirError {
@@ -21,21 +24,36 @@ const LOG_PARENT_DIR: &str = "stride-logs";
 const TASKS_DIR: &str = "tasks";
 const LOG_LATEST_LINK: &str = "latest";
 
+/// Counter of the runs created within the log dir; it orders runs independently of the clock
+const RUN_COUNTER_FILE: &str = ".run_counter";
+/// Sequence number of a run (taken from [`RUN_COUNTER_FILE`]) stored in its run directory
+const RUN_SEQ_FILE: &str = ".run_seq";
+
+// most filesystems limit path components to 255 bytes; we leave room for the `_{attempt}` suffix
+const MAX_INSTANCE_DIR_NAME_LEN: usize = 224;
+const TRUNCATED_INSTANCE_DIR_NAME_LEN: usize = 200;
+
 const RUN_DIR_FORMAT_SHORT: &str = "run_%y%m%d_%H%M%S"; // used only for first attempt
 const RUN_DIR_FORMAT_LONG: &str = "run_%y%m%d_%H%M%S%.6f";
 
 impl RunDirectory {
-    pub fn new() -> Result<Self, std::io::Error> {
-        Self::new_within(Path::new(LOG_PARENT_DIR))
+    pub fn new(label: Option<&str>) -> Result<Self, std::io::Error> {
+        Self::new_within(Path::new(LOG_PARENT_DIR), label)
     }
 
-    pub fn new_within(parent: &Path) -> Result<Self, std::io::Error> {
+    /// Creates a uniquely timestamped run directory within `parent`. If a `label` is provided,
+    /// it is appended to the directory name and an additional `latest-{label}` symlink is maintained.
+    pub fn new_within(parent: &Path, label: Option<&str>) -> Result<Self, std::io::Error> {
         fs::create_dir_all(parent)?;
 
         // we create a uniquely timestamped run directory
         let mut format = RUN_DIR_FORMAT_SHORT;
         let path = loop {
-            let prefix: String = chrono::Local::now().format(format).to_string();
+            let mut prefix: String = chrono::Local::now().format(format).to_string();
+            if let Some(label) = label {
+                prefix.push('_');
+                prefix.push_str(label);
+            }
             let path = parent.join(prefix);
             format = RUN_DIR_FORMAT_LONG;
 
@@ -49,36 +67,35 @@ impl RunDirectory {
 
         std::fs::create_dir(path.join(TASKS_DIR))?;
 
-        // now, create or update the "latest" symlink to point to the new log directory
-        let latest_path = parent.join(LOG_LATEST_LINK);
-        loop {
-            match std::os::unix::fs::symlink(path.file_name().unwrap(), &latest_path) {
-                Ok(()) => break,
-
-                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        let seq = next_run_seq(parent)?;
+        fs::write(path.join(RUN_SEQ_FILE), seq.to_string())?;
 
-                Err(e) => return Err(e),
-            }
-
-            // if the symlink already existed, only replace it if the symlink target is older
-            // (i.e., avoid races here)
-            let old_target = latest_path.read_link()?;
-            if old_target.file_name() < path.file_name() {
-                std::fs::remove_file(&latest_path)?;
-            } else {
-                break;
-            }
+        // now, create or update the "latest" symlinks to point to the new log directory
+        update_latest_link(&parent.join(LOG_LATEST_LINK), &path)?;
+        if let Some(label) = label {
+            update_latest_link(&parent.join(format!("{LOG_LATEST_LINK}-{label}")), &path)?;
         }
 
         Ok(Self { path })
     }
 
+    /// Returns the most recent run directory (of runs labeled `label`, if given), if any
+    pub fn latest(label: Option<&str>) -> Option<PathBuf> {
+        let parent = Path::new(LOG_PARENT_DIR);
+        let link = match label {
+            Some(label) => parent.join(format!("{LOG_LATEST_LINK}-{label}")),
+            None => parent.join(LOG_LATEST_LINK),
+        };
+        Some(parent.join(link.read_link().ok()?))
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
 
     /// Create a subdirectory for the given instance name.
     /// If the directory already exists, appends a suffix to make it unique.
+    /// Overly long names are truncated (see [`instance_dir_name`]).
     pub fn create_instance_dir(
         &self,
         parent: &Path,
@@ -87,10 +104,11 @@ impl RunDirectory {
         if instance_name.is_empty() {
             return Err(CreateInstanceDirError::EmptyInstanceName);
         }
+        let instance_name = instance_dir_name(instance_name);
 
         for attempt in 0.. {
             let dir = if attempt == 0 {
-                parent.join(instance_name)
+                parent.join(instance_name.as_ref())
             } else {
                 parent.join(format!("{}_{}", instance_name, attempt))
             };
@@ -126,12 +144,9 @@ impl RunDirectory {
             debug!(
                 "Consider {}, {:?}",
                 name,
-                NaiveDateTime::parse_from_str(&name, RUN_DIR_FORMAT_SHORT)
+                NaiveDateTime::parse_and_remainder(&name, RUN_DIR_FORMAT_SHORT)
             );
-            if NaiveDateTime::parse_from_str(&name, RUN_DIR_FORMAT_SHORT)
-                .or(NaiveDateTime::parse_from_str(&name, RUN_DIR_FORMAT_LONG))
-                .is_err()
-            {
+            if !is_run_dir_name(&name) {
                 continue;
             }
             logs.push(path);
@@ -143,7 +158,8 @@ impl RunDirectory {
             return Ok(());
         }
 
-        logs.sort_unstable();
+        // names are ordered by the clock, which may have jumped backwards between runs
+        logs.sort_by_cached_key(|path| (run_seq(path), path.clone()));
 
         for log in logs.into_iter().take(num_logs - num_keep) {
             info!("Remove old run log {}", log.display());
@@ -154,6 +170,99 @@ impl RunDirectory {
     }
 }
 
+/// Create or update the symlink `link` to point to the run directory `target`
+fn update_latest_link(link: &Path, target: &Path) -> Result<(), std::io::Error> {
+    loop {
+        match std::os::unix::fs::symlink(target.file_name().unwrap(), link) {
+            Ok(()) => break,
+
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+
+            Err(e) => return Err(e),
+        }
+
+        // if the symlink already existed, only replace it if the symlink target is older
+        // (i.e., avoid races here); we order by sequence number as names depend on the clock
+        let old_target = link.read_link()?;
+        let parent = link.parent().unwrap();
+        if run_seq(&parent.join(&old_target)) < run_seq(target) {
+            if old_target.file_name() > target.file_name() {
+                warn!(
+                    "Run directory {:?} is named older than its predecessor {old_target:?}; did the system clock jump backwards?",
+                    target.file_name().unwrap()
+                );
+            }
+            std::fs::remove_file(link)?;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Increments the run counter of the log dir `parent` and returns the new value; the counter
+/// file is locked, so concurrent runs obtain distinct numbers
+fn next_run_seq(parent: &Path) -> Result<u64, std::io::Error> {
+    use std::io::{Read, Seek, Write};
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(parent.join(RUN_COUNTER_FILE))?;
+    file.lock()?;
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    let seq = content.trim().parse::<u64>().unwrap_or(0) + 1;
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{seq}")?;
+
+    Ok(seq)
+}
+
+/// Returns the sequence number of the run directory `path`; runs predating the counter yield 0
+fn run_seq(path: &Path) -> u64 {
+    fs::read_to_string(path.join(RUN_SEQ_FILE))
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Returns `name` if it fits into a path component; otherwise, a prefix of `name` followed
+/// by a hash of the full name (to keep distinct long names apart)
+fn instance_dir_name(name: &str) -> Cow<'_, str> {
+    if name.len() <= MAX_INSTANCE_DIR_NAME_LEN {
+        return Cow::Borrowed(name);
+    }
+
+    let mut prefix_len = TRUNCATED_INSTANCE_DIR_NAME_LEN;
+    while !name.is_char_boundary(prefix_len) {
+        prefix_len -= 1;
+    }
+
+    Cow::Owned(format!(
+        "{}_{:016x}",
+        &name[..prefix_len],
+        fnv1a_hash(name.as_bytes())
+    ))
+}
+
+/// Returns `true` iff `name` matches `run_{TIMESTAMP}` optionally followed by
+/// sub-second digits and/or a `_{LABEL}` suffix
+fn is_run_dir_name(name: &str) -> bool {
+    match NaiveDateTime::parse_and_remainder(name, RUN_DIR_FORMAT_SHORT) {
+        Ok((_, remainder)) => {
+            remainder.is_empty() || remainder.starts_with('.') || remainder.starts_with('_')
+        }
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use tempdir::TempDir;
@@ -167,7 +276,7 @@ mod tests {
 
         // first run
         {
-            let log_dir = RunDirectory::new_within(parent).unwrap();
+            let log_dir = RunDirectory::new_within(parent, None).unwrap();
             assert!(log_dir.path().exists());
 
             std::fs::write(log_dir.path().join("test"), "test").unwrap();
@@ -179,7 +288,7 @@ mod tests {
 
         // second run
         {
-            let log_dir = RunDirectory::new_within(parent).unwrap();
+            let log_dir = RunDirectory::new_within(parent, None).unwrap();
             assert!(log_dir.path().exists());
 
             std::fs::write(log_dir.path().join("test"), "test").unwrap();
@@ -191,10 +300,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_clock_skew() {
+        let parent_dir = TempDir::new("logdir_test").unwrap();
+        let parent = parent_dir.path();
+
+        // simulate a first run while the clock was far ahead
+        let first = RunDirectory::new_within(parent, None).unwrap();
+        let skewed = parent.join("run_991231_235959");
+        std::fs::rename(first.path(), &skewed).unwrap();
+        std::fs::remove_file(parent.join(LOG_LATEST_LINK)).unwrap();
+        std::os::unix::fs::symlink(skewed.file_name().unwrap(), parent.join(LOG_LATEST_LINK))
+            .unwrap();
+
+        // the second run gets an older-looking name, but is still the latest one
+        let second = RunDirectory::new_within(parent, None).unwrap();
+        assert!(second.path().file_name() < skewed.file_name());
+        let link_target = parent.join(LOG_LATEST_LINK).read_link().unwrap();
+        assert_eq!(link_target, second.path().file_name().unwrap());
+
+        second.remove_old_run_logs_only_keep(1).unwrap();
+        assert!(second.path().exists());
+        assert!(!skewed.exists());
+    }
+
+    #[test]
+    fn test_labeled_log_directory_creation() {
+        let parent_dir = TempDir::new("logdir_test").unwrap();
+        let parent = parent_dir.path();
+
+        let log_dir = RunDirectory::new_within(parent, Some("exp1")).unwrap();
+        let dir_name = log_dir.path().file_name().unwrap().to_str().unwrap();
+        assert!(dir_name.ends_with("_exp1"), "{dir_name}");
+        assert!(is_run_dir_name(dir_name));
+
+        for link in [LOG_LATEST_LINK, "latest-exp1"] {
+            let link_target = parent.join(link).read_link().unwrap();
+            assert_eq!(link_target, log_dir.path().file_name().unwrap());
+        }
+    }
+
     #[test]
     fn test_instance_dir_creation() {
         let parent_dir = TempDir::new("logdir_test").unwrap();
-        let log_dir = RunDirectory::new_within(parent_dir.path()).unwrap();
+        let log_dir = RunDirectory::new_within(parent_dir.path(), None).unwrap();
         let instance_name = "instance1";
 
         // first job
@@ -212,4 +361,31 @@ mod tests {
         // make sure the two directories are different
         assert_ne!(dir1, dir2);
     }
+
+    #[test]
+    fn test_long_instance_dir_name() {
+        let parent_dir = TempDir::new("logdir_test").unwrap();
+        let log_dir = RunDirectory::new_within(parent_dir.path(), None).unwrap();
+
+        // multi-byte characters ensure that we truncate at a char boundary
+        let long_a = "ä".repeat(1000);
+        let long_b = format!("{long_a}b");
+
+        let dir_a = log_dir
+            .create_instance_dir(log_dir.path(), &long_a)
+            .unwrap();
+        let dir_a2 = log_dir
+            .create_instance_dir(log_dir.path(), &long_a)
+            .unwrap();
+        let dir_b = log_dir
+            .create_instance_dir(log_dir.path(), &long_b)
+            .unwrap();
+
+        for dir in [&dir_a, &dir_a2, &dir_b] {
+            assert!(dir.exists());
+            assert!(dir.file_name().unwrap().len() <= 255);
+        }
+        assert_ne!(dir_a, dir_a2);
+        assert_ne!(dir_a, dir_b);
+    }
 }
@@ -183,3 +183,98 @@ fn digest() {
         }
     }
 }
+
+#[test]
+fn solution_ext() {
+    let tempdir = tempdir::TempDir::new("solution_ext").unwrap();
+
+    let testcases_path = testcase_dir().join("valid_solutions").join("*.in");
+    let input_path = glob::glob(testcases_path.as_os_str().to_str().unwrap())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    let instance_path = tempdir.path().join("instance.graph");
+    std::fs::copy(&input_path, &instance_path).unwrap();
+    std::fs::copy(
+        input_path.with_extension("out"),
+        tempdir.path().join("instance.sol"),
+    )
+    .unwrap();
+
+    let output = command()
+        .arg("check")
+        .arg("--solution-ext")
+        .arg("sol")
+        .arg(instance_path)
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("#s solution_size"));
+}
+
+#[test]
+fn check_directory() {
+    let output = command()
+        .arg("check")
+        .arg(testcase_dir().join("valid_solutions"))
+        .output()
+        .expect("failed to run binary");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(" 0 failed"), "{stdout}");
+
+    let output = command()
+        .arg("check")
+        .arg(testcase_dir().join("invalid_solutions"))
+        .output()
+        .expect("failed to run binary");
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("FAILED"), "{stdout}");
+    assert!(stdout.contains(" 0 passed"), "{stdout}");
+}
+
+#[test]
+fn verify_idigest() {
+    let tempdir = tempdir::TempDir::new("verify_idigest").unwrap();
+    let source = glob::glob(
+        testcase_dir()
+            .join("valid_solutions/*.in")
+            .to_str()
+            .unwrap(),
+    )
+    .unwrap()
+    .next()
+    .unwrap()
+    .unwrap();
+    let content = std::fs::read_to_string(source).unwrap();
+
+    // without an idigest line, there is nothing to verify
+    let plain = tempdir.path().join("plain.in");
+    std::fs::write(&plain, &content).unwrap();
+    let output = command()
+        .args(["check", "--verify-idigest"])
+        .arg(&plain)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stale = tempdir.path().join("stale.in");
+    std::fs::write(
+        &stale,
+        format!("#s idigest \"00000000000000000000000000000000\"\n{content}"),
+    )
+    .unwrap();
+    let output = command()
+        .args(["check", "--verify-idigest"])
+        .arg(&stale)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("hashes to"));
+}
@@ -8,7 +8,7 @@ use std::{
 };
 use std::{
     path::Path,
-    process::{Command, Stdio},
+    process::{Command, ExitStatus, Stdio},
 };
 use tempdir::TempDir;
 
@@ -51,6 +51,80 @@ fn no_env() {
     }
 }
 
+#[test]
+fn custom_envs() {
+    let tempdir = TempDir::new("custom_envs_test").unwrap();
+
+    let list_path = test_testcases_dir()
+        .join("test_solver_valid/report_envs.in")
+        .canonicalize()
+        .unwrap();
+
+    run_stride(
+        tempdir.path(),
+        list_path,
+        Some(vec![
+            "--env-prefix".into(),
+            "MY_".into(),
+            "--solver-env".into(),
+            "BUDGET={timeout}s".into(),
+        ]),
+    );
+    let lines = read_summary(&tempdir.path().join("stride-logs/latest/summary.json"));
+
+    {
+        let envs = lines
+            .get("report_envs")
+            .unwrap()
+            .get("envs")
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        assert!(!envs.contains_key("STRIDE_TIMEOUT"));
+        assert!(envs.contains_key("MY_INSTANCE_PATH"));
+        assert!(envs.contains_key("MY_TIMEOUT"));
+        assert!(envs.contains_key("MY_GRACE"));
+        assert_eq!(envs.get("BUDGET").unwrap().as_str().unwrap(), "2s");
+    }
+}
+
+#[test]
+fn deterministic_envs() {
+    let tempdir = TempDir::new("deterministic_envs_test").unwrap();
+
+    let list_path = test_testcases_dir()
+        .join("test_solver_valid/report_envs.in")
+        .canonicalize()
+        .unwrap();
+
+    run_stride(
+        tempdir.path(),
+        list_path,
+        Some(vec![
+            "--deterministic-env".into(),
+            "--deterministic-env-clear".into(),
+            "HOME".into(),
+        ]),
+    );
+    let lines = read_summary(&tempdir.path().join("stride-logs/latest/summary.json"));
+
+    {
+        let envs = lines
+            .get("report_envs")
+            .unwrap()
+            .get("envs")
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        assert_eq!(envs.get("LC_ALL").unwrap().as_str().unwrap(), "C");
+        assert_eq!(envs.get("TZ").unwrap().as_str().unwrap(), "UTC");
+        assert!(!envs.contains_key("HOME"));
+        assert!(envs.contains_key("STRIDE_TIMEOUT"));
+    }
+}
+
 #[test]
 fn no_profiler() {
     let tempdir = TempDir::new("no_profiler_test").unwrap();
@@ -96,6 +170,87 @@ fn multiple_instances() {
     assert_eq!(lines.len(), 2);
 }
 
+#[test]
+fn limit_instances() {
+    let instance_dir = test_testcases_dir()
+        .join("test_solver_valid")
+        .canonicalize()
+        .unwrap();
+    let path2 = instance_dir.join("with_info.in").display().to_string();
+
+    for (limit, expected) in [(1, 1), (5, 2)] {
+        let tempdir = TempDir::new("limit_instances_test").unwrap();
+        run_stride(
+            tempdir.path(),
+            instance_dir.join("valid.in"),
+            Some(vec![path2.clone(), "--limit".into(), limit.to_string()]),
+        );
+        let lines = read_summary(&tempdir.path().join("stride-logs/latest/summary.json"));
+        assert_eq!(lines.len(), expected);
+    }
+}
+
+#[test]
+fn print_completions() {
+    let instance = test_testcases_dir()
+        .join("test_solver_valid/valid.in")
+        .canonicalize()
+        .unwrap();
+
+    let stderr = |extra_args: &[&str]| {
+        let tempdir = TempDir::new("print_completions_test").unwrap();
+        let output = Command::new(test_stride_path())
+            .current_dir(tempdir.path())
+            .args(["run", "-t", "2", "-g", "1", "--solver"])
+            .arg(test_solver_path())
+            .arg("-i")
+            .arg(&instance)
+            .args(extra_args)
+            .args(["--", "-f"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    // stderr is no terminal here, but completions are only printed on request
+    assert!(!stderr(&[]).contains("score="));
+
+    let printed = stderr(&["--print-completions"]);
+    let line = printed
+        .lines()
+        .find(|line| line.contains(" valid score="))
+        .unwrap_or_else(|| panic!("no completion line in {printed:?}"));
+    assert!(line.contains("Valid"), "{line}");
+}
+
+#[test]
+fn run_fingerprint() {
+    let instance = test_testcases_dir()
+        .join("test_solver_valid/valid.in")
+        .canonicalize()
+        .unwrap();
+
+    let fingerprint = |args: Vec<String>| {
+        let tempdir = TempDir::new("run_fingerprint_test").unwrap();
+        run_stride(tempdir.path(), instance.clone(), Some(args));
+        let report: Value = serde_json::from_str(
+            &std::fs::read_to_string(tempdir.path().join("stride-logs/latest/report.json"))
+                .unwrap(),
+        )
+        .unwrap();
+        report["run_fingerprint"].as_str().map(String::from)
+    };
+
+    let first = fingerprint(vec!["-O".into()]).unwrap();
+    assert_eq!(first.len(), 64);
+    assert_eq!(fingerprint(vec!["-O".into()]).unwrap(), first);
+    assert_eq!(
+        fingerprint(vec!["-O".into(), "--no-fingerprint".into()]),
+        None
+    );
+}
+
 #[test]
 fn summary() {
     let tempdir = TempDir::new("summary_test").unwrap();
@@ -140,6 +295,63 @@ fn summary() {
     }
 }
 
+#[test]
+fn report() {
+    let tempdir = TempDir::new("report_test").unwrap();
+
+    let list_path = test_testcases_dir()
+        .join("test_summary.lst")
+        .canonicalize()
+        .unwrap();
+
+    run_stride(tempdir.path(), list_path, None);
+
+    let report: Value = serde_json::from_str(
+        &std::fs::read_to_string(tempdir.path().join("stride-logs/latest/report.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(report["num_jobs"].as_u64(), Some(14));
+    assert_eq!(report["results"]["Timeout"].as_u64(), Some(1));
+}
+
+#[test]
+fn solver_args_from_env() {
+    let tempdir = TempDir::new("solver_args_env").unwrap();
+
+    let instance = test_testcases_dir()
+        .join("test_solver_valid/with_info.in")
+        .canonicalize()
+        .unwrap();
+
+    // no arguments after `--`, so `-f` has to be taken from STRIDE_SOLVER_ARGS
+    let status = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .env("STRIDE_SOLVER_ARGS", "'-f'")
+        .arg("run")
+        .arg("--solver")
+        .arg(test_solver_path())
+        .arg("-O")
+        .arg("-i")
+        .arg(instance)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let lines = read_summary(&tempdir.path().join("stride-logs/latest/summary.json"));
+    assert_eq!(
+        lines
+            .get("with_info")
+            .unwrap()
+            .get("s_result")
+            .unwrap()
+            .as_str()
+            .unwrap(),
+        "Valid"
+    );
+}
+
 #[test]
 fn relative_solver_path() {
     let tempdir = TempDir::new("relative_solver").unwrap();
@@ -171,6 +383,375 @@ fn relative_solver_path() {
     assert!(stdout.contains("'./'") || stderr.contains("'./'"));
 }
 
+#[test]
+fn fail_on() {
+    let instance = test_testcases_dir()
+        .join("test_solver_errors/timeout.in")
+        .canonicalize()
+        .unwrap();
+
+    for (fail_on, expected) in [("infeasible", 0), ("suboptimal,timeout", 5)] {
+        let tempdir = TempDir::new("fail_on_test").unwrap();
+        let status = run_stride_status(
+            tempdir.path(),
+            instance.clone(),
+            Some(vec!["-O".into(), "--fail-on".into(), fail_on.into()]),
+        );
+        assert_eq!(status.code(), Some(expected), "--fail-on {fail_on}");
+    }
+}
+
+#[test]
+fn group_instance_lists() {
+    let tempdir = TempDir::new("group_lists_test").unwrap();
+
+    let instance = test_testcases_dir()
+        .join("test_solver_errors/timeout.in")
+        .canonicalize()
+        .unwrap();
+
+    run_stride(tempdir.path(), instance.clone(), Some(vec!["-O".into()]));
+
+    let list = std::fs::read_to_string(
+        tempdir
+            .path()
+            .join("stride-logs/latest/timeout/instances.lst"),
+    )
+    .unwrap();
+    assert_eq!(
+        list.lines().collect::<Vec<_>>(),
+        [instance.to_str().unwrap()]
+    );
+}
+
+#[test]
+fn check_optimal() {
+    let valid =
+        std::fs::read_to_string(test_testcases_dir().join("test_solver_valid/valid.in")).unwrap();
+
+    // the test solver yields a solution of size 2
+    for (optimal, expected_code, is_optimal) in [(2, 0, true), (1, 1, false), (3, 0, true)] {
+        let tempdir = TempDir::new("check_optimal_test").unwrap();
+        let instance = tempdir.path().join("optimal.in");
+        std::fs::write(&instance, format!("#s optimal {optimal}\n{valid}")).unwrap();
+
+        let status = run_stride_status(
+            tempdir.path(),
+            instance,
+            Some(vec!["-O".into(), "--check-optimal".into()]),
+        );
+        assert_eq!(status.code(), Some(expected_code), "optimal {optimal}");
+
+        let lines = read_summary(&tempdir.path().join("stride-logs/latest/summary.json"));
+        let line = lines.get("optimal").unwrap();
+        assert_eq!(line.get("s_is_optimal"), Some(&Value::Bool(is_optimal)));
+
+        // a solution better than the optimum is flagged
+        let beats_optimum = line.get("s_beats_optimum").and_then(Value::as_bool);
+        assert_eq!(
+            beats_optimum,
+            (optimal > 2).then_some(true),
+            "optimal {optimal}"
+        );
+    }
+}
+
+#[test]
+fn stall_timeout() {
+    let instance = test_testcases_dir()
+        .join("test_solver_errors/timeout.in")
+        .canonicalize()
+        .unwrap();
+
+    // a timeout alone does not fail the run ...
+    let tempdir = TempDir::new("stall_timeout_test").unwrap();
+    let status = run_stride_status(tempdir.path(), instance.clone(), None);
+    assert_eq!(status.code(), Some(0));
+
+    // ... but no completion within the stall timeout (shorter than timeout + grace) does
+    let tempdir = TempDir::new("stall_timeout_test").unwrap();
+    let status = run_stride_status(
+        tempdir.path(),
+        instance,
+        Some(vec!["--stall-timeout".into(), "1".into()]),
+    );
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn status_on_sigusr1() {
+    let tempdir = TempDir::new("status_test").unwrap();
+    let instance = test_testcases_dir()
+        .join("test_solver_errors/timeout.in")
+        .canonicalize()
+        .unwrap();
+
+    let mut child = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "-O", "-t", "3", "-g", "1", "--solver"])
+        .arg(test_solver_path())
+        .arg("-i")
+        .arg(instance)
+        .args(["--", "-f"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // give the runner time to install its handler and start the solver
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGUSR1);
+    }
+    assert!(child.wait().unwrap().success());
+
+    let log =
+        std::fs::read_to_string(tempdir.path().join("stride-logs/latest/messages.log")).unwrap();
+    assert!(log.contains("Status: 1 running instances"), "{log}");
+    assert!(log.contains("Status:   timeout running for"), "{log}");
+}
+
+#[test]
+fn capture_cores_with_relative_instance() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tempdir = TempDir::new("capture_cores_test").unwrap();
+
+    // the solver runs in its task folder, but has to find the instance given relative to ours
+    let solver = tempdir.path().join("solver.sh");
+    std::fs::write(
+        &solver,
+        format!(
+            "#!/bin/sh\ncat > /dev/null\ntest -f \"$INSTANCE\" || exit 1\nexec {} -f < \"$STRIDE_INSTANCE_PATH\"\n",
+            test_solver_path().display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&solver, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let valid = test_testcases_dir().join("test_solver_valid/valid.in");
+    std::fs::copy(&valid, tempdir.path().join("valid.in")).unwrap();
+
+    let output = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "-O", "--print-completions", "--capture-cores"])
+        .args(["--solver-env", "INSTANCE={instance}"])
+        .args(["--solver", "./solver.sh", "-i", "valid.in"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let line = stderr
+        .lines()
+        .find(|line| line.contains(" valid score="))
+        .unwrap_or_else(|| panic!("no completion line in {stderr:?}"));
+    assert!(line.contains("Valid"), "{line}");
+}
+
+#[test]
+fn keep_if_stderr() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tempdir = TempDir::new("keep_if_stderr_test").unwrap();
+
+    // writes no solution, but complains on stderr for the instance `noisy`
+    let solver = tempdir.path().join("solver.sh");
+    std::fs::write(
+        &solver,
+        "#!/bin/sh\ncat > /dev/null\ncase \"$STRIDE_INSTANCE_PATH\" in *noisy*) echo oops >&2;; esac\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&solver, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let valid = test_testcases_dir().join("test_solver_valid/valid.in");
+    for name in ["noisy.in", "quiet.in"] {
+        std::fs::copy(&valid, tempdir.path().join(name)).unwrap();
+    }
+
+    let status = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "-O", "--keep-if-stderr", "--solver", "./solver.sh"])
+        .args(["-i", "noisy.in", "quiet.in"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let kept = tempdir.path().join("stride-logs/latest/emptysolution");
+    assert!(kept.join("noisy").is_dir());
+    assert!(!kept.join("quiet").exists());
+}
+
+#[test]
+fn export_dot() {
+    let tempdir = TempDir::new("export_dot_test").unwrap();
+
+    let instance = test_testcases_dir()
+        .join("test_solver_valid/valid.in")
+        .canonicalize()
+        .unwrap();
+
+    let status = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "-O", "--export-dot", "--solver"])
+        .arg(test_solver_path())
+        .arg("-i")
+        .arg(&instance)
+        .args(["--", "-f"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // the folder of a valid solution is kept only because of the dot export
+    let dot_path = tempdir
+        .path()
+        .join("stride-logs/latest/valid/valid/solution.dot");
+    let dot = std::fs::read_to_string(&dot_path).unwrap();
+    assert!(dot.contains("graph"), "{dot}");
+}
+
+#[test]
+fn shell_command() {
+    let tempdir = TempDir::new("shell_command_test").unwrap();
+
+    let instance = test_testcases_dir()
+        .join("test_solver_valid/valid.in")
+        .canonicalize()
+        .unwrap();
+
+    // the instance reaches the solver through the pipeline on stdin
+    let command = format!("cat | {} -f", test_solver_path().display());
+    let output = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "-O", "--print-completions", "-i"])
+        .arg(&instance)
+        .args(["--shell-command", command.as_str()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let line = stderr
+        .lines()
+        .find(|line| line.contains(" valid score="))
+        .unwrap_or_else(|| panic!("no completion line in {stderr:?}"));
+    assert!(line.contains("Valid"), "{line}");
+
+    // a shell command replaces the solver
+    let status = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "--solver", "./solver", "-i"])
+        .arg(&instance)
+        .args(["--shell-command", "true"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn filter_instances() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tempdir = TempDir::new("filter_instances_test").unwrap();
+
+    let solver = tempdir.path().join("solver.sh");
+    std::fs::write(&solver, "#!/bin/sh\ncat > /dev/null\n").unwrap();
+    std::fs::set_permissions(&solver, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let valid = test_testcases_dir().join("test_solver_valid/valid.in");
+    std::fs::create_dir(tempdir.path().join("sub")).unwrap();
+    for name in ["alpha.in", "beta.in", "sub/gamma.in"] {
+        std::fs::copy(&valid, tempdir.path().join(name)).unwrap();
+    }
+
+    let completed = |filter: &[&str]| {
+        let output = Command::new(test_stride_path())
+            .current_dir(tempdir.path())
+            .args(["run", "-O", "--print-completions"])
+            .args(["--solver", "./solver.sh"])
+            .args(["-i", "alpha.in", "beta.in", "sub/gamma.in"])
+            .args(filter)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        ["alpha", "beta", "gamma"]
+            .into_iter()
+            .filter(|name| stderr.contains(&format!(" {name} score=")))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(completed(&[]), ["alpha", "beta", "gamma"]);
+    assert_eq!(
+        completed(&["--filter-name", "^(alpha|gamma)$"]),
+        ["alpha", "gamma"]
+    );
+    assert_eq!(completed(&["--filter-path", "sub/"]), ["gamma"]);
+    assert_eq!(
+        completed(&["--filter-name", "a$", "--filter-path", "sub/"]),
+        ["gamma"]
+    );
+}
+
+#[test]
+fn interrupt_terminates_solvers() {
+    let tempdir = TempDir::new("interrupt_test").unwrap();
+
+    let valid = test_testcases_dir().join("test_solver_valid/valid.in");
+    for name in ["a.in", "b.in"] {
+        std::fs::copy(&valid, tempdir.path().join(name)).unwrap();
+    }
+
+    // each solver records its pid (kept by `exec`) and then blocks far beyond the test
+    let pids_path = tempdir.path().join("pids");
+    let mut child = Command::new(test_stride_path())
+        .current_dir(tempdir.path())
+        .args(["run", "-O", "--parallel", "2", "-t", "60", "-g", "1"])
+        .arg("--shell-command")
+        .arg(format!("echo $$ >> {}; exec sleep 60", pids_path.display()))
+        .args(["-i", "a.in", "b.in"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let read_pids = || -> Vec<libc::pid_t> {
+        std::fs::read_to_string(&pids_path)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.trim().parse().unwrap())
+            .collect()
+    };
+
+    let start = std::time::Instant::now();
+    while read_pids().len() < 2 {
+        assert!(start.elapsed().as_secs() < 30, "solvers did not start");
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(128 + libc::SIGTERM));
+
+    for pid in read_pids() {
+        let alive = unsafe { libc::kill(pid, 0) } == 0;
+        assert!(!alive, "solver {pid} survived the runner");
+    }
+
+    // the interrupted instances are still recorded
+    let lines = read_summary(&tempdir.path().join("stride-logs/latest/summary.json"));
+    assert_eq!(lines.len(), 2);
+}
+
 fn assert_results(lines: &HashMap<String, Map<String, Value>>) {
     for (name, expected) in [
         ("syntaxerror", "SyntaxError"),
@@ -207,10 +788,23 @@ fn assert_results(lines: &HashMap<String, Map<String, Value>>) {
             assert!(line.contains_key("s_nvcsw"));
             assert!(line.contains_key("s_nivcsw"));
         }
+
+        if expected == "Timeout" {
+            assert_eq!(line.get("s_timeout_kind").unwrap().as_str(), Some("wall"));
+        }
     }
 }
 
 fn run_stride(tempdir: &Path, list_path: PathBuf, stride_args: Option<Vec<String>>) {
+    let result = run_stride_status(tempdir, list_path, stride_args);
+    assert!(result.success());
+}
+
+fn run_stride_status(
+    tempdir: &Path,
+    list_path: PathBuf,
+    stride_args: Option<Vec<String>>,
+) -> ExitStatus {
     let mut command = Command::new(test_stride_path());
 
     command
@@ -235,9 +829,7 @@ fn run_stride(tempdir: &Path, list_path: PathBuf, stride_args: Option<Vec<String
     command.stdout(Stdio::null()).stderr(Stdio::null());
 
     let mut child = command.spawn().unwrap();
-
-    let result = child.wait().unwrap();
-    assert!(result.success());
+    child.wait().unwrap()
 }
 
 fn read_summary(path: &Path) -> HashMap<String, Map<String, Value>> {
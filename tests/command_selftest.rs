@@ -0,0 +1,15 @@
+use std::process::{Command, Stdio};
+
+#[test]
+fn selftest_passes() {
+    let status = Command::new(env!("CARGO_BIN_EXE_stride"))
+        .arg("selftest")
+        .arg("--test-solver")
+        .arg(env!("CARGO_BIN_EXE_test_solver"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
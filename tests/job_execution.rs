@@ -3,11 +3,11 @@
 // fully build.
 
 use pace26stride::{
-    job::job_processor::{JobProcessorBuilder, JobProgress},
+    job::job_processor::{JobProcessorBuilder, JobProgress, JobResult, PATH_TRANSFORMED_STDOUT},
     run_directory::RunDirectory,
     test_helpers::*,
 };
-use std::{path::PathBuf, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use tempdir::TempDir;
 
 fn test_solver_path() -> PathBuf {
@@ -24,7 +24,7 @@ async fn test_solutions(key: &str, expected: ExpectedResult) {
     let instances = test_cases_glob(key);
 
     let tempdir = TempDir::new(key).unwrap();
-    let run_dir = RunDirectory::new_within(tempdir.path()).unwrap();
+    let run_dir = RunDirectory::new_within(tempdir.path(), None).unwrap();
 
     let mut handles = Vec::new();
     for instance_path in instances {
@@ -67,3 +67,132 @@ async fn test_valid_solutions() {
 async fn test_invalid_solutions() {
     test_solutions("invalid_solutions", ExpectedResult::FailRequired).await
 }
+
+#[tokio::test]
+async fn test_trust_solver() {
+    let tempdir = TempDir::new("trust_solver").unwrap();
+    let run_dir = RunDirectory::new_within(tempdir.path(), None).unwrap();
+
+    let run = |script: &str| {
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let job = JobProcessorBuilder::default()
+            .soft_timeout(Duration::from_secs(1))
+            .grace_period(Duration::from_secs(1))
+            .solver(PathBuf::from("/bin/sh"))
+            .solver_args(vec!["-c".into(), script.into()])
+            .work_dir(run_dir.create_task_dir_for(&instance_path).unwrap())
+            .instance_path(instance_path)
+            .trust_solver(true)
+            .build()
+            .unwrap();
+        async move { job.run().await }
+    };
+
+    let (result, infos) = run("echo '#s s_score 42'; echo '#s note \"fast\"'").await;
+    assert_eq!(result, JobResult::Unchecked { size: 42 });
+    assert_eq!(infos.unwrap().1, [("note".into(), "fast".into())]);
+
+    let (result, _) = run("echo '(1,2);'").await;
+    assert_eq!(result, JobResult::SyntaxError);
+}
+
+#[tokio::test]
+async fn test_solution_transform() {
+    let tempdir = TempDir::new("solution_transform").unwrap();
+    let run_dir = RunDirectory::new_within(tempdir.path(), None).unwrap();
+
+    let run = |transform: &str| {
+        let instance_path = test_cases_glob("valid_solutions").next().unwrap();
+        let work_dir = run_dir.create_task_dir_for(&instance_path).unwrap();
+        let job = JobProcessorBuilder::default()
+            .soft_timeout(Duration::from_secs(1))
+            .grace_period(Duration::from_secs(1))
+            .solver(test_solver_path())
+            .work_dir(work_dir.clone())
+            .instance_path(instance_path)
+            .set_stride_envs(true)
+            .solution_transform(Some(transform.into()))
+            .build()
+            .unwrap();
+        async move { (job.run().await, work_dir) }
+    };
+
+    let ((result, _), work_dir) = run("cat").await;
+    assert!(result.is_valid(), "{result:?}");
+    assert_eq!(
+        std::fs::read(work_dir.join("stdout")).unwrap(),
+        std::fs::read(work_dir.join(PATH_TRANSFORMED_STDOUT)).unwrap()
+    );
+
+    // the transformed output is checked, while the raw output is kept
+    let ((result, _), work_dir) = run("echo '(1,2);'").await;
+    assert!(!result.is_valid(), "{result:?}");
+    assert_ne!(
+        std::fs::read(work_dir.join("stdout")).unwrap(),
+        std::fs::read(work_dir.join(PATH_TRANSFORMED_STDOUT)).unwrap()
+    );
+
+    let ((result, infos), _) = run("exit 3").await;
+    assert_eq!(result, JobResult::SystemError);
+    assert!(
+        infos
+            .unwrap()
+            .1
+            .iter()
+            .any(|(key, _)| key == "s_transform_error")
+    );
+}
+
+#[tokio::test]
+async fn test_checker_io_retries() {
+    let tempdir = TempDir::new("checker_io_retries").unwrap();
+    let run_dir = RunDirectory::new_within(tempdir.path(), None).unwrap();
+    let source = test_cases_glob("valid_solutions").next().unwrap();
+
+    for (retries, expect_valid) in [(0, false), (3, true)] {
+        // the solver moves the instance away, so the checker fails to read it until it is back
+        let instance_path = tempdir.path().join(format!("instance{retries}.in"));
+        let moved_path = instance_path.with_extension("moved");
+        std::fs::copy(&source, &instance_path).unwrap();
+        let script = format!(
+            "cat > /dev/null; mv '{}' '{}'; cat '{}'",
+            instance_path.display(),
+            moved_path.display(),
+            source.with_extension("out").display()
+        );
+
+        let job = Arc::new(
+            JobProcessorBuilder::default()
+                .soft_timeout(Duration::from_secs(1))
+                .grace_period(Duration::from_secs(1))
+                .solver(PathBuf::from("/bin/sh"))
+                .solver_args(vec!["-c".into(), script])
+                .work_dir(run_dir.create_task_dir_for(&instance_path).unwrap())
+                .instance_path(instance_path.clone())
+                .checker_io_retries(retries)
+                .build()
+                .unwrap(),
+        );
+        let handle = tokio::spawn({
+            let job = job.clone();
+            async move { job.run().await }
+        });
+
+        // restore the instance shortly after the first check, but before the first retry
+        while !matches!(
+            job.progress(),
+            JobProgress::Checking | JobProgress::Finished
+        ) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        std::fs::rename(&moved_path, &instance_path).unwrap();
+
+        let (result, _) = handle.await.unwrap();
+        if expect_valid {
+            assert!(result.is_valid(), "{result:?}");
+        } else {
+            assert_eq!(result, JobResult::SystemError);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use pace26stride::{
-    job::job_processor::{JobProcessorBuilder, JobResult},
+    job::{
+        job_processor::{JobProcessorBuilder, JobResult},
+        solver_executor::PATH_STDOUT,
+    },
     run_directory::RunDirectory,
     test_helpers::*,
 };
@@ -16,12 +19,24 @@ fn test_stride_path() -> PathBuf {
 }
 
 async fn run(instance: PathBuf, profiler: bool) -> (JobResult, HashMap<String, Value>) {
+    let (job_result, infos, _) = run_customized(instance, profiler, |_| {}).await;
+    (job_result, infos)
+}
+
+/// Like `run`, but `customize` may override the settings of the job before it is built;
+/// additionally returns the job's runtime
+async fn run_customized(
+    instance: PathBuf,
+    profiler: bool,
+    customize: impl FnOnce(&mut JobProcessorBuilder),
+) -> (JobResult, HashMap<String, Value>, Option<Duration>) {
     let instance = test_testcases_dir().join(instance);
     let tempdir = TempDir::new("profile_test").unwrap();
-    let run_dir = RunDirectory::new_within(tempdir.path()).unwrap();
+    let run_dir = RunDirectory::new_within(tempdir.path(), None).unwrap();
     let work_dir = run_dir.create_task_dir_for(&instance).unwrap();
 
-    let job = JobProcessorBuilder::default()
+    let mut builder = JobProcessorBuilder::default();
+    builder
         .soft_timeout(Duration::from_secs_f64(1.5))
         .grace_period(Duration::from_secs_f64(1.5))
         .solver(test_solver_path())
@@ -29,9 +44,9 @@ async fn run(instance: PathBuf, profiler: bool) -> (JobResult, HashMap<String, V
         .work_dir(work_dir)
         .instance_path(instance)
         .profiler(profiler)
-        .profiler_executable(Some(test_stride_path()))
-        .build()
-        .unwrap();
+        .profiler_executable(Some(test_stride_path()));
+    customize(&mut builder);
+    let job = builder.build().unwrap();
 
     let (job_result, solution_infos) = job.run().await;
 
@@ -42,7 +57,7 @@ async fn run(instance: PathBuf, profiler: bool) -> (JobResult, HashMap<String, V
         }
     }
 
-    (job_result, infos)
+    (job_result, infos, job.runtime())
 }
 
 #[tokio::test]
@@ -57,6 +72,15 @@ async fn valid_with_profiler() {
     assert_eq!(result, JobResult::Valid { size: 2 });
 }
 
+#[tokio::test]
+async fn stdout_sha_independent_of_profiler() {
+    let (_, without) = run(PathBuf::from("test_solver_valid/valid.in"), false).await;
+    let (_, with) = run(PathBuf::from("test_solver_valid/valid.in"), true).await;
+    assert!(with.contains_key("s_wtime"));
+    assert_eq!(with.get("s_stdout_sha"), without.get("s_stdout_sha"));
+    assert!(with.get("s_stdout_sha").is_some());
+}
+
 #[tokio::test]
 async fn profile_time_shortwait() {
     // idle wait
@@ -91,3 +115,165 @@ async fn profile_maxrss() {
     // make sure it's atleast 30mb larger
     assert!(maxrss_before + 30_000_000 < maxrss_after);
 }
+
+#[tokio::test]
+async fn cpu_timeout() {
+    for profiler in [false, true] {
+        let (result, infos, runtime) = run_customized(
+            PathBuf::from("test_solver_errors/cpu_limit.in"),
+            profiler,
+            |builder| {
+                builder
+                    .soft_timeout(Duration::from_secs(8))
+                    .grace_period(Duration::from_secs(1))
+                    .cpu_timeout(Some(Duration::from_secs(1)));
+            },
+        )
+        .await;
+        assert_eq!(result, JobResult::Timeout, "profiler: {profiler}");
+
+        assert_eq!(infos.get("s_cpu_limited"), Some(&Value::Bool(true)));
+        assert_eq!(
+            infos.get("s_timeout_kind").and_then(Value::as_str),
+            Some("cpu")
+        );
+        assert!(runtime.unwrap() < Duration::from_secs(5));
+    }
+}
+
+#[tokio::test]
+async fn sigkill_without_cpu_limit() {
+    for profiler in [false, true] {
+        let (result, infos, _) = run_customized(
+            PathBuf::from("test_solver_errors/killed.in"),
+            profiler,
+            |builder| {
+                builder
+                    .soft_timeout(Duration::from_secs(8))
+                    .grace_period(Duration::from_secs(1))
+                    .cpu_timeout(Some(Duration::from_secs(1)));
+            },
+        )
+        .await;
+
+        // the solver is killed immediately, i.e., not due to its CPU time
+        assert_eq!(result, JobResult::SolverError, "profiler: {profiler}");
+        assert!(!infos.contains_key("s_cpu_limited"));
+        assert!(!infos.contains_key("s_timeout_kind"));
+    }
+}
+
+/// Runs the test solver on `instance` with a memory limit of `memory_limit_mb` MB
+async fn run_with_memory_limit(instance: &str, profiler: bool, memory_limit_mb: u64) -> JobResult {
+    let (result, _, _) = run_customized(PathBuf::from(instance), profiler, |builder| {
+        builder
+            .soft_timeout(Duration::from_secs(8))
+            .grace_period(Duration::from_secs(1))
+            .memory_limit(Some(memory_limit_mb * 1024 * 1024));
+    })
+    .await;
+    result
+}
+
+#[tokio::test]
+async fn memory_limit() {
+    for profiler in [false, true] {
+        let result =
+            run_with_memory_limit("test_solver_errors/memory_limit.in", profiler, 256).await;
+        assert_eq!(
+            result,
+            JobResult::MemoryLimitExceeded,
+            "profiler: {profiler}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn abort_below_memory_limit() {
+    for profiler in [false, true] {
+        // the solver aborts while using only a few MB
+        let result = run_with_memory_limit("test_solver_errors/abort.in", profiler, 256).await;
+        assert_eq!(result, JobResult::SolverError, "profiler: {profiler}");
+    }
+}
+
+#[tokio::test]
+async fn memory_limit_spares_profiler() {
+    // too small for the threads of the profiler, but enough for the test solver
+    let result = run_with_memory_limit("test_solver_valid/valid.in", true, 64).await;
+    assert_eq!(result, JobResult::Valid { size: 2 });
+}
+
+/// Returns `true` if the process exists and is no zombie
+fn is_alive(pid: u32) -> bool {
+    // the state follows the parenthesized command name
+    std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        !stat
+            .rsplit_once(") ")
+            .is_some_and(|(_, rest)| rest.starts_with('Z'))
+    })
+}
+
+/// Runs the test solver on `instance`, which spawns a long-sleeping helper, and returns the
+/// job's result and the pid of the helper
+async fn run_with_helper(instance: &str, profiler: bool) -> (JobResult, u32) {
+    let instance = test_testcases_dir().join(instance);
+    let tempdir = TempDir::new("helper_test").unwrap();
+    let run_dir = RunDirectory::new_within(tempdir.path(), None).unwrap();
+    let work_dir = run_dir.create_task_dir_for(&instance).unwrap();
+
+    let job = JobProcessorBuilder::default()
+        .soft_timeout(Duration::from_secs(1))
+        .grace_period(Duration::from_secs(1))
+        .solver(test_solver_path())
+        .solver_args(vec!["-f".into()])
+        .work_dir(work_dir.clone())
+        .instance_path(instance)
+        .profiler(profiler)
+        .profiler_executable(Some(test_stride_path()))
+        .build()
+        .unwrap();
+
+    let (result, _) = job.run().await;
+
+    let stdout = std::fs::read_to_string(work_dir.join(PATH_STDOUT)).unwrap();
+    let pid = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("#s s_helper_pid "))
+        .expect("helper pid reported")
+        .parse()
+        .unwrap();
+
+    (result, pid)
+}
+
+/// Returns `true` if the process terminated within a second
+async fn terminates(pid: u32) -> bool {
+    // the orphaned helper is reaped by init shortly after it was killed
+    let start = std::time::Instant::now();
+    while is_alive(pid) && start.elapsed() < Duration::from_secs(1) {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    !is_alive(pid)
+}
+
+#[tokio::test]
+async fn timeout_kills_helpers() {
+    for profiler in [false, true] {
+        let (result, pid) = run_with_helper("test_solver_errors/helper_timeout.in", profiler).await;
+        assert_eq!(result, JobResult::Timeout, "profiler: {profiler}");
+        assert!(terminates(pid).await, "profiler: {profiler}");
+    }
+}
+
+#[tokio::test]
+async fn exit_kills_helpers() {
+    for profiler in [false, true] {
+        let (result, pid) = run_with_helper("test_solver_valid/helper_exit.in", profiler).await;
+        assert!(
+            matches!(result, JobResult::Valid { .. }),
+            "profiler: {profiler}, result: {result:?}"
+        );
+        assert!(terminates(pid).await, "profiler: {profiler}");
+    }
+}